@@ -1,12 +1,40 @@
-use crate::data::{Database, Todo};
-use crate::ui::{DetailMode, DetailView, MainView, ConfirmDialog};
+use crate::data::{export, size_warning_due, Database, ListTab, Prefs, SortOrder, Todo, TodoFilter, LARGE_FILE_BYTES};
+use crate::ui::{parse_due_date, DetailMode, DetailView, MainView, ConfirmDialog, InputDialog};
 use anyhow::Result;
+use ratatui::widgets::TableState;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
 
-#[derive(Clone)]
+#[derive(Clone, Debug)]
 pub enum AppState {
     Main,
     Detail,
     Confirm,
+    Search,
+    Help,
+    ThemePreview,
+    Input,
+    RecentlyCompleted,
+}
+
+/// Action waiting for the user's answer in the confirm dialog.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ConfirmAction {
+    /// Delete the todos with these ids.
+    Delete(Vec<String>),
+    /// Delete every completed todo.
+    ClearCompleted,
+    /// Complete the todos with these ids.
+    CompleteAll(Vec<String>),
+    /// Quit, dropping unsaved edits in the detail view.
+    Quit,
+}
+
+/// What the text typed into the input dialog is used for.
+#[derive(Clone, Debug, PartialEq)]
+pub enum InputAction {
+    /// Due date (see `parse_due`, empty to clear) for the todos with these ids.
+    SetDueDate(Vec<String>),
 }
 
 pub struct App {
@@ -17,14 +45,105 @@ pub struct App {
     pub database: Database,
     pub should_quit: bool,
     pub current_todo_id: Option<String>,
-    pub pending_delete_id: Option<String>,
+    pub pending_action: Option<ConfirmAction>,
+    pub input_dialog: Option<InputDialog>,
+    pub pending_input: Option<InputAction>,
+    pub filter: TodoFilter,
+    /// Search text as typed; copied to `filter.query` by `apply_search_if_due`.
+    pub search_input: String,
+    pub search_debounce: SearchDebounce,
+    pub prefs: Prefs,
+    /// Selection and scroll position of each named list (`None` is the
+    /// all-lists view), restored when switching back to it.
+    pub list_states: HashMap<Option<String>, TableState>,
+    /// Short feedback shown in the footer, with the time it was set.
+    pub status_message: Option<(String, Instant)>,
+    /// Why the last prefs write failed, until reported (see
+    /// `report_save_failure`).
+    prefs_save_failure: Option<String>,
+    /// The status message reports a problem rather than a success.
+    pub status_is_error: bool,
+    /// Fold completed todos into a single summary row.
+    pub completed_collapsed: bool,
+    /// Todo being focused on and when the focus session ends.
+    pub focus_todo_id: Option<String>,
+    pub focus_until: Option<Instant>,
+}
+
+/// How long a status message stays in the footer.
+pub const STATUS_MESSAGE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Length of a focus session.
+pub const FOCUS_DURATION: Duration = Duration::from_secs(25 * 60);
+
+/// Shortest time between two re-filters while a search is being typed.
+pub const SEARCH_DEBOUNCE: Duration = Duration::from_millis(50);
+
+/// Decides when typed search text is applied to the filter, so a burst of
+/// keystrokes re-filters the list at most once per `SEARCH_DEBOUNCE`.
+#[derive(Default)]
+pub struct SearchDebounce {
+    pending: bool,
+    last_applied: Option<Instant>,
+}
+
+impl SearchDebounce {
+    pub fn input_changed(&mut self) {
+        self.pending = true;
+    }
+
+    /// Whether pending input should be applied at `now`. Returning true
+    /// counts as applying it.
+    pub fn take_due(&mut self, now: Instant) -> bool {
+        let due = self.pending
+            && self
+                .last_applied
+                .is_none_or(|applied| now.duration_since(applied) >= SEARCH_DEBOUNCE);
+        if due {
+            self.pending = false;
+            self.last_applied = Some(now);
+        }
+        due
+    }
+
+    /// Forgets pending input, for when it is applied or dropped directly.
+    pub fn clear(&mut self) {
+        self.pending = false;
+    }
 }
 
 impl App {
     pub fn new() -> Result<Self> {
-        let database = Database::new()?;
-        
-        Ok(Self {
+        let prefs = Prefs::load();
+        let database = Database::open(prefs.storage)?;
+        let mut app = Self::with_database(database);
+        app.prefs = prefs;
+        app.database.assign_sequences = app.prefs.sequential_ids;
+        if let Some(policy) = app.prefs.completed_retention {
+            let cleaned = app.database.cleanup_completed(policy, chrono::Utc::now());
+            app.absorb_save_failure(cleaned)?;
+        }
+        if let Some(inbox) = app.database.inbox_path() {
+            let drained = app.database.drain_inbox(&inbox);
+            let captured = app.absorb_save_failure(drained)?.unwrap_or(0);
+            if captured > 0 {
+                app.set_status(format!("Added {} todo(s) from the inbox", captured));
+            }
+        }
+        let size = app.database.file_size();
+        let checked = app.check_file_size(size);
+        app.absorb_save_failure(checked)?;
+        app.main_view.show_short_ids = app.prefs.show_short_ids;
+        app.main_view.show_durations = app.prefs.show_durations;
+        app.main_view.compact = app.prefs.compact;
+        app.main_view.priority_icons = app.prefs.priority_icons.clone();
+        app.restore_view();
+        app.restore_selection();
+        Ok(app)
+    }
+
+    pub fn with_database(database: Database) -> Self {
+        Self {
             state: AppState::Main,
             main_view: MainView::new(),
             detail_view: None,
@@ -32,28 +151,322 @@ impl App {
             database,
             should_quit: false,
             current_todo_id: None,
-            pending_delete_id: None,
-        })
+            pending_action: None,
+            input_dialog: None,
+            pending_input: None,
+            filter: TodoFilter::default(),
+            search_input: String::new(),
+            search_debounce: SearchDebounce::default(),
+            prefs: Prefs::default(),
+            list_states: HashMap::new(),
+            status_message: None,
+            prefs_save_failure: None,
+            status_is_error: false,
+            completed_collapsed: false,
+            focus_todo_id: None,
+            focus_until: None,
+        }
+    }
+
+    /// Todos in the current view, borrowed from the database.
+    pub fn get_current_todo_refs(&self) -> Vec<&Todo> {
+        let fold = self.folds_completed();
+        self.database.query(|todo| self.filter.matches(todo) && !(fold && todo.is_completed()))
+    }
+
+    /// Whether completed todos are folded away in the current view. The
+    /// Completed tab always shows them.
+    fn folds_completed(&self) -> bool {
+        self.completed_collapsed && self.filter.tab != ListTab::Completed
+    }
+
+    /// How many completed todos the summary row stands for, when folded.
+    pub fn collapsed_completed_count(&self) -> Option<usize> {
+        if !self.folds_completed() {
+            return None;
+        }
+        let count = self
+            .database
+            .query(|todo| todo.is_completed() && self.filter.matches(todo))
+            .len();
+        (count > 0).then_some(count)
+    }
+
+    pub fn toggle_completed_collapsed(&mut self) {
+        self.completed_collapsed = !self.completed_collapsed;
+        let len = self.get_current_todo_refs().len();
+        if self.main_view.selected_index().is_some_and(|index| index >= len) {
+            self.main_view.table_state.select(Some(len.saturating_sub(1)));
+        }
     }
 
+    /// Owned copy of the current view, for callers that need to modify
+    /// todos or keep them past the next database change.
     pub fn get_current_todos(&self) -> Vec<Todo> {
-        // Always show all todos (both active and completed)
-        self.database.get_all_todos().into_iter().cloned().collect()
+        self.get_current_todo_refs().into_iter().cloned().collect()
     }
 
-    pub fn get_selected_todo(&self) -> Option<Todo> {
-        let todos = self.get_current_todos();
-        if let Some(index) = self.main_view.selected_index() {
-            todos.get(index).cloned()
+    pub fn toggle_overdue_filter(&mut self) {
+        self.filter.overdue_only = !self.filter.overdue_only;
+        // The visible list changes size, so start again from the top
+        self.main_view.table_state.select(Some(0));
+    }
+
+    pub fn cycle_priority_filter(&mut self) {
+        self.filter.cycle_priority();
+        self.main_view.table_state.select(Some(0));
+    }
+
+    /// Snaps the list between only active and only completed todos.
+    pub fn invert_completion_view(&mut self) {
+        self.filter.invert_completion();
+        self.main_view.table_state.select(Some(0));
+    }
+
+    pub fn clear_completion_view(&mut self) {
+        if self.filter.completion.take().is_some() {
+            self.main_view.table_state.select(Some(0));
+        }
+    }
+
+    /// Switches the priority filter between kept across runs and cleared
+    /// on the next launch.
+    pub fn toggle_priority_filter_sticky(&mut self) -> Result<()> {
+        let sticky = &mut self.prefs.sticky_filters.priority;
+        *sticky = !*sticky;
+        let message = if *sticky {
+            "Priority filter is kept between runs"
+        } else {
+            "Priority filter is cleared on the next launch"
+        };
+        self.set_status(message);
+        self.save_prefs()
+    }
+
+    pub fn start_search(&mut self) {
+        self.search_input = self.filter.query.clone();
+        self.state = AppState::Search;
+    }
+
+    pub fn push_search_char(&mut self, c: char) {
+        self.search_input.push(c);
+        self.search_debounce.input_changed();
+        self.apply_search_if_due(Instant::now());
+    }
+
+    pub fn pop_search_char(&mut self) {
+        self.search_input.pop();
+        self.search_debounce.input_changed();
+        self.apply_search_if_due(Instant::now());
+    }
+
+    pub fn toggle_search_case(&mut self) {
+        self.filter.case_sensitive = !self.filter.case_sensitive;
+        self.apply_search_input();
+    }
+
+    /// Filters by the typed search text unless the list was already
+    /// re-filtered within `SEARCH_DEBOUNCE`. Also called on every tick so
+    /// the last keystroke is never left unapplied.
+    pub fn apply_search_if_due(&mut self, now: Instant) {
+        if self.search_debounce.take_due(now) {
+            self.apply_search_input();
+        }
+    }
+
+    fn apply_search_input(&mut self) {
+        self.search_debounce.clear();
+        self.filter.query = self.search_input.clone();
+        self.main_view.table_state.select(Some(0));
+    }
+
+    /// Leaves search mode keeping the query applied to the list.
+    pub fn finish_search(&mut self) {
+        self.apply_search_input();
+        self.state = AppState::Main;
+    }
+
+    /// Leaves search mode and clears the query.
+    pub fn cancel_search(&mut self) {
+        self.search_input.clear();
+        self.apply_search_input();
+        self.state = AppState::Main;
+    }
+
+    /// Cycles through the all-lists view and each named list, remembering
+    /// where the selection was in the list being left.
+    pub fn next_list(&mut self) {
+        let names = self.database.list_names();
+        let next = match &self.filter.list {
+            None => names.first().cloned(),
+            Some(current) => names
+                .iter()
+                .position(|name| name == current)
+                .and_then(|index| names.get(index + 1))
+                .cloned(),
+        };
+        self.switch_to_list(next);
+    }
+
+    pub fn switch_to_list(&mut self, list: Option<String>) {
+        let previous = std::mem::replace(&mut self.filter.list, list);
+        self.list_states
+            .insert(previous, self.main_view.table_state.clone());
+
+        self.main_view.table_state = match self.list_states.get(&self.filter.list) {
+            Some(state) => state.clone(),
+            None => {
+                let mut state = TableState::default();
+                state.select(Some(0));
+                state
+            }
+        };
+    }
+
+    pub fn next_tab(&mut self) {
+        self.filter.tab = self.filter.tab.next();
+        self.main_view.table_state.select(Some(0));
+    }
+
+    pub fn previous_tab(&mut self) {
+        self.filter.tab = self.filter.tab.previous();
+        self.main_view.table_state.select(Some(0));
+    }
+
+    /// Completed-today count and the daily goal, when a goal is set.
+    pub fn daily_progress(&self) -> Option<(usize, usize)> {
+        self.prefs
+            .daily_goal
+            .map(|goal| (self.database.completed_today(), goal))
+    }
+
+    /// Raises or lowers the daily goal by one; lowering it to zero turns
+    /// the goal off.
+    pub fn adjust_daily_goal(&mut self, increase: bool) -> Result<()> {
+        let current = self.prefs.daily_goal.unwrap_or(0);
+        let goal = if increase {
+            current + 1
         } else {
-            None
+            current.saturating_sub(1)
+        };
+        self.prefs.daily_goal = if goal == 0 { None } else { Some(goal) };
+        self.save_prefs()
+    }
+
+    pub fn cycle_sort_mode(&mut self) {
+        let mut sort_order = self.database.sort_order();
+        sort_order.mode = sort_order.mode.next();
+        self.database.set_sort_order(sort_order);
+        self.set_status(format!("Sort: {}", sort_order.label()));
+    }
+
+    /// Flips the sort direction and remembers it for the next launch.
+    pub fn toggle_sort_direction(&mut self) -> Result<()> {
+        let mut sort_order = self.database.sort_order();
+        sort_order.direction = sort_order.direction.toggled();
+        self.database.set_sort_order(sort_order);
+        self.set_status(format!("Sort: {}", sort_order.label()));
+        self.prefs.sort_direction = sort_order.direction;
+        self.save_prefs()
+    }
+
+    pub fn toggle_compact(&mut self) -> Result<()> {
+        self.main_view.compact = !self.main_view.compact;
+        self.prefs.compact = self.main_view.compact;
+        self.save_prefs()
+    }
+
+    pub fn toggle_durations(&mut self) -> Result<()> {
+        self.main_view.show_durations = !self.main_view.show_durations;
+        self.prefs.show_durations = self.main_view.show_durations;
+        self.save_prefs()
+    }
+
+    pub fn toggle_short_ids(&mut self) -> Result<()> {
+        self.main_view.show_short_ids = !self.main_view.show_short_ids;
+        self.prefs.show_short_ids = self.main_view.show_short_ids;
+        self.save_prefs()
+    }
+
+    /// Selects the todo remembered in prefs, if it still exists.
+    pub fn restore_selection(&mut self) {
+        if let Some(id) = self.prefs.last_selected_id.clone() {
+            self.select_todo(&id);
+        }
+    }
+
+    /// Moves the selection to `id` in the current view. Returns whether it
+    /// is visible.
+    pub fn select_todo(&mut self, id: &str) -> bool {
+        // Borrow the fields separately so `main_view` can be updated
+        let fold = self.folds_completed();
+        let filter = &self.filter;
+        let todos = self.database.query(|todo| filter.matches(todo) && !(fold && todo.is_completed()));
+        self.main_view.select_by_id(id, &todos)
+    }
+
+    /// Warns once when the database file has grown past
+    /// `LARGE_FILE_BYTES`, and re-arms the warning once it shrinks again.
+    pub fn check_file_size(&mut self, size: u64) -> Result<()> {
+        if size_warning_due(size, self.prefs.large_file_warned) {
+            self.set_status(format!(
+                "The database is {:.1} MB; consider archiving old todos (completed_retention)",
+                size as f64 / (1024.0 * 1024.0)
+            ));
+            self.prefs.large_file_warned = true;
+            self.save_prefs()?;
+        } else if size <= LARGE_FILE_BYTES && self.prefs.large_file_warned {
+            self.prefs.large_file_warned = false;
+            self.save_prefs()?;
         }
+        Ok(())
+    }
+
+    /// Moves the selection to the next overdue todo in the current view,
+    /// wrapping around.
+    pub fn select_next_overdue(&mut self) {
+        let fold = self.folds_completed();
+        let filter = &self.filter;
+        let todos = self.database.query(|todo| filter.matches(todo) && !(fold && todo.is_completed()));
+        if !self.main_view.select_next_matching(Todo::is_overdue, &todos) {
+            self.set_status("No overdue todos");
+        }
+    }
+
+    /// Applies the sort order and filters saved in prefs.
+    pub fn restore_view(&mut self) {
+        self.database.set_sort_order(SortOrder {
+            mode: self.prefs.sort_mode,
+            direction: self.prefs.sort_direction,
+            stable_reopen: self.prefs.stable_reopen,
+        });
+        self.filter = self.prefs.sticky_filters.saved(&self.prefs.filter);
+    }
+
+    /// Stores the selected todo, sort mode and filters in prefs so the next
+    /// launch starts there.
+    pub fn remember_selection(&mut self) -> Result<()> {
+        self.prefs.last_selected_id = self.get_selected_todo().map(|todo| todo.id);
+        self.prefs.sort_mode = self.database.sort_order().mode;
+        self.prefs.filter = self.prefs.sticky_filters.saved(&self.filter);
+        self.save_prefs()
+    }
+
+    pub fn get_selected_todo(&self) -> Option<Todo> {
+        let index = self.main_view.selected_index()?;
+        self.get_current_todo_refs()
+            .get(index)
+            .map(|todo| (*todo).clone())
     }
 
     pub fn open_detail_view(&mut self) {
         if let Some(todo) = self.get_selected_todo() {
             self.current_todo_id = Some(todo.id.clone());
-            self.detail_view = Some(DetailView::new_for_viewing(&todo));
+            let mut detail_view = DetailView::new_for_viewing(&todo);
+            detail_view.known_tags = self.database.tag_counts();
+            detail_view.locale = self.prefs.locale;
+            detail_view.tab_width = self.prefs.tab_width;
+            self.detail_view = Some(detail_view);
             self.state = AppState::Detail;
         }
     }
@@ -61,147 +474,625 @@ impl App {
     pub fn open_edit_view(&mut self) {
         if let Some(todo) = self.get_selected_todo() {
             self.current_todo_id = Some(todo.id.clone());
-            self.detail_view = Some(DetailView::new_for_editing(&todo));
+            let mut detail_view = DetailView::new_for_editing(&todo);
+            detail_view.known_tags = self.database.tag_counts();
+            detail_view.locale = self.prefs.locale;
+            detail_view.tab_width = self.prefs.tab_width;
+            self.detail_view = Some(detail_view);
             self.state = AppState::Detail;
         }
     }
 
     pub fn open_new_todo(&mut self) {
         self.current_todo_id = None;
-        self.detail_view = Some(DetailView::new_for_creation());
+        let mut detail_view = DetailView::new_for_creation();
+        detail_view.known_tags = self.database.tag_counts();
+        detail_view.locale = self.prefs.locale;
+        detail_view.tab_width = self.prefs.tab_width;
+        detail_view.priority = self.prefs.default_priority;
+        if let Some(list) = &self.filter.list {
+            detail_view.list = list.clone();
+        }
+        detail_view.mark_saved();
+        self.detail_view = Some(detail_view);
         self.state = AppState::Detail;
     }
 
+    /// Saves the form in place and keeps the detail view open (Ctrl+S). A
+    /// new todo switches to editing the saved todo, so saving again updates
+    /// it instead of creating a copy.
     pub fn save_current_todo(&mut self) -> Result<()> {
-        if let Some(detail_view) = &self.detail_view {
-            if !detail_view.is_valid() {
-                return Ok(());
-            }
+        self.save_detail_view().map(|_| ())
+    }
+
+    /// Saves the form and returns to the list (Ctrl+D). The view stays open
+    /// if the form is invalid.
+    pub fn save_and_close_detail_view(&mut self) -> Result<()> {
+        if self.save_detail_view()? {
+            self.close_detail_view();
+        }
+        Ok(())
+    }
+
+    /// Shared save path. Returns whether anything was written; an invalid
+    /// form is kept open with its error shown instead.
+    fn save_detail_view(&mut self) -> Result<bool> {
+        let Some(detail_view) = &mut self.detail_view else {
+            return Ok(false);
+        };
+        if !detail_view.is_valid() {
+            detail_view.show_errors = true;
+            return Ok(false);
+        }
 
-            match detail_view.mode {
-                DetailMode::New => {
-                    let todo = Todo::new(
-                        detail_view.subject.clone(),
-                        detail_view.description.clone(),
-                    );
-                    self.database.add_todo(todo)?;
+        let saved = match detail_view.mode {
+            DetailMode::New => {
+                let mut todo = Todo::new(
+                    detail_view.subject_value(),
+                    detail_view.description.clone(),
+                );
+                todo.due_date = detail_view.due_date_value();
+                todo.list = detail_view.list_value();
+                todo.tags = detail_view.tags_value();
+                todo.notes = detail_view.notes.clone();
+                todo.priority = detail_view.priority;
+                let id = todo.id.clone();
+                let added = self.database.add_todo(todo);
+                self.select_todo(&id);
+                self.current_todo_id = Some(id);
+                // The todo is kept even if it couldn't be written, so the
+                // form edits it from now on instead of adding it again.
+                if let Err(err) = added {
+                    self.refresh_detail_view();
+                    return Err(err);
                 }
-                DetailMode::Edit => {
-                    if let Some(id) = &self.current_todo_id {
-                        if let Some(mut todo) = self.database.get_todo(id).cloned() {
-                            todo.update(
-                                detail_view.subject.clone(),
-                                detail_view.description.clone(),
-                            );
-                            self.database.update_todo(todo)?;
-                        }
+                true
+            }
+            DetailMode::Edit => {
+                let todo = self
+                    .current_todo_id
+                    .as_ref()
+                    .and_then(|id| self.database.get_todo(id).cloned());
+                match todo {
+                    Some(mut todo) => {
+                        apply_edits(detail_view, &mut todo);
+                        self.database.update_todo(todo)?;
+                        true
                     }
-                }
-                DetailMode::View => {
-                    // Nothing to save in view mode
+                    None => false,
                 }
             }
+            DetailMode::View => false,
+        };
+
+        if saved {
+            self.refresh_detail_view();
+            self.set_status("Saved");
         }
+        Ok(saved)
+    }
+
+    /// Reloads the open edit form from the stored todo, keeping the focused
+    /// field, so timestamps and the closed time reflect what was saved.
+    fn refresh_detail_view(&mut self) {
+        let Some(todo) = self
+            .current_todo_id
+            .as_ref()
+            .and_then(|id| self.database.get_todo(id))
+        else {
+            return;
+        };
+        let mut refreshed = DetailView::new_for_editing(todo);
+        refreshed.known_tags = self.database.tag_counts();
+        refreshed.locale = self.prefs.locale;
+        refreshed.tab_width = self.prefs.tab_width;
+        if let Some(detail_view) = &self.detail_view {
+            refreshed.current_field = detail_view.current_field;
+        }
+        self.detail_view = Some(refreshed);
+    }
+
+    /// Opens the first attachment of the todo shown in the detail view with
+    /// the system's default application.
+    pub fn open_first_attachment(&self) -> std::io::Result<()> {
+        match self.detail_view.as_ref().and_then(|view| view.attachments.first()) {
+            Some(path) => open::that_detached(path),
+            None => Ok(()),
+        }
+    }
 
-        self.close_detail_view();
+    /// Resets a future creation time of the viewed todo to now (see
+    /// `Todo::clamp_created_at`).
+    pub fn reset_future_created(&mut self) -> Result<()> {
+        let Some(mut todo) = self.current_todo_id.as_ref().and_then(|id| self.database.get_todo(id)).cloned() else {
+            return Ok(());
+        };
+        if !todo.clamp_created_at(chrono::Utc::now()) {
+            return Ok(());
+        }
+        if let Some(detail_view) = &mut self.detail_view {
+            detail_view.created_at = Some(todo.created_at);
+            detail_view.last_modified_at = Some(todo.last_modified_at);
+        }
+        self.database.update_todo(todo)?;
+        self.set_status("Creation time reset to now");
         Ok(())
     }
 
+    /// Moves the subtask selected in the detail view up or down and saves
+    /// the new order; the selection follows it.
+    pub fn move_selected_subtask(&mut self, up: bool) -> Result<()> {
+        let Some(detail_view) = &mut self.detail_view else {
+            return Ok(());
+        };
+        let Some(mut todo) = self.current_todo_id.as_ref().and_then(|id| self.database.get_todo(id)).cloned() else {
+            return Ok(());
+        };
+        let index = detail_view.selected_subtask;
+        if !todo.move_subtask(index, up) {
+            return Ok(());
+        }
+        detail_view.selected_subtask = if up { index - 1 } else { index + 1 };
+        detail_view.sync_subtasks(&todo);
+        self.database.update_todo(todo)
+    }
+
+    /// Ticks or unticks the subtask selected in the detail view and saves
+    /// it; ticking the last open one completes the todo.
+    pub fn toggle_selected_subtask(&mut self) -> Result<()> {
+        let Some(detail_view) = &mut self.detail_view else {
+            return Ok(());
+        };
+        let Some(mut todo) = self.current_todo_id.as_ref().and_then(|id| self.database.get_todo(id)).cloned() else {
+            return Ok(());
+        };
+        if !todo.toggle_subtask(detail_view.selected_subtask) {
+            return Ok(());
+        }
+        detail_view.sync_subtasks(&todo);
+        self.database.update_todo(todo)
+    }
+
     pub fn close_detail_view(&mut self) {
         self.detail_view = None;
         self.current_todo_id = None;
         self.state = AppState::Main;
     }
 
-    pub fn close_detail_view_with_save(&mut self) -> Result<()> {
-        // Save the current todo if it's valid and in edit/new mode
-        if let Some(detail_view) = &self.detail_view {
-            if detail_view.is_valid() {
-                match detail_view.mode {
-                    DetailMode::New => {
-                        let todo = Todo::new(
-                            detail_view.subject.clone(),
-                            detail_view.description.clone(),
-                        );
-                        self.database.add_todo(todo)?;
-                    }
-                    DetailMode::Edit => {
-                        if let Some(id) = &self.current_todo_id {
-                            if let Some(mut todo) = self.database.get_todo(id).cloned() {
-                                todo.update(
-                                    detail_view.subject.clone(),
-                                    detail_view.description.clone(),
-                                );
-                                self.database.update_todo(todo)?;
-                            }
-                        }
-                    }
-                    DetailMode::View => {
-                        // Nothing to save in view mode
-                    }
+    pub fn toggle_selected_todo(&mut self) -> Result<()> {
+        if let Some(mut todo) = self.get_selected_todo() {
+            todo.toggle_completion();
+            self.database.update_todo(todo)?;
+        }
+        Ok(())
+    }
+
+    /// Marks the selected todo as a template, or unmarks it.
+    pub fn toggle_selected_template(&mut self) -> Result<()> {
+        if let Some(mut todo) = self.get_selected_todo() {
+            todo.is_template = !todo.is_template;
+            todo.last_modified_at = chrono::Utc::now();
+            self.database.update_todo(todo)?;
+        }
+        Ok(())
+    }
+
+    /// Adds a todo copied from the template `id` (see `Todo::from_template`)
+    /// and returns its id, or `None` when `id` isn't a template.
+    pub fn create_from_template(&mut self, id: &str) -> Result<Option<String>> {
+        let Some(todo) = self.database.get_todo(id).filter(|todo| todo.is_template).map(Todo::from_template) else {
+            return Ok(None);
+        };
+        self.database.add_todo(todo).map(Some)
+    }
+
+    /// Creates a todo from the selected template and opens it for editing.
+    pub fn new_from_selected_template(&mut self) -> Result<()> {
+        let Some(template) = self.get_selected_todo() else {
+            return Ok(());
+        };
+        match self.create_from_template(&template.id)? {
+            Some(id) => {
+                if self.select_todo(&id) {
+                    self.open_edit_view();
                 }
             }
+            None => self.set_status("Not a template; mark one with M"),
         }
-
-        self.close_detail_view();
         Ok(())
     }
 
-    pub fn toggle_selected_todo(&mut self) -> Result<()> {
+    pub fn cycle_selected_priority(&mut self) -> Result<()> {
         if let Some(mut todo) = self.get_selected_todo() {
-            todo.toggle_completion();
+            todo.priority = todo.priority.next();
+            todo.last_modified_at = chrono::Utc::now();
             self.database.update_todo(todo)?;
         }
         Ok(())
     }
 
-    pub fn confirm_delete_selected(&mut self) {
+    /// Copies the todos in the current view to the clipboard as a markdown
+    /// checklist. Failures (e.g. no clipboard on a headless system) are
+    /// reported in the status bar.
+    pub fn copy_view_as_markdown(&mut self) {
+        let todos = self.get_current_todo_refs();
+        let count = todos.len();
+        let markdown = export::markdown_checklist(&todos);
+        let copied = arboard::Clipboard::new().and_then(|mut clipboard| clipboard.set_text(markdown));
+        match copied {
+            Ok(()) => self.set_status(format!("Copied {} todos as markdown", count)),
+            Err(error) => self.set_status(format!("Could not copy: {}", error)),
+        }
+    }
+
+    pub fn show_help(&mut self) {
+        self.state = AppState::Help;
+    }
+
+    /// Starts a focus session on the selected todo, or stops the running
+    /// one.
+    pub fn toggle_focus(&mut self, now: Instant) {
+        if self.focus_until.is_some() {
+            self.focus_todo_id = None;
+            self.focus_until = None;
+            self.set_status("Focus stopped");
+        } else if let Some(todo) = self.get_selected_todo() {
+            self.focus_todo_id = Some(todo.id);
+            self.focus_until = Some(now + FOCUS_DURATION);
+            self.set_status(format!("Focusing for {} minutes", FOCUS_DURATION.as_secs() / 60));
+        }
+    }
+
+    /// Time left in the focus session, if one is running.
+    pub fn focus_remaining(&self, now: Instant) -> Option<Duration> {
+        self.focus_until.map(|until| until.saturating_duration_since(now))
+    }
+
+    /// Ends the focus session once its time is up. Called on every tick;
+    /// returns true on the tick the session ends so the caller can ring
+    /// the bell.
+    pub fn update_focus(&mut self, now: Instant) -> bool {
+        if self.focus_remaining(now) != Some(Duration::ZERO) {
+            return false;
+        }
+        self.focus_todo_id = None;
+        self.focus_until = None;
+        self.set_status("Focus session done");
+        true
+    }
+
+    /// Header text for a running focus session, e.g. `🍅 24:59 Write report`.
+    pub fn focus_label(&self, now: Instant) -> Option<String> {
+        let remaining = self.focus_remaining(now)?;
+        let subject = self
+            .focus_todo_id
+            .as_ref()
+            .and_then(|id| self.database.get_todo(id))
+            .map_or("", |todo| todo.subject.as_str());
+        let seconds = remaining.as_secs();
+        Some(format!("🍅 {:02}:{:02} {}", seconds / 60, seconds % 60, subject))
+    }
+
+    pub fn show_recently_completed(&mut self) {
+        self.state = AppState::RecentlyCompleted;
+    }
+
+    /// Closes the help, theme preview or recently completed overlay.
+    pub fn close_overlay(&mut self) {
+        self.state = AppState::Main;
+    }
+
+    pub fn show_theme_preview(&mut self) {
+        self.state = AppState::ThemePreview;
+    }
+
+    /// Marks or unmarks the selected todo for actions on several todos.
+    pub fn toggle_mark_selected(&mut self) {
         if let Some(todo) = self.get_selected_todo() {
-            self.pending_delete_id = Some(todo.id.clone());
-            self.confirm_dialog = Some(ConfirmDialog::new(
-                "Delete Todo".to_string(),
-                format!("Delete todo: \"{}\"?", todo.subject),
-            ));
-            self.state = AppState::Confirm;
+            self.main_view.toggle_mark(&todo.id);
+        }
+    }
+
+    /// Unmarks every todo, including ones hidden by the current filter.
+    pub fn clear_marks(&mut self) {
+        self.main_view.marked.clear();
+    }
+
+    /// Drops marks of todos that no longer exist, so the marked count in
+    /// the list title stays right.
+    fn prune_marks(&mut self) {
+        let database = &self.database;
+        self.main_view.marked.retain(|id| database.get_todo(id).is_some());
+    }
+
+    /// Ids of marked todos that still exist, in a stable order.
+    pub fn marked_ids(&self) -> Vec<String> {
+        let mut ids: Vec<String> = self
+            .main_view
+            .marked
+            .iter()
+            .filter(|id| self.database.get_todo(id).is_some())
+            .cloned()
+            .collect();
+        ids.sort();
+        ids
+    }
+
+    /// Asks to delete the marked todos, or the selected one when nothing
+    /// is marked.
+    pub fn confirm_delete_selected(&mut self) {
+        let marked = self.marked_ids();
+        if !marked.is_empty() {
+            let message = format!("Delete {} todo(s)?", marked.len());
+            self.ask_confirmation(
+                ConfirmAction::Delete(marked),
+                ConfirmDialog::new("Delete Todos".to_string(), message),
+            );
+        } else if let Some(todo) = self.get_selected_todo() {
+            self.ask_confirmation(
+                ConfirmAction::Delete(vec![todo.id.clone()]),
+                ConfirmDialog::new(
+                    "Delete Todo".to_string(),
+                    format!("Delete todo: \"{}\"?", todo.subject),
+                ),
+            );
         }
     }
 
-    pub fn delete_confirmed_todo(&mut self) -> Result<()> {
-        if let Some(id) = &self.pending_delete_id {
-            self.database.delete_todo(id)?;
+    pub fn confirm_clear_completed(&mut self) {
+        let count = self.database.query(Todo::is_completed).len();
+        if count == 0 {
+            self.set_status("No completed todos");
+            return;
         }
+        self.ask_confirmation(
+            ConfirmAction::ClearCompleted,
+            ConfirmDialog::new(
+                "Clear Completed".to_string(),
+                format!("Delete {} completed todo(s)?", count),
+            ),
+        );
+    }
+
+    fn ask_confirmation(&mut self, action: ConfirmAction, dialog: ConfirmDialog) {
+        self.pending_action = Some(action);
+        self.confirm_dialog = Some(dialog);
+        self.state = AppState::Confirm;
+    }
+
+    /// Runs the action the confirm dialog was opened for and closes it.
+    pub fn run_confirmed_action(&mut self) -> Result<()> {
+        let result = match self.pending_action.take() {
+            Some(action) => self.apply_confirmed_action(action),
+            None => Ok(()),
+        };
+        // Closed even if saving failed, so the dialog isn't left behind
+        // without an action.
         self.close_confirm_dialog();
+        result
+    }
+
+    fn apply_confirmed_action(&mut self, action: ConfirmAction) -> Result<()> {
+        match action {
+            ConfirmAction::Delete(ids) => {
+                let deleted = self.database.with_batch(|database| {
+                    ids.iter().try_for_each(|id| database.delete_todo(id))
+                });
+                self.prune_marks();
+                deleted?;
+                if ids.len() == 1 {
+                    self.set_status("Deleted");
+                } else {
+                    self.set_status(format!("Deleted {} todos", ids.len()));
+                }
+            }
+            ConfirmAction::ClearCompleted => {
+                let removed = self.database.delete_completed();
+                self.prune_marks();
+                let removed = removed?;
+                self.main_view.table_state.select(Some(0));
+                self.set_status(format!("Deleted {} completed", removed));
+            }
+            ConfirmAction::CompleteAll(ids) => {
+                let completed = self.database.complete_all(&ids)?;
+                self.set_status(format!("Completed {} todo(s)", completed));
+            }
+            ConfirmAction::Quit => self.quit(),
+        }
         Ok(())
     }
 
+    /// Closes the confirm dialog, returning to the detail view if it was
+    /// asked from there.
     pub fn close_confirm_dialog(&mut self) {
         self.confirm_dialog = None;
-        self.pending_delete_id = None;
+        self.pending_action = None;
+        self.state = if self.detail_view.is_some() {
+            AppState::Detail
+        } else {
+            AppState::Main
+        };
+    }
+
+    /// Asks to complete every active todo in the current view.
+    pub fn confirm_complete_view(&mut self) {
+        let ids: Vec<String> = self
+            .get_current_todo_refs()
+            .iter()
+            .filter(|todo| !todo.is_completed())
+            .map(|todo| todo.id.clone())
+            .collect();
+        if ids.is_empty() {
+            self.set_status("No active todos shown");
+            return;
+        }
+        let message = format!("Complete {} shown todo(s)?", ids.len());
+        self.ask_confirmation(
+            ConfirmAction::CompleteAll(ids),
+            ConfirmDialog::new("Complete Shown".to_string(), message),
+        );
+    }
+
+    /// Asks for a due date to give every todo in the current view.
+    pub fn prompt_due_date_for_view(&mut self) {
+        let ids: Vec<String> = self
+            .get_current_todo_refs()
+            .iter()
+            .map(|todo| todo.id.clone())
+            .collect();
+        if ids.is_empty() {
+            return;
+        }
+        self.input_dialog = Some(InputDialog::new(
+            "Set Due Date".to_string(),
+            format!("Due date for {} todo(s), e.g. 2024-06-30, friday or +2w (empty clears it):", ids.len()),
+        ));
+        self.pending_input = Some(InputAction::SetDueDate(ids));
+        self.state = AppState::Input;
+    }
+
+    /// Uses the typed value for the pending action. An invalid value keeps
+    /// the dialog open with the reason shown.
+    pub fn submit_input(&mut self) -> Result<()> {
+        let (Some(dialog), Some(action)) = (&mut self.input_dialog, &self.pending_input) else {
+            self.close_input_dialog();
+            return Ok(());
+        };
+        match action {
+            InputAction::SetDueDate(ids) => {
+                let due_date = match parse_due_date(&dialog.value) {
+                    Ok(due_date) => due_date,
+                    Err(error) => {
+                        dialog.error = Some(error.to_string());
+                        return Ok(());
+                    }
+                };
+                let updated = self.database.set_due_date_for(ids, due_date)?;
+                let verb = if due_date.is_some() { "Set" } else { "Cleared" };
+                self.set_status(format!("{} due date on {} todos", verb, updated));
+            }
+        }
+        self.close_input_dialog();
+        Ok(())
+    }
+
+    pub fn close_input_dialog(&mut self) {
+        self.input_dialog = None;
+        self.pending_input = None;
         self.state = AppState::Main;
     }
 
+    pub fn set_status(&mut self, message: impl Into<String>) {
+        self.status_message = Some((message.into(), Instant::now()));
+        self.status_is_error = false;
+    }
+
+    pub fn set_error_status(&mut self, message: impl Into<String>) {
+        self.set_status(message);
+        self.status_is_error = true;
+    }
+
+    /// Writes the prefs. A failed write is recorded so that it can be
+    /// reported like a failed database save.
+    fn save_prefs(&mut self) -> Result<()> {
+        let saved = self.prefs.save();
+        if let Err(err) = &saved {
+            let path = self.prefs.path().map(|path| path.display().to_string()).unwrap_or_default();
+            self.prefs_save_failure = Some(format!("{} ({})", path, err.root_cause()));
+        }
+        saved
+    }
+
+    /// Turns a failed database or prefs save into a status message, keeping
+    /// the session and the unsaved changes, which the next successful save
+    /// writes. Returns false if the last save didn't fail.
+    pub fn report_save_failure(&mut self) -> bool {
+        let failure = self.database.take_save_failure().or_else(|| self.prefs_save_failure.take());
+        match failure {
+            Some(failure) => {
+                self.set_error_status(format!("Not saved, could not write {}", failure));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// `result`, unless it failed only because the database or prefs
+    /// couldn't be written; that is reported instead (see `report_save_failure`) and
+    /// `None` returned.
+    fn absorb_save_failure<T>(&mut self, result: Result<T>) -> Result<Option<T>> {
+        match result {
+            Ok(value) => Ok(Some(value)),
+            Err(_) if self.report_save_failure() => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Clears the status message once it has been shown for
+    /// `STATUS_MESSAGE_TIMEOUT`. Called on every tick.
+    pub fn expire_status_message(&mut self, now: Instant) {
+        if let Some((_, set_at)) = &self.status_message {
+            if now.duration_since(*set_at) >= STATUS_MESSAGE_TIMEOUT {
+                self.status_message = None;
+            }
+        }
+    }
+
     pub fn quit(&mut self) {
         self.should_quit = true;
     }
+
+    /// Quits, first asking whether to drop unsaved edits in the detail view.
+    pub fn request_quit(&mut self) {
+        let unsaved = self
+            .detail_view
+            .as_ref()
+            .is_some_and(|detail_view| detail_view.has_unsaved_changes());
+        if unsaved {
+            self.ask_confirmation(
+                ConfirmAction::Quit,
+                ConfirmDialog::new(
+                    "Unsaved Changes".to_string(),
+                    "Quit without saving your changes?".to_string(),
+                ),
+            );
+        } else {
+            self.quit();
+        }
+    }
+}
+
+/// Copies the edit form onto `todo`, bumping `last_modified_at` only when
+/// something actually changed.
+fn apply_edits(detail_view: &DetailView, todo: &mut Todo) {
+    let before = (todo.due_date, todo.list.clone(), todo.tags.clone(), todo.notes.clone(), todo.priority, todo.closed_at);
+    let content_changed = todo.update(detail_view.subject_value(), detail_view.description.clone());
+
+    todo.due_date = detail_view.due_date_value();
+    todo.list = detail_view.list_value();
+    todo.tags = detail_view.tags_value();
+    todo.notes = detail_view.notes.clone();
+    todo.priority = detail_view.priority;
+    if todo.is_completed() {
+        if let Ok(closed_at) = detail_view.closed_at_value() {
+            todo.closed_at = closed_at;
+        }
+    }
+
+    let after = (todo.due_date, todo.list.clone(), todo.tags.clone(), todo.notes.clone(), todo.priority, todo.closed_at);
+    if !content_changed && before != after {
+        todo.last_modified_at = chrono::Utc::now();
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::data::Database;
+    use crate::data::{Database, Priority, Subtask};
 
     fn create_test_app() -> App {
-        let database = Database::new_in_memory().unwrap();
-        App {
-            state: AppState::Main,
-            main_view: MainView::new(),
-            detail_view: None,
-            confirm_dialog: None,
-            database,
-            should_quit: false,
-            current_todo_id: None,
-            pending_delete_id: None,
-        }
+        let database = Database::in_memory();
+        App::with_database(database)
     }
 
     #[test]
@@ -212,7 +1103,7 @@ mod tests {
         assert!(app.detail_view.is_none());
         assert!(app.confirm_dialog.is_none());
         assert!(app.current_todo_id.is_none());
-        assert!(app.pending_delete_id.is_none());
+        assert!(app.pending_action.is_none());
     }
 
     #[test]
@@ -237,6 +1128,30 @@ mod tests {
         assert_eq!(todos.len(), 2);
     }
 
+    #[test]
+    fn test_todo_refs_match_cloned_todos() {
+        let mut app = create_test_app();
+        app.database.insert_todo_for_test(Todo::new("Write docs".to_string(), String::new()));
+        app.database.insert_todo_for_test(Todo::new("Fix bug".to_string(), String::new()));
+        let mut done = Todo::new("Release".to_string(), String::new());
+        done.toggle_completion();
+        app.database.insert_todo_for_test(done);
+        app.next_tab();
+
+        let borrowed: Vec<&str> = app
+            .get_current_todo_refs()
+            .iter()
+            .map(|todo| todo.subject.as_str())
+            .collect();
+        let cloned: Vec<String> = app
+            .get_current_todos()
+            .into_iter()
+            .map(|todo| todo.subject)
+            .collect();
+        assert_eq!(borrowed.len(), 2);
+        assert_eq!(borrowed, cloned);
+    }
+
     #[test]
     fn test_get_selected_todo() {
         let mut app = create_test_app();
@@ -274,6 +1189,103 @@ mod tests {
         assert!(matches!(detail_view.mode, DetailMode::View));
     }
 
+    #[test]
+    fn test_create_from_template() {
+        let mut app = create_test_app();
+        let mut template = Todo::new("Release".to_string(), "Tag and publish".to_string());
+        template.tags = vec!["ops".to_string()];
+        let template_id = template.id.clone();
+        app.database.insert_todo_for_test(template);
+
+        // Only todos marked as templates can be copied
+        assert_eq!(app.create_from_template(&template_id).unwrap(), None);
+
+        app.main_view.table_state.select(Some(0));
+        app.toggle_selected_template().unwrap();
+        let id = app.create_from_template(&template_id).unwrap().unwrap();
+        let todo = app.database.get_todo(&id).unwrap();
+        assert_eq!(todo.subject, "Release");
+        assert_eq!(todo.tags, ["ops"]);
+        assert!(!todo.is_template);
+        assert_eq!(app.database.get_all_todos().len(), 2);
+    }
+
+    #[test]
+    fn test_large_file_warning_shown_once() {
+        let mut app = create_test_app();
+        app.check_file_size(LARGE_FILE_BYTES + 1).unwrap();
+        assert!(app.status_message.is_some());
+        assert!(app.prefs.large_file_warned);
+
+        app.status_message = None;
+        app.check_file_size(LARGE_FILE_BYTES + 1).unwrap();
+        assert!(app.status_message.is_none());
+
+        app.check_file_size(1024).unwrap();
+        assert!(!app.prefs.large_file_warned);
+    }
+
+    #[test]
+    fn test_move_selected_subtask() {
+        let mut app = create_test_app();
+        let mut todo = Todo::new("Trip".to_string(), String::new());
+        todo.subtasks = ["Pack", "Go"].map(|subject| Subtask::new(subject.to_string())).into();
+        let todo_id = todo.id.clone();
+        app.database.insert_todo_for_test(todo);
+        app.open_detail_view();
+
+        app.move_selected_subtask(false).unwrap();
+        let subjects = |todo: &Todo| todo.subtasks.iter().map(|subtask| subtask.subject.clone()).collect::<Vec<_>>();
+        assert_eq!(subjects(app.database.get_todo(&todo_id).unwrap()), ["Go", "Pack"]);
+        let detail_view = app.detail_view.as_ref().unwrap();
+        assert_eq!(detail_view.selected_subtask, 1);
+        assert_eq!(detail_view.subtasks[1].subject, "Pack");
+
+        // Already last, so nothing changes
+        app.move_selected_subtask(false).unwrap();
+        assert_eq!(subjects(app.database.get_todo(&todo_id).unwrap()), ["Go", "Pack"]);
+    }
+
+    #[test]
+    fn test_toggle_selected_subtask() {
+        let mut app = create_test_app();
+        let mut todo = Todo::new("Trip".to_string(), String::new());
+        todo.subtasks = ["Pack", "Go"].map(|subject| Subtask::new(subject.to_string())).into();
+        let todo_id = todo.id.clone();
+        app.database.insert_todo_for_test(todo);
+        app.open_detail_view();
+
+        app.detail_view.as_mut().unwrap().cycle_subtask(true);
+        app.toggle_selected_subtask().unwrap();
+        let stored = app.database.get_todo(&todo_id).unwrap();
+        assert_eq!(stored.subtask_progress(), Some((1, 2)));
+        assert!(app.detail_view.as_ref().unwrap().subtasks[1].done);
+
+        // Ticking the last open one completes the todo
+        app.detail_view.as_mut().unwrap().cycle_subtask(true);
+        app.toggle_selected_subtask().unwrap();
+        assert!(app.database.get_todo(&todo_id).unwrap().is_completed());
+        let detail_view = app.detail_view.as_ref().unwrap();
+        assert!(detail_view.closed_at.is_some());
+        assert!(!detail_view.has_unsaved_changes());
+    }
+
+    #[test]
+    fn test_reset_future_created() {
+        let mut app = create_test_app();
+        let mut todo = Todo::new("Skewed".to_string(), String::new());
+        todo.created_at = chrono::Utc::now() + chrono::Duration::days(30);
+        let todo_id = todo.id.clone();
+        app.database.insert_todo_for_test(todo);
+        app.open_detail_view();
+        assert!(app.detail_view.as_ref().unwrap().has_future_created(chrono::Utc::now()));
+
+        app.reset_future_created().unwrap();
+        let now = chrono::Utc::now();
+        assert!(!app.database.get_todo(&todo_id).unwrap().has_future_created(now));
+        assert!(!app.detail_view.as_ref().unwrap().has_future_created(now));
+    }
+
     #[test]
     fn test_open_edit_view() {
         let mut app = create_test_app();
@@ -357,7 +1369,7 @@ mod tests {
         
         assert!(matches!(app.state, AppState::Confirm));
         assert!(app.confirm_dialog.is_some());
-        assert_eq!(app.pending_delete_id, Some(todo_id));
+        assert_eq!(app.pending_action, Some(ConfirmAction::Delete(vec![todo_id])));
     }
 
     #[test]
@@ -375,7 +1387,732 @@ mod tests {
         
         assert!(matches!(app.state, AppState::Main));
         assert!(app.confirm_dialog.is_none());
-        assert!(app.pending_delete_id.is_none());
+        assert!(app.pending_action.is_none());
+    }
+
+    #[test]
+    fn test_confirmed_delete_removes_selected_todo() {
+        let mut app = create_test_app();
+        let keep = Todo::new("Keep".to_string(), String::new());
+        let keep_id = keep.id.clone();
+        app.database.insert_todo_for_test(keep);
+        app.confirm_delete_selected();
+
+        app.run_confirmed_action().unwrap();
+
+        assert!(app.database.get_todo(&keep_id).is_none());
+        assert!(matches!(app.state, AppState::Main));
+        assert!(app.pending_action.is_none());
+        assert_eq!(app.status_message.as_ref().map(|(message, _)| message.as_str()), Some("Deleted"));
+    }
+
+    #[test]
+    fn test_delete_marked_todos() {
+        let mut app = create_test_app();
+        for subject in ["One", "Two", "Three", "Four"] {
+            app.database.insert_todo_for_test(Todo::new(subject.to_string(), String::new()));
+        }
+        let ids: Vec<String> = app.get_current_todos().into_iter().map(|todo| todo.id).collect();
+        for index in [0, 2, 3] {
+            app.main_view.table_state.select(Some(index));
+            app.toggle_mark_selected();
+        }
+
+        // Cancelling leaves everything in place
+        app.confirm_delete_selected();
+        assert!(app.confirm_dialog.as_ref().unwrap().message.contains("Delete 3 todo(s)?"));
+        app.close_confirm_dialog();
+        assert_eq!(app.get_current_todos().len(), 4);
+
+        app.confirm_delete_selected();
+        app.run_confirmed_action().unwrap();
+        let remaining = app.get_current_todos();
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].id, ids[1]);
+        assert!(app.main_view.marked.is_empty());
+    }
+
+    #[test]
+    fn test_marks_survive_filters_that_hide_them() {
+        let mut app = create_test_app();
+        let mut report = Todo::new("Write report".to_string(), String::new());
+        report.list = "Work".to_string();
+        let report_id = report.id.clone();
+        app.database.insert_todo_for_test(report);
+        app.database.insert_todo_for_test(Todo::new("Buy milk".to_string(), String::new()));
+
+        assert!(app.select_todo(&report_id));
+        app.toggle_mark_selected();
+
+        // Searching hides the marked todo without unmarking it
+        app.filter.query = "milk".to_string();
+        assert!(app.get_current_todos().iter().all(|todo| todo.id != report_id));
+        assert_eq!(app.marked_ids(), vec![report_id.clone()]);
+
+        app.filter.query.clear();
+        app.switch_to_list(Some("Inbox".to_string()));
+        assert_eq!(app.marked_ids(), vec![report_id.clone()]);
+
+        app.switch_to_list(None);
+        assert!(app.select_todo(&report_id));
+        assert!(app.main_view.marked.contains(&report_id));
+
+        app.clear_marks();
+        assert!(app.marked_ids().is_empty());
+    }
+
+    #[test]
+    fn test_invert_completion_view() {
+        let mut app = create_test_app();
+        let mut done = Todo::new("Done".to_string(), String::new());
+        done.toggle_completion();
+        app.database.insert_todo_for_test(done);
+        app.database.insert_todo_for_test(Todo::new("Open".to_string(), String::new()));
+
+        app.invert_completion_view();
+        assert_eq!(app.get_current_todos()[0].subject, "Open");
+        assert_eq!(app.get_current_todos().len(), 1);
+        app.invert_completion_view();
+        assert_eq!(app.get_current_todos()[0].subject, "Done");
+        assert_eq!(app.get_current_todos().len(), 1);
+
+        app.clear_completion_view();
+        assert_eq!(app.get_current_todos().len(), 2);
+    }
+
+    #[test]
+    fn test_confirmed_clear_completed_keeps_active_todos() {
+        let mut app = create_test_app();
+        let active = Todo::new("Active".to_string(), String::new());
+        let active_id = active.id.clone();
+        let mut done = Todo::new("Done".to_string(), String::new());
+        done.toggle_completion();
+        let mut also_done = Todo::new("Also done".to_string(), String::new());
+        also_done.toggle_completion();
+        app.main_view.marked.insert(done.id.clone());
+        app.main_view.marked.insert(active_id.clone());
+        app.database.insert_todo_for_test(active);
+        app.database.insert_todo_for_test(done);
+        app.database.insert_todo_for_test(also_done);
+
+        app.confirm_clear_completed();
+        assert_eq!(app.pending_action, Some(ConfirmAction::ClearCompleted));
+        app.run_confirmed_action().unwrap();
+
+        let remaining = app.get_current_todos();
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].id, active_id);
+        assert!(app.confirm_dialog.is_none());
+        // Marks of deleted todos don't linger in the marked count
+        assert_eq!(app.main_view.marked.len(), 1);
+        assert!(app.main_view.marked.contains(&active_id));
+    }
+
+    #[test]
+    fn test_clear_completed_without_completed_todos_skips_dialog() {
+        let mut app = create_test_app();
+        app.database.insert_todo_for_test(Todo::new("Active".to_string(), String::new()));
+
+        app.confirm_clear_completed();
+
+        assert!(matches!(app.state, AppState::Main));
+        assert!(app.pending_action.is_none());
+    }
+
+    #[test]
+    fn test_complete_view_after_confirm() {
+        let mut app = create_test_app();
+        let project = Todo::new("Project: wrap up".to_string(), String::new());
+        let project_id = project.id.clone();
+        let other = Todo::new("Other".to_string(), String::new());
+        let other_id = other.id.clone();
+        app.database.insert_todo_for_test(project);
+        app.database.insert_todo_for_test(other);
+        app.filter.query = "project".to_string();
+
+        app.confirm_complete_view();
+        assert_eq!(app.pending_action, Some(ConfirmAction::CompleteAll(vec![project_id.clone()])));
+        app.run_confirmed_action().unwrap();
+
+        assert!(app.database.get_todo(&project_id).unwrap().is_completed());
+        assert!(!app.database.get_todo(&other_id).unwrap().is_completed());
+
+        // Nothing active left in the view, so there is nothing to confirm
+        app.confirm_complete_view();
+        assert!(matches!(app.state, AppState::Main));
+        assert!(app.pending_action.is_none());
+    }
+
+    #[test]
+    fn test_due_date_dialog_applies_to_current_view() {
+        let mut app = create_test_app();
+        let sprint = Todo::new("Sprint work".to_string(), String::new());
+        let sprint_id = sprint.id.clone();
+        let other = Todo::new("Other".to_string(), String::new());
+        let other_id = other.id.clone();
+        app.database.insert_todo_for_test(sprint);
+        app.database.insert_todo_for_test(other);
+        app.filter.query = "sprint".to_string();
+
+        app.prompt_due_date_for_view();
+        assert!(matches!(app.state, AppState::Input));
+        assert_eq!(app.pending_input, Some(InputAction::SetDueDate(vec![sprint_id.clone()])));
+
+        // A malformed date keeps the dialog open
+        app.input_dialog.as_mut().unwrap().value = "next week".to_string();
+        app.submit_input().unwrap();
+        assert!(matches!(app.state, AppState::Input));
+        assert!(app.input_dialog.as_ref().unwrap().error.is_some());
+
+        app.input_dialog.as_mut().unwrap().value = "2030-06-30".to_string();
+        app.submit_input().unwrap();
+        assert!(matches!(app.state, AppState::Main));
+        assert_eq!(
+            app.database.get_todo(&sprint_id).unwrap().due_date,
+            parse_due_date("2030-06-30").unwrap()
+        );
+        assert_eq!(app.database.get_todo(&other_id).unwrap().due_date, None);
+    }
+
+    #[test]
+    fn test_overdue_filter() {
+        let mut app = create_test_app();
+
+        let mut overdue = Todo::new("Overdue".to_string(), String::new());
+        overdue.due_date = Some(chrono::Utc::now() - chrono::Duration::days(1));
+        let mut completed = Todo::new("Completed late".to_string(), String::new());
+        completed.due_date = Some(chrono::Utc::now() - chrono::Duration::days(3));
+        completed.toggle_completion();
+        let no_due = Todo::new("No due date".to_string(), String::new());
+
+        app.database.insert_todo_for_test(overdue);
+        app.database.insert_todo_for_test(completed);
+        app.database.insert_todo_for_test(no_due);
+        assert_eq!(app.get_current_todos().len(), 3);
+
+        app.toggle_overdue_filter();
+        let todos = app.get_current_todos();
+        assert_eq!(todos.len(), 1);
+        assert_eq!(todos[0].subject, "Overdue");
+
+        app.toggle_overdue_filter();
+        assert_eq!(app.get_current_todos().len(), 3);
+    }
+
+    #[test]
+    fn test_switching_tabs() {
+        let mut app = create_test_app();
+
+        let active = Todo::new("Active".to_string(), String::new());
+        let mut completed = Todo::new("Completed".to_string(), String::new());
+        completed.toggle_completion();
+        app.database.insert_todo_for_test(active);
+        app.database.insert_todo_for_test(completed);
+
+        assert_eq!(app.filter.tab, ListTab::All);
+        assert_eq!(app.get_current_todos().len(), 2);
+
+        app.next_tab();
+        assert_eq!(app.filter.tab, ListTab::Active);
+        let todos = app.get_current_todos();
+        assert_eq!(todos.len(), 1);
+        assert_eq!(todos[0].subject, "Active");
+
+        app.next_tab();
+        assert_eq!(app.filter.tab, ListTab::Completed);
+        let todos = app.get_current_todos();
+        assert_eq!(todos.len(), 1);
+        assert_eq!(todos[0].subject, "Completed");
+
+        app.previous_tab();
+        app.previous_tab();
+        assert_eq!(app.filter.tab, ListTab::All);
+        assert_eq!(app.get_current_todos().len(), 2);
+    }
+
+    #[test]
+    fn test_search_debounce() {
+        let start = Instant::now();
+        let mut debounce = SearchDebounce::default();
+        assert!(!debounce.take_due(start));
+
+        // The first keystroke applies straight away
+        debounce.input_changed();
+        assert!(debounce.take_due(start));
+        assert!(!debounce.take_due(start));
+
+        // Further keystrokes wait for the interval to pass
+        debounce.input_changed();
+        assert!(!debounce.take_due(start + SEARCH_DEBOUNCE / 2));
+        debounce.input_changed();
+        assert!(debounce.take_due(start + SEARCH_DEBOUNCE));
+        assert!(!debounce.take_due(start + SEARCH_DEBOUNCE * 3));
+    }
+
+    #[test]
+    fn test_typed_search_is_applied_on_tick() {
+        let mut app = create_test_app();
+        app.database.insert_todo_for_test(Todo::new("Write docs".to_string(), String::new()));
+        app.database.insert_todo_for_test(Todo::new("Fix tests".to_string(), String::new()));
+
+        app.start_search();
+        for c in "tests".chars() {
+            app.push_search_char(c);
+        }
+        assert_eq!(app.search_input, "tests");
+        assert_eq!(app.filter.query, "t");
+
+        app.apply_search_if_due(Instant::now() + SEARCH_DEBOUNCE);
+        assert_eq!(app.filter.query, "tests");
+        assert_eq!(app.get_current_todos().len(), 1);
+    }
+
+    #[test]
+    fn test_search_case_toggle() {
+        let mut app = create_test_app();
+        app.database.insert_todo_for_test(Todo::new("Fix API bug".to_string(), String::new()));
+        app.database.insert_todo_for_test(Todo::new("Read about apis".to_string(), String::new()));
+
+        app.start_search();
+        assert!(matches!(app.state, AppState::Search));
+        for c in "API".chars() {
+            app.push_search_char(c);
+        }
+        assert_eq!(app.get_current_todos().len(), 2);
+
+        app.toggle_search_case();
+        let todos = app.get_current_todos();
+        assert_eq!(todos.len(), 1);
+        assert_eq!(todos[0].subject, "Fix API bug");
+
+        app.finish_search();
+        assert!(matches!(app.state, AppState::Main));
+        assert_eq!(app.get_current_todos().len(), 1);
+
+        app.start_search();
+        app.cancel_search();
+        assert!(app.filter.query.is_empty());
+        assert_eq!(app.get_current_todos().len(), 2);
+    }
+
+    fn add_todo_in_list(app: &mut App, subject: &str, list: &str) {
+        let mut todo = Todo::new(subject.to_string(), String::new());
+        todo.list = list.to_string();
+        app.database.insert_todo_for_test(todo);
+    }
+
+    #[test]
+    fn test_next_list_cycles_through_lists() {
+        let mut app = create_test_app();
+        add_todo_in_list(&mut app, "Inbox item", "Inbox");
+        add_todo_in_list(&mut app, "Work item", "Work");
+
+        assert_eq!(app.filter.list, None);
+        app.next_list();
+        assert_eq!(app.filter.list.as_deref(), Some("Inbox"));
+        app.next_list();
+        assert_eq!(app.filter.list.as_deref(), Some("Work"));
+        assert_eq!(app.get_current_todos().len(), 1);
+        app.next_list();
+        assert_eq!(app.filter.list, None);
+        assert_eq!(app.get_current_todos().len(), 2);
+    }
+
+    #[test]
+    fn test_list_selection_restored_when_switching_back() {
+        let mut app = create_test_app();
+        for i in 0..3 {
+            add_todo_in_list(&mut app, &format!("Work {}", i), "Work");
+        }
+        add_todo_in_list(&mut app, "Home 0", "Home");
+        add_todo_in_list(&mut app, "Home 1", "Home");
+
+        app.switch_to_list(Some("Work".to_string()));
+        app.main_view.table_state.select(Some(2));
+        *app.main_view.table_state.offset_mut() = 1;
+
+        app.switch_to_list(Some("Home".to_string()));
+        assert_eq!(app.main_view.selected_index(), Some(0));
+        app.main_view.table_state.select(Some(1));
+
+        app.switch_to_list(Some("Work".to_string()));
+        assert_eq!(app.main_view.selected_index(), Some(2));
+        assert_eq!(app.main_view.table_state.offset(), 1);
+
+        app.switch_to_list(Some("Home".to_string()));
+        assert_eq!(app.main_view.selected_index(), Some(1));
+    }
+
+    #[test]
+    fn test_new_todo_defaults_to_current_list() {
+        let mut app = create_test_app();
+        app.switch_to_list(Some("Work".to_string()));
+        app.open_new_todo();
+        assert_eq!(app.detail_view.as_ref().unwrap().list, "Work");
+    }
+
+    #[test]
+    fn test_edit_closed_at() {
+        let mut app = create_test_app();
+        let mut todo = Todo::new("Done".to_string(), String::new());
+        todo.created_at = chrono::Utc::now() - chrono::Duration::days(5);
+        todo.toggle_completion();
+        let todo_id = todo.id.clone();
+        app.database.insert_todo_for_test(todo);
+
+        app.open_edit_view();
+        let corrected = (chrono::Utc::now() - chrono::Duration::days(2))
+            .format("%Y-%m-%d %H:%M:%S")
+            .to_string();
+        app.detail_view.as_mut().unwrap().closed_date = corrected.clone();
+        app.save_current_todo().unwrap();
+
+        let saved = app.database.get_todo(&todo_id).unwrap();
+        assert_eq!(saved.closed_at.unwrap().format("%Y-%m-%d %H:%M:%S").to_string(), corrected);
+    }
+
+    #[test]
+    fn test_cycle_selected_priority() {
+        let mut app = create_test_app();
+        let todo = Todo::new("Test".to_string(), String::new());
+        let todo_id = todo.id.clone();
+        app.database.insert_todo_for_test(todo);
+        app.main_view.table_state.select(Some(0));
+
+        app.cycle_selected_priority().unwrap();
+        assert_eq!(app.database.get_todo(&todo_id).unwrap().priority, Priority::High);
+    }
+
+    #[test]
+    fn test_status_message_set_on_save_and_expires() {
+        let mut app = create_test_app();
+        app.open_new_todo();
+        app.detail_view.as_mut().unwrap().subject = "New".to_string();
+        app.save_current_todo().unwrap();
+
+        let (message, set_at) = app.status_message.clone().unwrap();
+        assert_eq!(message, "Saved");
+
+        app.expire_status_message(set_at + STATUS_MESSAGE_TIMEOUT / 2);
+        assert!(app.status_message.is_some());
+
+        app.expire_status_message(set_at + STATUS_MESSAGE_TIMEOUT);
+        assert!(app.status_message.is_none());
+    }
+
+    #[test]
+    fn test_sort_keys_update_order_and_prefs() {
+        let mut app = create_test_app();
+        app.cycle_sort_mode();
+        assert_eq!(app.database.sort_order().mode, crate::data::sort::SortMode::Created);
+
+        app.toggle_sort_direction().unwrap();
+        assert_eq!(app.database.sort_order().direction, crate::data::SortDirection::Descending);
+        assert_eq!(app.prefs.sort_direction, crate::data::SortDirection::Descending);
+        assert_eq!(app.status_message.as_ref().unwrap().0, "Sort: Created ↓");
+    }
+
+    #[test]
+    fn test_untouched_edit_keeps_last_modified() {
+        let mut app = create_test_app();
+        let todo = Todo::new("Stable".to_string(), "Unchanged".to_string());
+        let todo_id = todo.id.clone();
+        let last_modified = todo.last_modified_at;
+        app.database.insert_todo_for_test(todo);
+
+        app.open_edit_view();
+        app.save_and_close_detail_view().unwrap();
+        assert_eq!(app.database.get_todo(&todo_id).unwrap().last_modified_at, last_modified);
+
+        app.open_edit_view();
+        app.detail_view.as_mut().unwrap().due_date = "2030-01-01".to_string();
+        app.save_and_close_detail_view().unwrap();
+        assert!(app.database.get_todo(&todo_id).unwrap().last_modified_at > last_modified);
+    }
+
+    #[test]
+    fn test_new_todo_selected_after_save() {
+        let mut app = create_test_app();
+        for subject in ["First", "Second"] {
+            app.database.insert_todo_for_test(Todo::new(subject.to_string(), String::new()));
+        }
+        app.main_view.table_state.select(Some(0));
+
+        app.open_new_todo();
+        app.detail_view.as_mut().unwrap().subject = "Brand new".to_string();
+        app.save_current_todo().unwrap();
+
+        assert_eq!(app.get_selected_todo().unwrap().subject, "Brand new");
+        assert_eq!(app.main_view.table_state.selected(), Some(2));
+    }
+
+    #[test]
+    fn test_invalid_save_keeps_detail_view_open() {
+        let mut app = create_test_app();
+        app.open_new_todo();
+        app.detail_view.as_mut().unwrap().description = "Typed a lot".to_string();
+
+        app.save_current_todo().unwrap();
+
+        assert!(matches!(app.state, AppState::Detail));
+        let detail_view = app.detail_view.as_ref().unwrap();
+        assert!(detail_view.show_errors);
+        assert_eq!(detail_view.description, "Typed a lot");
+        assert!(app.database.get_all_todos().is_empty());
+    }
+
+    #[test]
+    fn test_save_in_place_keeps_editing() {
+        let mut app = create_test_app();
+        app.open_new_todo();
+        app.detail_view.as_mut().unwrap().subject = "Draft".to_string();
+
+        app.save_current_todo().unwrap();
+        assert!(matches!(app.state, AppState::Detail));
+        assert!(matches!(app.detail_view.as_ref().unwrap().mode, DetailMode::Edit));
+
+        // A second save updates the same todo rather than adding another
+        app.detail_view.as_mut().unwrap().subject = "Final".to_string();
+        app.save_current_todo().unwrap();
+        let todos = app.database.get_all_todos();
+        assert_eq!(todos.len(), 1);
+        assert_eq!(todos[0].subject, "Final");
+    }
+
+    #[test]
+    fn test_subject_trimmed_on_save() {
+        let mut app = create_test_app();
+        app.open_new_todo();
+        let detail_view = app.detail_view.as_mut().unwrap();
+        detail_view.subject = "  Buy milk  ".to_string();
+        detail_view.description = "  2 litres  ".to_string();
+
+        app.save_current_todo().unwrap();
+        let todo = app.database.get_all_todos()[0].clone();
+        assert_eq!(todo.subject, "Buy milk");
+        assert_eq!(todo.description, "  2 litres  ");
+
+        // Editing trims as well
+        app.open_edit_view();
+        app.detail_view.as_mut().unwrap().subject = "Buy oat milk ".to_string();
+        app.save_current_todo().unwrap();
+        assert_eq!(app.database.get_todo(&todo.id).unwrap().subject, "Buy oat milk");
+    }
+
+    #[test]
+    fn test_save_and_close() {
+        let mut app = create_test_app();
+        app.open_new_todo();
+        app.detail_view.as_mut().unwrap().subject = "Done editing".to_string();
+
+        app.save_and_close_detail_view().unwrap();
+        assert!(matches!(app.state, AppState::Main));
+        assert!(app.detail_view.is_none());
+        assert_eq!(app.database.get_all_todos()[0].subject, "Done editing");
+    }
+
+    #[test]
+    fn test_notes_saved_without_touching_description() {
+        let mut app = create_test_app();
+        let todo = Todo::new("Plan trip".to_string(), "Short summary".to_string());
+        let todo_id = todo.id.clone();
+        app.database.insert_todo_for_test(todo);
+
+        app.open_edit_view();
+        app.detail_view.as_mut().unwrap().notes = "Book hotel\nRent car".to_string();
+        app.save_and_close_detail_view().unwrap();
+
+        let saved = app.database.get_todo(&todo_id).unwrap();
+        assert_eq!(saved.notes, "Book hotel\nRent car");
+        assert_eq!(saved.description, "Short summary");
+        assert!(saved.last_modified_at > saved.created_at);
+    }
+
+    #[test]
+    fn test_new_todo_uses_default_priority() {
+        let mut app = create_test_app();
+        app.prefs.default_priority = Priority::High;
+
+        app.open_new_todo();
+        app.detail_view.as_mut().unwrap().subject = "Urgent by default".to_string();
+        app.save_and_close_detail_view().unwrap();
+
+        // Changing it in the form overrides the default
+        app.open_new_todo();
+        let detail_view = app.detail_view.as_mut().unwrap();
+        assert_eq!(detail_view.priority, Priority::High);
+        detail_view.subject = "Can wait".to_string();
+        detail_view.cycle_priority();
+        app.save_and_close_detail_view().unwrap();
+
+        let priority_of = |subject: &str| {
+            app.database
+                .get_all_todos()
+                .into_iter()
+                .find(|todo| todo.subject == subject)
+                .map(|todo| todo.priority)
+        };
+        assert_eq!(priority_of("Urgent by default"), Some(Priority::High));
+        assert_eq!(priority_of("Can wait"), Some(Priority::Low));
+    }
+
+    #[test]
+    fn test_close_discards_unsaved_edits() {
+        let mut app = create_test_app();
+        let todo = Todo::new("Original".to_string(), String::new());
+        let todo_id = todo.id.clone();
+        app.database.insert_todo_for_test(todo);
+
+        app.open_edit_view();
+        app.detail_view.as_mut().unwrap().subject = "Changed".to_string();
+        app.close_detail_view();
+
+        assert!(matches!(app.state, AppState::Main));
+        assert_eq!(app.database.get_todo(&todo_id).unwrap().subject, "Original");
+    }
+
+    #[test]
+    fn test_tags_saved_and_suggested() {
+        let mut app = create_test_app();
+        app.open_new_todo();
+        let detail_view = app.detail_view.as_mut().unwrap();
+        detail_view.subject = "Tagged".to_string();
+        detail_view.tags = "work, home".to_string();
+        app.save_and_close_detail_view().unwrap();
+
+        app.open_new_todo();
+        let detail_view = app.detail_view.as_mut().unwrap();
+        detail_view.tags = "wo".to_string();
+        assert_eq!(detail_view.tag_suggestions(), vec!["work"]);
+    }
+
+    #[test]
+    fn test_toggle_short_ids() {
+        let mut app = create_test_app();
+        app.toggle_short_ids().unwrap();
+        assert!(app.main_view.show_short_ids);
+        assert!(app.prefs.show_short_ids);
+    }
+
+    #[test]
+    fn test_adjust_daily_goal() {
+        let mut app = create_test_app();
+        assert_eq!(app.daily_progress(), None);
+
+        app.adjust_daily_goal(true).unwrap();
+        app.adjust_daily_goal(true).unwrap();
+        assert_eq!(app.daily_progress(), Some((0, 2)));
+
+        let mut todo = Todo::new("Done".to_string(), String::new());
+        todo.toggle_completion();
+        app.database.insert_todo_for_test(todo);
+        assert_eq!(app.daily_progress(), Some((1, 2)));
+
+        app.adjust_daily_goal(false).unwrap();
+        app.adjust_daily_goal(false).unwrap();
+        assert_eq!(app.daily_progress(), None);
+
+        // Lowering an unset goal keeps it off
+        app.adjust_daily_goal(false).unwrap();
+        assert_eq!(app.prefs.daily_goal, None);
+    }
+
+    #[test]
+    fn test_restore_selection() {
+        let mut app = create_test_app();
+
+        let mut first = Todo::new("First".to_string(), String::new());
+        first.last_modified_at = chrono::Utc::now() - chrono::Duration::hours(2);
+        let mut second = Todo::new("Second".to_string(), String::new());
+        second.last_modified_at = chrono::Utc::now() - chrono::Duration::hours(1);
+        let third = Todo::new("Third".to_string(), String::new());
+        let third_id = third.id.clone();
+        app.database.insert_todo_for_test(first);
+        app.database.insert_todo_for_test(second);
+        app.database.insert_todo_for_test(third);
+
+        app.main_view.table_state.select(Some(2));
+        app.remember_selection().unwrap();
+        assert_eq!(app.prefs.last_selected_id, Some(third_id));
+
+        app.main_view.table_state.select(Some(0));
+        app.restore_selection();
+        assert_eq!(app.main_view.selected_index(), Some(2));
+    }
+
+    #[test]
+    fn test_collapsed_completed_row_count() {
+        let mut app = create_test_app();
+        for subject in ["Open 1", "Open 2", "Done 1", "Done 2", "Done 3"] {
+            let mut todo = Todo::new(subject.to_string(), String::new());
+            if subject.starts_with("Done") {
+                todo.toggle_completion();
+            }
+            app.database.insert_todo_for_test(todo);
+        }
+        let row_count = |app: &App| {
+            crate::ui::table_row_count(&app.get_current_todo_refs(), app.collapsed_completed_count())
+        };
+
+        // Five todos plus the separator
+        assert_eq!(row_count(&app), 6);
+        assert_eq!(app.collapsed_completed_count(), None);
+
+        app.main_view.table_state.select(Some(4));
+        app.toggle_completed_collapsed();
+        // Two todos plus the "Completed (3)" summary
+        assert_eq!(app.collapsed_completed_count(), Some(3));
+        assert_eq!(row_count(&app), 3);
+        assert_eq!(app.main_view.selected_index(), Some(1));
+
+        // The Completed tab is never folded
+        app.next_tab();
+        app.next_tab();
+        assert_eq!(app.get_current_todo_refs().len(), 3);
+        assert_eq!(app.collapsed_completed_count(), None);
+    }
+
+    #[test]
+    fn test_restore_view_from_prefs() {
+        let mut app = create_test_app();
+        for (subject, priority, list) in [
+            ("Zebra report", Priority::High, "Work"),
+            ("Alpha review", Priority::High, "Work"),
+            ("Low work", Priority::Low, "Work"),
+            ("Home chore", Priority::High, "Inbox"),
+        ] {
+            let mut todo = Todo::new(subject.to_string(), String::new());
+            todo.priority = priority;
+            todo.list = list.to_string();
+            app.database.insert_todo_for_test(todo);
+        }
+        app.filter.list = Some("Work".to_string());
+        app.filter.priority = Some(Priority::High);
+        app.cycle_sort_mode();
+        app.cycle_sort_mode();
+        app.remember_selection().unwrap();
+
+        let mut restarted = App::with_database(std::mem::replace(&mut app.database, Database::in_memory()));
+        restarted.prefs = app.prefs.clone();
+        restarted.restore_view();
+        assert_eq!(restarted.database.sort_order().mode, crate::data::SortMode::Subject);
+        let subjects: Vec<String> = restarted
+            .get_current_todos()
+            .into_iter()
+            .map(|todo| todo.subject)
+            .collect();
+        assert_eq!(subjects, ["Alpha review", "Zebra report"]);
+    }
+
+    #[test]
+    fn test_restore_selection_with_stale_id() {
+        let mut app = create_test_app();
+        app.database.insert_todo_for_test(Todo::new("Only".to_string(), String::new()));
+        app.prefs.last_selected_id = Some("deleted-todo".to_string());
+        app.main_view.table_state.select(None);
+
+        app.restore_selection();
+        assert_eq!(app.main_view.selected_index(), Some(0));
     }
 
     #[test]
@@ -386,4 +2123,40 @@ mod tests {
         app.quit();
         assert!(app.should_quit);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_focus_remaining_time() {
+        let mut app = create_test_app();
+        app.database.insert_todo_for_test(Todo::new("Write report".to_string(), String::new()));
+        let start = Instant::now();
+        assert_eq!(app.focus_remaining(start), None);
+
+        app.toggle_focus(start);
+        assert_eq!(app.focus_remaining(start), Some(FOCUS_DURATION));
+        let later = start + Duration::from_secs(61);
+        assert_eq!(app.focus_remaining(later), Some(FOCUS_DURATION - Duration::from_secs(61)));
+        assert_eq!(app.focus_label(later).unwrap(), "🍅 23:59 Write report");
+
+        // Pressing again stops the session
+        app.toggle_focus(later);
+        assert_eq!(app.focus_remaining(later), None);
+    }
+
+    #[test]
+    fn test_focus_expires() {
+        let mut app = create_test_app();
+        app.database.insert_todo_for_test(Todo::new("Write report".to_string(), String::new()));
+        let start = Instant::now();
+        app.toggle_focus(start);
+
+        assert!(!app.update_focus(start + FOCUS_DURATION - Duration::from_secs(1)));
+        assert!(app.focus_until.is_some());
+
+        assert!(app.update_focus(start + FOCUS_DURATION));
+        assert!(app.focus_until.is_none());
+        assert!(app.focus_todo_id.is_none());
+        assert_eq!(app.status_message.as_ref().unwrap().0, "Focus session done");
+        // Only the expiring tick reports the end
+        assert!(!app.update_focus(start + FOCUS_DURATION * 2));
+    }
+}
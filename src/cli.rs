@@ -0,0 +1,674 @@
+use crate::data::{Counts, Database, Prefs, Priority, Subtask, Todo};
+use crate::ui::help;
+use anyhow::{bail, Context, Result};
+use chrono::NaiveDate;
+use serde::Deserialize;
+use std::io::Read;
+use std::path::PathBuf;
+
+/// What the binary should do, decided from the command line before any
+/// terminal setup happens.
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    Tui,
+    Batch,
+    Done {
+        id_prefix: String,
+    },
+    Import {
+        skip_duplicates: bool,
+    },
+    List {
+        since: Option<NaiveDate>,
+        until: Option<NaiveDate>,
+    },
+    Keys,
+    Dupes,
+    Count,
+    Notify,
+    Ics {
+        path: PathBuf,
+    },
+    TagReport {
+        path: PathBuf,
+    },
+    Stats {
+        json: bool,
+    },
+    Version,
+    Help,
+}
+
+/// Printed by `todocli --help`.
+pub const USAGE: &str = "\
+Usage: todocli [command]
+
+Without a command, starts the interactive todo list.
+
+Commands:
+  list [--since DATE] [--until DATE]  Print todos last modified in a range
+  keys                                Print every key binding
+  dupes                               Print todos sharing a subject
+  stats [--json]                      Print todo counts
+  count                               Print counts on one line for status bars
+  notify                              Print a line per overdue or due-soon todo
+  ics <file>                          Write due dates as an iCalendar feed
+  tag-report <file>                   Write todo counts per tag as CSV
+  done <id-prefix>                    Complete a todo
+  batch                               Apply JSON operations read from stdin
+  import [--skip-duplicates]          Add one todo per line read from stdin
+
+Options:
+  -h, --help     Print this help
+  -V, --version  Print the version
+";
+
+pub fn parse_args<I>(args: I) -> Result<Command>
+where
+    I: IntoIterator<Item = String>,
+{
+    let args: Vec<String> = args.into_iter().collect();
+    match args.first().map(String::as_str) {
+        None => Ok(Command::Tui),
+        Some("batch") => Ok(Command::Batch),
+        Some("done") => match &args[1..] {
+            [id_prefix] => Ok(Command::Done { id_prefix: id_prefix.clone() }),
+            _ => bail!("Usage: todocli done <id-prefix>"),
+        },
+        Some("import") => parse_import_args(&args[1..]),
+        Some("list") => parse_list_args(&args[1..]),
+        Some("keys") => Ok(Command::Keys),
+        Some("dupes") => Ok(Command::Dupes),
+        Some("count") => Ok(Command::Count),
+        Some("notify") => Ok(Command::Notify),
+        Some("ics") => match &args[1..] {
+            [path] => Ok(Command::Ics { path: PathBuf::from(path) }),
+            _ => bail!("Usage: todocli ics <file>"),
+        },
+        Some("tag-report") => match &args[1..] {
+            [path] => Ok(Command::TagReport { path: PathBuf::from(path) }),
+            _ => bail!("Usage: todocli tag-report <file>"),
+        },
+        Some("stats") => match &args[1..] {
+            [] => Ok(Command::Stats { json: false }),
+            [flag] if flag == "--json" => Ok(Command::Stats { json: true }),
+            _ => bail!("Usage: todocli stats [--json]"),
+        },
+        Some("--version" | "-V") => Ok(Command::Version),
+        Some("--help" | "-h") => Ok(Command::Help),
+        Some(other) => bail!("Unknown command: {}", other),
+    }
+}
+
+fn parse_list_args(args: &[String]) -> Result<Command> {
+    let mut since = None;
+    let mut until = None;
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        let target = match arg.as_str() {
+            "--since" => &mut since,
+            "--until" => &mut until,
+            other => bail!("Unknown option for list: {}", other),
+        };
+        let value = args.next().with_context(|| format!("{} needs a date", arg))?;
+        *target = Some(parse_date(value)?);
+    }
+
+    Ok(Command::List { since, until })
+}
+
+fn parse_import_args(args: &[String]) -> Result<Command> {
+    let mut skip_duplicates = false;
+    for arg in args {
+        match arg.as_str() {
+            "--skip-duplicates" => skip_duplicates = true,
+            other => bail!("Unknown option for import: {}", other),
+        }
+    }
+    Ok(Command::Import { skip_duplicates })
+}
+
+fn parse_date(value: &str) -> Result<NaiveDate> {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .with_context(|| format!("Invalid date '{}', expected YYYY-MM-DD", value))
+}
+
+pub fn run(command: Command) -> Result<()> {
+    match command {
+        Command::Tui => unreachable!("the TUI is started from main"),
+        Command::Batch => run_batch(),
+        Command::Done { id_prefix } => run_done(&id_prefix),
+        Command::Import { skip_duplicates } => run_import(skip_duplicates),
+        Command::List { since, until } => run_list(since, until),
+        Command::Keys => {
+            print!("{}", help::cheat_sheet());
+            Ok(())
+        }
+        Command::Dupes => run_dupes(),
+        Command::Notify => {
+            let database = Database::new()?;
+            for todo in notification_todos(&database, chrono::Utc::now()) {
+                println!("{}", todo.notification_summary());
+            }
+            Ok(())
+        }
+        Command::Count => {
+            println!("{}", count_line(Database::new()?.counts()));
+            Ok(())
+        }
+        Command::Ics { path } => Database::new()?.export_ics(&path),
+        Command::TagReport { path } => Database::new()?.export_tag_report(&path),
+        Command::Stats { json } => run_stats(json),
+        Command::Version => {
+            println!("todocli {}", env!("CARGO_PKG_VERSION"));
+            Ok(())
+        }
+        Command::Help => {
+            print!("{}", USAGE);
+            Ok(())
+        }
+    }
+}
+
+/// Keeps todos last modified within `since..=until` (UTC days). Either bound
+/// may be left open.
+pub fn filter_by_modified(
+    todos: Vec<&Todo>,
+    since: Option<NaiveDate>,
+    until: Option<NaiveDate>,
+) -> Vec<&Todo> {
+    todos
+        .into_iter()
+        .filter(|todo| {
+            let day = todo.last_modified_at.date_naive();
+            since.is_none_or(|since| day >= since) && until.is_none_or(|until| day <= until)
+        })
+        .collect()
+}
+
+fn run_list(since: Option<NaiveDate>, until: Option<NaiveDate>) -> Result<()> {
+    let database = Database::new()?;
+    for todo in filter_by_modified(database.get_all_todos(), since, until) {
+        let status = if todo.is_completed() { "x" } else { " " };
+        println!("[{}] {}  {}", status, todo.id, todo.subject);
+    }
+    Ok(())
+}
+
+/// A single change in a `todocli batch` JSON array, e.g.
+/// `{"op": "add", "subject": "Buy milk"}`.
+#[derive(Debug, Deserialize, PartialEq)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum Operation {
+    Add {
+        subject: String,
+        #[serde(default)]
+        description: String,
+        #[serde(default)]
+        attach: Vec<PathBuf>,
+        list: Option<String>,
+        priority: Option<Priority>,
+        #[serde(default)]
+        subtasks: Vec<String>,
+        remind_before_hours: Option<u32>,
+    },
+    Update {
+        id: String,
+        subject: Option<String>,
+        description: Option<String>,
+        completed: Option<bool>,
+        priority: Option<Priority>,
+        #[serde(default)]
+        attach: Vec<PathBuf>,
+        #[serde(default)]
+        detach: Vec<PathBuf>,
+        /// Index of a subtask to tick or untick.
+        toggle_subtask: Option<usize>,
+        remind_before_hours: Option<u32>,
+    },
+    Delete {
+        id: String,
+    },
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct BatchSummary {
+    pub added: usize,
+    pub updated: usize,
+    pub deleted: usize,
+    pub errors: Vec<String>,
+}
+
+pub fn parse_operations(input: &str) -> Result<Vec<Operation>> {
+    serde_json::from_str(input).context("Could not parse batch operations")
+}
+
+/// Gives `add` operations without a priority the configured default.
+pub fn fill_default_priority(operations: &mut [Operation], default: Priority) {
+    for operation in operations {
+        if let Operation::Add { priority, .. } = operation {
+            priority.get_or_insert(default);
+        }
+    }
+}
+
+/// Applies operations in order. Operations referring to unknown ids are
+/// recorded as errors without stopping the rest of the batch.
+pub fn apply_operations(database: &mut Database, operations: Vec<Operation>) -> Result<BatchSummary> {
+    let mut summary = BatchSummary::default();
+
+    for operation in operations {
+        match operation {
+            Operation::Add { subject, description, attach, list, priority, subtasks, remind_before_hours } => {
+                let mut todo = Todo::new(subject.trim().to_string(), description);
+                if let Some(list) = list {
+                    todo.list = list;
+                }
+                if let Some(priority) = priority {
+                    todo.priority = priority;
+                }
+                for path in attach {
+                    todo.add_attachment(path);
+                }
+                todo.subtasks = subtasks.into_iter().map(Subtask::new).collect();
+                todo.remind_before = remind_before_hours.map(|hours| chrono::Duration::hours(i64::from(hours)));
+                database.add_todo(todo)?;
+                summary.added += 1;
+            }
+            Operation::Update {
+                id,
+                subject,
+                description,
+                completed,
+                priority,
+                attach,
+                detach,
+                toggle_subtask,
+                remind_before_hours,
+            } => {
+                let Some(mut todo) = database.get_todo(&id).cloned() else {
+                    summary.errors.push(format!("update: no todo with id {}", id));
+                    continue;
+                };
+                if subject.is_some() || description.is_some() {
+                    todo.update(
+                        subject.map_or_else(|| todo.subject.clone(), |s| s.trim().to_string()),
+                        description.unwrap_or_else(|| todo.description.clone()),
+                    );
+                }
+                if let Some(completed) = completed {
+                    if completed != todo.is_completed() {
+                        todo.toggle_completion();
+                    }
+                }
+                if let Some(priority) = priority {
+                    todo.priority = priority;
+                }
+                if let Some(hours) = remind_before_hours {
+                    todo.remind_before = Some(chrono::Duration::hours(i64::from(hours)));
+                }
+                for path in attach {
+                    todo.add_attachment(path);
+                }
+                for path in detach {
+                    todo.remove_attachment(&path);
+                }
+                if let Some(index) = toggle_subtask {
+                    if !todo.toggle_subtask(index) {
+                        summary.errors.push(format!("update: todo {} has no subtask {}", id, index));
+                        continue;
+                    }
+                }
+                database.update_todo(todo)?;
+                summary.updated += 1;
+            }
+            Operation::Delete { id } => {
+                if database.get_todo(&id).is_none() {
+                    summary.errors.push(format!("delete: no todo with id {}", id));
+                    continue;
+                }
+                database.delete_todo(&id)?;
+                summary.deleted += 1;
+            }
+        }
+    }
+
+    Ok(summary)
+}
+
+fn run_batch() -> Result<()> {
+    let mut input = String::new();
+    std::io::stdin()
+        .read_to_string(&mut input)
+        .context("Could not read operations from stdin")?;
+
+    let prefs = Prefs::load();
+    let mut operations = parse_operations(&input)?;
+    fill_default_priority(&mut operations, prefs.default_priority);
+    let mut database = Database::open(prefs.storage)?;
+    database.assign_sequences = prefs.sequential_ids;
+    let summary = database.with_batch(|database| apply_operations(database, operations))?;
+
+    println!(
+        "Batch complete: {} added, {} updated, {} deleted",
+        summary.added, summary.updated, summary.deleted
+    );
+    for error in &summary.errors {
+        eprintln!("{}", error);
+    }
+
+    Ok(())
+}
+
+/// Marks the todo whose id starts with `id_prefix` as completed. Already
+/// completed todos are left as they are.
+pub fn complete_by_prefix(database: &mut Database, id_prefix: &str) -> Result<Todo> {
+    let id = database.resolve_id_prefix(id_prefix)?;
+    let mut todo = database
+        .get_todo(&id)
+        .cloned()
+        .with_context(|| format!("No todo with id {}", id))?;
+    if !todo.is_completed() {
+        todo.toggle_completion();
+        database.update_todo(todo.clone())?;
+    }
+    Ok(todo)
+}
+
+fn run_done(id_prefix: &str) -> Result<()> {
+    let mut database = Database::new()?;
+    let todo = complete_by_prefix(&mut database, id_prefix)?;
+    println!("[x] {}  {}", todo.id, todo.subject);
+    Ok(())
+}
+
+fn run_dupes() -> Result<()> {
+    let database = Database::new()?;
+    let groups = database.duplicate_groups();
+    if groups.is_empty() {
+        println!("No duplicates found");
+    }
+    for (index, group) in groups.iter().enumerate() {
+        if index > 0 {
+            println!();
+        }
+        for todo in group {
+            let status = if todo.is_completed() { "x" } else { " " };
+            println!("[{}] {}  {}", status, todo.id, todo.subject);
+        }
+    }
+    Ok(())
+}
+
+/// Active todos worth a reminder: overdue or within their lead time.
+pub fn notification_todos(database: &Database, now: chrono::DateTime<chrono::Utc>) -> Vec<&Todo> {
+    database.query(|todo| todo.is_overdue() || todo.is_due_soon(now))
+}
+
+/// `key=value` pairs that are easy to read and to pick apart in a script.
+pub fn count_line(counts: Counts) -> String {
+    format!(
+        "total={} active={} completed={} overdue={}",
+        counts.total, counts.active, counts.completed, counts.overdue
+    )
+}
+
+fn run_stats(json: bool) -> Result<()> {
+    let stats = Database::new()?.stats();
+    if json {
+        println!("{}", serde_json::to_string_pretty(&stats).context("Could not serialize stats")?);
+    } else {
+        println!("Total:           {}", stats.counts.total);
+        println!("Active:          {}", stats.counts.active);
+        println!("Completed:       {}", stats.counts.completed);
+        println!("Overdue:         {}", stats.counts.overdue);
+        println!("Due soon:        {}", stats.due_soon);
+        println!("Completed today: {}", stats.completed_today);
+    }
+    Ok(())
+}
+
+fn run_import(skip_duplicates: bool) -> Result<()> {
+    let mut input = String::new();
+    std::io::stdin()
+        .read_to_string(&mut input)
+        .context("Could not read lines from stdin")?;
+
+    let prefs = Prefs::load();
+    let mut database = Database::open(prefs.storage)?;
+    database.assign_sequences = prefs.sequential_ids;
+    let added = database.import_lines(&input, skip_duplicates)?;
+    println!("Imported {} todos", added);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_args() {
+        assert_eq!(parse_args(args(&[])).unwrap(), Command::Tui);
+        assert_eq!(parse_args(args(&["batch"])).unwrap(), Command::Batch);
+        assert_eq!(parse_args(args(&["keys"])).unwrap(), Command::Keys);
+        assert_eq!(parse_args(args(&["dupes"])).unwrap(), Command::Dupes);
+        assert_eq!(parse_args(args(&["count"])).unwrap(), Command::Count);
+        assert_eq!(parse_args(args(&["notify"])).unwrap(), Command::Notify);
+        assert_eq!(
+            parse_args(args(&["ics", "due.ics"])).unwrap(),
+            Command::Ics { path: PathBuf::from("due.ics") }
+        );
+        assert!(parse_args(args(&["ics"])).is_err());
+        assert_eq!(
+            parse_args(args(&["tag-report", "tags.csv"])).unwrap(),
+            Command::TagReport { path: PathBuf::from("tags.csv") }
+        );
+        assert!(parse_args(args(&["tag-report"])).is_err());
+        assert_eq!(parse_args(args(&["stats"])).unwrap(), Command::Stats { json: false });
+        assert_eq!(parse_args(args(&["stats", "--json"])).unwrap(), Command::Stats { json: true });
+        assert!(parse_args(args(&["stats", "--csv"])).is_err());
+        assert_eq!(parse_args(args(&["--version"])).unwrap(), Command::Version);
+        assert_eq!(parse_args(args(&["-V"])).unwrap(), Command::Version);
+        assert_eq!(parse_args(args(&["--help"])).unwrap(), Command::Help);
+        assert_eq!(parse_args(args(&["-h"])).unwrap(), Command::Help);
+        assert_eq!(
+            parse_args(args(&["import"])).unwrap(),
+            Command::Import { skip_duplicates: false }
+        );
+        assert_eq!(
+            parse_args(args(&["import", "--skip-duplicates"])).unwrap(),
+            Command::Import { skip_duplicates: true }
+        );
+        assert!(parse_args(args(&["import", "--force"])).is_err());
+        assert!(parse_args(args(&["frobnicate"])).is_err());
+        assert_eq!(
+            parse_args(args(&["done", "3f2a"])).unwrap(),
+            Command::Done { id_prefix: "3f2a".to_string() }
+        );
+        assert!(parse_args(args(&["done"])).is_err());
+    }
+
+    #[test]
+    fn test_parse_list_args() {
+        assert_eq!(
+            parse_args(args(&["list"])).unwrap(),
+            Command::List { since: None, until: None }
+        );
+        assert_eq!(
+            parse_args(args(&["list", "--until", "2024-03-31", "--since", "2024-03-01"])).unwrap(),
+            Command::List {
+                since: NaiveDate::from_ymd_opt(2024, 3, 1),
+                until: NaiveDate::from_ymd_opt(2024, 3, 31),
+            }
+        );
+        assert!(parse_args(args(&["list", "--since"])).is_err());
+        assert!(parse_args(args(&["list", "--since", "March"])).is_err());
+        assert!(parse_args(args(&["list", "--verbose"])).is_err());
+    }
+
+    fn todo_modified_on(subject: &str, date: &str) -> Todo {
+        let mut todo = Todo::new(subject.to_string(), String::new());
+        todo.last_modified_at = parse_date(date).unwrap().and_hms_opt(12, 0, 0).unwrap().and_utc();
+        todo
+    }
+
+    fn subjects(todos: Vec<&Todo>) -> Vec<&str> {
+        todos.into_iter().map(|todo| todo.subject.as_str()).collect()
+    }
+
+    #[test]
+    fn test_filter_by_modified_inclusive_bounds() {
+        let before = todo_modified_on("Before", "2024-02-29");
+        let first = todo_modified_on("First", "2024-03-01");
+        let last = todo_modified_on("Last", "2024-03-31");
+        let after = todo_modified_on("After", "2024-04-01");
+        let todos = vec![&before, &first, &last, &after];
+
+        let filtered = filter_by_modified(
+            todos,
+            NaiveDate::from_ymd_opt(2024, 3, 1),
+            NaiveDate::from_ymd_opt(2024, 3, 31),
+        );
+        assert_eq!(subjects(filtered), vec!["First", "Last"]);
+    }
+
+    #[test]
+    fn test_filter_by_modified_open_ended() {
+        let early = todo_modified_on("Early", "2024-01-10");
+        let late = todo_modified_on("Late", "2024-06-10");
+        let cutoff = NaiveDate::from_ymd_opt(2024, 3, 1);
+
+        let since_only = filter_by_modified(vec![&early, &late], cutoff, None);
+        assert_eq!(subjects(since_only), vec!["Late"]);
+
+        let until_only = filter_by_modified(vec![&early, &late], None, cutoff);
+        assert_eq!(subjects(until_only), vec!["Early"]);
+
+        let unbounded = filter_by_modified(vec![&early, &late], None, None);
+        assert_eq!(unbounded.len(), 2);
+    }
+
+    #[test]
+    fn test_parse_and_apply_mixed_operations() {
+        let mut database = Database::in_memory();
+        let existing = Todo::new("Existing".to_string(), "Old".to_string());
+        let existing_id = existing.id.clone();
+        let doomed = Todo::new("Doomed".to_string(), String::new());
+        let doomed_id = doomed.id.clone();
+        database.insert_todo_for_test(existing);
+        database.insert_todo_for_test(doomed);
+
+        let input = format!(
+            r#"[
+                {{"op": "add", "subject": "New todo", "description": "From batch"}},
+                {{"op": "add", "subject": "Bare todo"}},
+                {{"op": "update", "id": "{}", "description": "New", "completed": true, "priority": "high", "attach": ["/tmp/spec.md"]}},
+                {{"op": "delete", "id": "{}"}},
+                {{"op": "delete", "id": "missing"}}
+            ]"#,
+            existing_id, doomed_id
+        );
+
+        let operations = parse_operations(&input).unwrap();
+        assert_eq!(operations.len(), 5);
+        assert_eq!(
+            operations[1],
+            Operation::Add {
+                subject: "Bare todo".to_string(),
+                description: String::new(),
+                attach: Vec::new(),
+                list: None,
+                priority: None,
+                subtasks: Vec::new(),
+                remind_before_hours: None,
+            }
+        );
+
+        let summary = apply_operations(&mut database, operations).unwrap();
+        assert_eq!(summary.added, 2);
+        assert_eq!(summary.updated, 1);
+        assert_eq!(summary.deleted, 1);
+        assert_eq!(summary.errors.len(), 1);
+
+        let updated = database.get_todo(&existing_id).unwrap();
+        assert_eq!(updated.subject, "Existing");
+        assert_eq!(updated.description, "New");
+        assert!(updated.is_completed());
+        assert_eq!(updated.priority, Priority::High);
+        assert_eq!(updated.attachments, vec![PathBuf::from("/tmp/spec.md")]);
+        assert!(database.get_todo(&doomed_id).is_none());
+        assert_eq!(database.get_all_todos().len(), 3);
+    }
+
+    #[test]
+    fn test_fill_default_priority_keeps_explicit_priority() {
+        let mut operations = parse_operations(
+            r#"[
+                {"op": "add", "subject": "Defaulted"},
+                {"op": "add", "subject": "Explicit", "priority": "low"}
+            ]"#,
+        )
+        .unwrap();
+        fill_default_priority(&mut operations, Priority::High);
+
+        let priorities: Vec<Option<Priority>> = operations
+            .iter()
+            .map(|operation| match operation {
+                Operation::Add { priority, .. } => *priority,
+                _ => None,
+            })
+            .collect();
+        assert_eq!(priorities, vec![Some(Priority::High), Some(Priority::Low)]);
+    }
+
+    #[test]
+    fn test_parse_operations_rejects_unknown_op() {
+        assert!(parse_operations(r#"[{"op": "rename", "id": "x"}]"#).is_err());
+    }
+
+    #[test]
+    fn test_complete_by_prefix() {
+        let mut database = Database::in_memory();
+        let todo = Todo::new("Finish me".to_string(), String::new());
+        let id = database.add_todo(todo).unwrap();
+
+        let completed = complete_by_prefix(&mut database, &id[..8]).unwrap();
+        assert_eq!(completed.id, id);
+        assert!(database.get_todo(&id).unwrap().is_completed());
+
+        // Completing again keeps it completed
+        complete_by_prefix(&mut database, &id[..8]).unwrap();
+        assert!(database.get_todo(&id).unwrap().is_completed());
+        assert!(complete_by_prefix(&mut database, "not-an-id").is_err());
+    }
+
+    #[test]
+    fn test_count_line() {
+        let counts = Counts { total: 4, active: 3, completed: 1, overdue: 2 };
+        assert_eq!(count_line(counts), "total=4 active=3 completed=1 overdue=2");
+    }
+
+    #[test]
+    fn test_notification_todos() {
+        let now = chrono::Utc::now();
+        let mut database = Database::in_memory();
+        let mut late = Todo::new("Late".to_string(), String::new());
+        late.due_date = Some(now - chrono::Duration::hours(1));
+        let mut soon = Todo::new("Soon".to_string(), String::new());
+        soon.due_date = Some(now + chrono::Duration::hours(1));
+        let mut later = Todo::new("Later".to_string(), String::new());
+        later.due_date = Some(now + chrono::Duration::days(7));
+        for todo in [late, soon, later, Todo::new("Undated".to_string(), String::new())] {
+            database.insert_todo_for_test(todo);
+        }
+
+        let mut subjects: Vec<&str> = notification_todos(&database, now).iter().map(|todo| todo.subject.as_str()).collect();
+        subjects.sort();
+        assert_eq!(subjects, ["Late", "Soon"]);
+    }
+}
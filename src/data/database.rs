@@ -1,98 +1,674 @@
-use crate::data::Todo;
-use anyhow::{Context, Result};
-use std::collections::HashMap;
+use crate::data::history::{self, HistoryAction};
+use crate::data::storage::Storage;
+use crate::data::tags::normalize_tag;
+use crate::data::{paths, Prefs, SortOrder, StorageFormat, Todo};
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+
+/// What happens to completed todos once they are old enough.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum RetentionAction {
+    /// Move them to `archive.json` next to the database.
+    Archive,
+    Delete,
+}
+
+/// Cleanup applied at startup to todos completed more than `days` days ago.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RetentionPolicy {
+    pub action: RetentionAction,
+    pub days: u32,
+}
+
+/// Database size above which archiving old todos is suggested.
+pub const LARGE_FILE_BYTES: u64 = 5 * 1024 * 1024;
+
+/// Whether to warn about a database file of `size` bytes. The warning is
+/// shown once, until the file shrinks back under `LARGE_FILE_BYTES`.
+pub fn size_warning_due(size: u64, already_warned: bool) -> bool {
+    size > LARGE_FILE_BYTES && !already_warned
+}
+
+/// Todo counts, as printed by `todocli stats`.
+#[derive(Serialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Stats {
+    #[serde(flatten)]
+    pub counts: Counts,
+    pub due_soon: usize,
+    pub completed_today: usize,
+}
+
+/// The basic counts, gathered in one pass for status bars (`todocli
+/// count`).
+#[derive(Serialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Counts {
+    pub total: usize,
+    pub active: usize,
+    pub completed: usize,
+    pub overdue: usize,
+}
+
+/// How many todos carry a tag, and how many of those are done.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TagCount {
+    pub tag: String,
+    pub total: usize,
+    pub completed: usize,
+}
+
+impl TagCount {
+    pub fn active(&self) -> usize {
+        self.total - self.completed
+    }
+}
 
 pub struct Database {
-    file_path: PathBuf,
+    /// Where todos are saved; `None` keeps them in memory only.
+    file_path: Option<PathBuf>,
+    /// Reads and writes `file_path` in the format chosen with the `storage`
+    /// pref.
+    storage: Box<dyn Storage>,
     todos: HashMap<String, Todo>,
+    sort_order: SortOrder,
+    /// Set inside `with_batch`; saves are deferred until it finishes.
+    batching: bool,
+    /// Todos changed since the last load or write; `save` skips the write
+    /// otherwise.
+    dirty: bool,
+    /// How many times the file has been written.
+    writes: usize,
+    /// Last sequential id handed out per list. Kept in `sequences.json` so
+    /// numbers of deleted todos are never reused.
+    sequences: BTreeMap<String, u64>,
+    /// Give new todos a sequential id (see `next_sequence`).
+    pub assign_sequences: bool,
+    /// Why the last write failed, until picked up with `take_save_failure`.
+    save_failure: Option<String>,
 }
 
 impl Database {
+    /// Opens the database in the app directory, in the format chosen with
+    /// the `storage` pref. Callers that already hold the prefs use `open`.
     pub fn new() -> Result<Self> {
-        let config_dir = dirs::config_dir()
-            .context("Could not find config directory")?
-            .join("todo");
+        Self::open(Prefs::load().storage)
+    }
+
+    /// Opens the database in the app directory, stored as `format`.
+    pub fn open(format: StorageFormat) -> Result<Self> {
+        let app_dir = paths::app_dir();
         
-        fs::create_dir_all(&config_dir)
+        fs::create_dir_all(&app_dir)
             .context("Could not create config directory")?;
         
-        let file_path = config_dir.join("todo.gdbm");
+        let file_path = app_dir.join(format.file_name());
         
         let mut db = Self {
-            file_path,
+            file_path: Some(file_path),
+            storage: format.storage(),
             todos: HashMap::new(),
+            sort_order: SortOrder::default(),
+            batching: false,
+            dirty: false,
+            writes: 0,
+            sequences: BTreeMap::new(),
+            assign_sequences: false,
+            save_failure: None,
         };
         
         db.load()?;
+        db.adopt_other_file(format)?;
         Ok(db)
     }
 
+    /// A database that never touches disk: `load` and `save` are no-ops.
+    pub fn in_memory() -> Self {
+        Self {
+            file_path: None,
+            storage: StorageFormat::default().storage(),
+            todos: HashMap::new(),
+            sort_order: SortOrder::default(),
+            batching: false,
+            dirty: false,
+            writes: 0,
+            sequences: BTreeMap::new(),
+            assign_sequences: false,
+            save_failure: None,
+        }
+    }
+
     pub fn load(&mut self) -> Result<()> {
-        if self.file_path.exists() {
-            let content = fs::read(&self.file_path)
-                .context("Could not read database file")?;
-            
-            if !content.is_empty() {
-                self.todos = bincode::deserialize(&content)
-                    .context("Could not deserialize database file")?;
-            }
+        let Some(file_path) = &self.file_path else {
+            return Ok(());
+        };
+        if file_path.exists() {
+            self.todos = self.storage.load(file_path)?;
         }
+        if let Some(path) = self.sequences_path().filter(|path| path.exists()) {
+            let content = fs::read(path).context("Could not read sequences file")?;
+            self.sequences = serde_json::from_slice(&content).context("Could not parse sequences file")?;
+        }
+        self.dirty = false;
         Ok(())
     }
 
-    pub fn save(&self) -> Result<()> {
-        let content = bincode::serialize(&self.todos)
-            .context("Could not serialize todos")?;
-        
-        fs::write(&self.file_path, content)
-            .context("Could not write database file")?;
+    pub fn save(&mut self) -> Result<()> {
+        if self.batching || !self.dirty {
+            return Ok(());
+        }
+        let Some(file_path) = self.file_path.clone() else {
+            return Ok(());
+        };
+        // On failure the todos stay in memory and dirty, so the next save
+        // retries both files
+        if let Err(err) = self.storage.save(&file_path, &self.todos) {
+            return Err(self.save_failed(&file_path, err)).context("Could not write database file");
+        }
+        if !self.sequences.is_empty() {
+            let sequences_path = file_path.with_file_name("sequences.json");
+            let written = serde_json::to_vec(&self.sequences)
+                .map_err(anyhow::Error::from)
+                .and_then(|sequences| Ok(fs::write(&sequences_path, sequences)?));
+            if let Err(err) = written {
+                return Err(self.save_failed(&sequences_path, err)).context("Could not write sequences file");
+            }
+        }
+        crate::logging::log("save", &format!("{} todos to {}", self.todos.len(), file_path.display()));
+        self.writes += 1;
+        self.dirty = false;
         
         Ok(())
     }
 
-    pub fn add_todo(&mut self, todo: Todo) -> Result<()> {
-        self.todos.insert(todo.id.clone(), todo);
-        self.save()
+    /// Records that `path` could not be written, for `take_save_failure`.
+    fn save_failed(&mut self, path: &Path, err: anyhow::Error) -> anyhow::Error {
+        self.save_failure = Some(format!("{} ({:#})", path.display(), err));
+        err
+    }
+
+    /// Takes the reason the last save could not write the file, if it
+    /// failed. Callers can then carry on with the changes kept in memory.
+    pub fn take_save_failure(&mut self) -> Option<String> {
+        self.save_failure.take()
+    }
+
+    /// Whether there are changes that haven't been written yet, e.g.
+    /// because the last save failed.
+    pub fn has_unsaved_changes(&self) -> bool {
+        self.dirty
+    }
+
+    /// After switching the `storage` pref, picks up the todos from the file
+    /// of the other format while there is none in `format` yet. Once they
+    /// are written in `format`, the old file is renamed to `<name>.bak`, so
+    /// switching back later doesn't reopen its stale todos; if the write
+    /// fails it is kept and adopted again next time.
+    fn adopt_other_file(&mut self, format: StorageFormat) -> Result<()> {
+        let Some(file_path) = self.file_path.clone() else {
+            return Ok(());
+        };
+        let other = format.other();
+        let other_path = file_path.with_file_name(other.file_name());
+        if file_path.exists() || !other_path.exists() {
+            return Ok(());
+        }
+        self.todos = other.storage().load(&other_path)?;
+        self.dirty = true;
+        if self.save().is_ok() {
+            let backup = file_path.with_file_name(format!("{}.bak", other.file_name()));
+            fs::rename(&other_path, backup).context("Could not move the old database file aside")?;
+        }
+        Ok(())
+    }
+
+    /// Runs `f` with saves deferred, then writes the file once if anything
+    /// changed. Changes made before `f` fails are still saved, just as they
+    /// would have been without batching. Batches can nest; only the
+    /// outermost one writes.
+    pub fn with_batch<T>(&mut self, f: impl FnOnce(&mut Self) -> Result<T>) -> Result<T> {
+        let nested = std::mem::replace(&mut self.batching, true);
+        let result = f(self);
+        self.batching = nested;
+        if !nested {
+            self.save()?;
+        }
+        result
+    }
+
+    /// Stores a new todo and returns its id.
+    pub fn add_todo(&mut self, mut todo: Todo) -> Result<String> {
+        self.assign_sequence(&mut todo);
+        let id = todo.id.clone();
+        self.todos.insert(id.clone(), todo);
+        self.dirty = true;
+        self.save()?;
+        Ok(id)
     }
 
+    /// Stores changes to a todo. Completing or reopening it is also
+    /// appended to `history.log` next to the database file.
     pub fn update_todo(&mut self, todo: Todo) -> Result<()> {
-        self.todos.insert(todo.id.clone(), todo);
-        self.save()
+        let was_completed = self.todos.get(&todo.id).map(Todo::is_completed);
+        let action = match (was_completed, todo.is_completed()) {
+            (Some(false), true) => Some(HistoryAction::Completed),
+            (Some(true), false) => Some(HistoryAction::Reopened),
+            _ => None,
+        };
+        let id = todo.id.clone();
+        self.todos.insert(id.clone(), todo);
+        self.dirty = true;
+        self.save()?;
+
+        if let (Some(action), Some(path)) = (action, self.history_path()) {
+            history::log_event(&path, &id, action)?;
+        }
+        Ok(())
+    }
+
+    /// Size of the database file in bytes; 0 when in memory or not yet
+    /// written.
+    pub fn file_size(&self) -> u64 {
+        self.file_path
+            .as_ref()
+            .and_then(|path| fs::metadata(path).ok())
+            .map_or(0, |metadata| metadata.len())
+    }
+
+    /// Where completion history is logged; `None` for in-memory databases.
+    pub fn history_path(&self) -> Option<PathBuf> {
+        let file_path = self.file_path.as_ref()?;
+        Some(file_path.with_file_name("history.log"))
+    }
+
+    /// Where archived todos are kept; `None` for in-memory databases, which
+    /// simply drop them.
+    pub fn archive_path(&self) -> Option<PathBuf> {
+        let file_path = self.file_path.as_ref()?;
+        Some(file_path.with_file_name("archive.json"))
+    }
+
+    /// Where the sequential id counters are kept; `None` for in-memory
+    /// databases.
+    pub fn sequences_path(&self) -> Option<PathBuf> {
+        let file_path = self.file_path.as_ref()?;
+        Some(file_path.with_file_name("sequences.json"))
+    }
+
+    /// Hands out the next sequential number for `list`, starting at 1.
+    /// Numbers only go up, so those of deleted todos are never reused.
+    /// Lists that share an id prefix (see `sequence_prefix`), such as
+    /// `Work` and `work`, share a counter.
+    pub fn next_sequence(&mut self, list: &str) -> u64 {
+        let counter = self.sequences.entry(sequence_prefix(list)).or_insert(0);
+        *counter += 1;
+        self.dirty = true;
+        *counter
+    }
+
+    /// Gives a new todo a sequential id such as `work-3` when
+    /// `assign_sequences` is on.
+    fn assign_sequence(&mut self, todo: &mut Todo) {
+        if self.assign_sequences && todo.seq_id.is_none() {
+            let number = self.next_sequence(&todo.list);
+            todo.seq_id = Some(format!("{}-{}", sequence_prefix(&todo.list), number));
+        }
+    }
+
+    /// Text file next to the database that other programs can append
+    /// quick captures to, one todo per line.
+    pub fn inbox_path(&self) -> Option<PathBuf> {
+        let file_path = self.file_path.as_ref()?;
+        Some(file_path.with_file_name("inbox.txt"))
+    }
+
+    /// Imports every line of the inbox file at `path` as a new todo (see
+    /// `import_lines`). The file is moved aside before it is read, so lines
+    /// appended meanwhile start a new inbox, and deleted afterwards even if
+    /// saving the todos failed: they are kept in memory like any unsaved
+    /// change rather than imported again. A missing file imports nothing.
+    /// Returns how many todos were created.
+    pub fn drain_inbox(&mut self, path: &Path) -> Result<usize> {
+        let claimed = path.with_extension("txt.importing");
+        match fs::rename(path, &claimed) {
+            Ok(()) => {}
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+            Err(err) => return Err(self.save_failed(path, err.into())).context("Could not move inbox file aside"),
+        }
+        let text = fs::read_to_string(&claimed).context("Could not read inbox file")?;
+        let added = self.import_lines(&text, false);
+        if let Err(err) = fs::remove_file(&claimed) {
+            return Err(self.save_failed(&claimed, err.into())).context("Could not remove imported inbox file");
+        }
+        added
+    }
+
+    /// Archives or deletes todos completed more than `policy.days` days
+    /// before `now`. Returns how many were removed from the database.
+    pub fn cleanup_completed(&mut self, policy: RetentionPolicy, now: DateTime<Utc>) -> Result<usize> {
+        let cutoff = now - chrono::Duration::days(i64::from(policy.days));
+        let expired: Vec<Todo> = self
+            .query(|todo| matches!(todo.closed_at, Some(closed) if closed < cutoff))
+            .into_iter()
+            .cloned()
+            .collect();
+        if expired.is_empty() {
+            return Ok(0);
+        }
+
+        // Removed only once they are archived, so a failed archive write
+        // keeps them in the database
+        if let (RetentionAction::Archive, Some(path)) = (policy.action, self.archive_path()) {
+            let content = archive_with(&path, &expired)?;
+            if let Err(err) = fs::write(&path, content) {
+                return Err(self.save_failed(&path, err.into())).context("Could not write archive file");
+            }
+        }
+        for todo in &expired {
+            self.todos.remove(&todo.id);
+        }
+        self.dirty = true;
+        self.save()?;
+        Ok(expired.len())
     }
 
     pub fn delete_todo(&mut self, id: &str) -> Result<()> {
-        self.todos.remove(id);
+        if self.todos.remove(id).is_some() {
+            self.dirty = true;
+        }
         self.save()
     }
 
+    /// Deletes every completed todo with a single save. Returns how many
+    /// were removed.
+    pub fn delete_completed(&mut self) -> Result<usize> {
+        let before = self.todos.len();
+        self.todos.retain(|_, todo| !todo.is_completed());
+        let removed = before - self.todos.len();
+        if removed > 0 {
+            self.dirty = true;
+            self.save()?;
+        }
+        Ok(removed)
+    }
+
+    /// Sets (or with `None` clears) the due date of every todo in `ids`,
+    /// saving once. Unknown ids are skipped. Returns how many todos were
+    /// updated.
+    pub fn set_due_date_for(&mut self, ids: &[String], due_date: Option<DateTime<Utc>>) -> Result<usize> {
+        let now = Utc::now();
+        let mut updated = 0;
+        for id in ids {
+            if let Some(todo) = self.todos.get_mut(id) {
+                todo.due_date = due_date;
+                todo.last_modified_at = now;
+                updated += 1;
+            }
+        }
+        if updated > 0 {
+            self.dirty = true;
+            self.save()?;
+        }
+        Ok(updated)
+    }
+
+    /// Completes every active todo in `ids`, saving once, and appends each
+    /// to `history.log` like `update_todo`. Unknown and already completed
+    /// ids are skipped. Returns how many were completed.
+    pub fn complete_all(&mut self, ids: &[String]) -> Result<usize> {
+        let mut completed = Vec::new();
+        for id in ids {
+            if let Some(todo) = self.todos.get_mut(id).filter(|todo| !todo.is_completed()) {
+                todo.toggle_completion();
+                completed.push(id);
+            }
+        }
+        if completed.is_empty() {
+            return Ok(0);
+        }
+        self.dirty = true;
+        self.save()?;
+
+        if let Some(path) = self.history_path() {
+            for id in &completed {
+                history::log_event(&path, id, HistoryAction::Completed)?;
+            }
+        }
+        Ok(completed.len())
+    }
+
+    /// Creates one todo per non-empty line of `text`, using the trimmed line
+    /// as its subject, and saves once at the end. With `skip_duplicates`,
+    /// lines matching an active todo's subject (see `find_by_subject`) are
+    /// left out. Returns how many todos were created.
+    pub fn import_lines(&mut self, text: &str, skip_duplicates: bool) -> Result<usize> {
+        let mut added = 0;
+        for line in text.lines().map(str::trim).filter(|line| !line.is_empty()) {
+            if skip_duplicates && self.find_by_subject(line).is_some() {
+                continue;
+            }
+            let mut todo = Todo::new(line.to_string(), String::new());
+            self.assign_sequence(&mut todo);
+            self.todos.insert(todo.id.clone(), todo);
+            added += 1;
+        }
+        if added > 0 {
+            self.dirty = true;
+            self.save()?;
+        }
+        Ok(added)
+    }
+
+    /// Finds an active todo whose subject matches `subject`, ignoring case
+    /// and surrounding whitespace.
+    pub fn find_by_subject(&self, subject: &str) -> Option<&Todo> {
+        let subject = subject.trim().to_lowercase();
+        self.todos
+            .values()
+            .find(|todo| !todo.is_completed() && todo.subject.trim().to_lowercase() == subject)
+    }
+
+    /// Groups of two or more todos whose subjects match ignoring case and
+    /// extra whitespace, ordered by subject. Each group keeps the
+    /// `get_all_todos` order.
+    pub fn duplicate_groups(&self) -> Vec<Vec<&Todo>> {
+        let mut groups: BTreeMap<String, Vec<&Todo>> = BTreeMap::new();
+        for todo in self.get_all_todos() {
+            let key = todo.subject.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase();
+            groups.entry(key).or_default().push(todo);
+        }
+        groups.into_values().filter(|group| group.len() > 1).collect()
+    }
+
     pub fn get_todo(&self, id: &str) -> Option<&Todo> {
         self.todos.get(id)
     }
 
+    pub fn sort_order(&self) -> SortOrder {
+        self.sort_order
+    }
+
+    pub fn set_sort_order(&mut self, sort_order: SortOrder) {
+        self.sort_order = sort_order;
+    }
+
+    /// Expands a unique id prefix to the full id, so todos can be referred
+    /// to without typing the whole UUID.
+    pub fn resolve_id_prefix(&self, prefix: &str) -> Result<String> {
+        if prefix.is_empty() {
+            bail!("No todo id given");
+        }
+        let mut sequential = self
+            .todos
+            .values()
+            .filter(|todo| todo.seq_id.as_deref().is_some_and(|seq_id| seq_id.eq_ignore_ascii_case(prefix)));
+        match (sequential.next(), sequential.next()) {
+            (Some(todo), None) => return Ok(todo.id.clone()),
+            (Some(_), Some(_)) => bail!("Id '{}' matches more than one todo", prefix),
+            (None, _) => {}
+        }
+        let mut matches = self.todos.keys().filter(|id| id.starts_with(prefix));
+        match (matches.next(), matches.next()) {
+            (Some(id), None) => Ok(id.clone()),
+            (Some(_), Some(_)) => bail!("Id prefix '{}' matches more than one todo", prefix),
+            (None, _) => bail!("No todo with id starting with '{}'", prefix),
+        }
+    }
+
+    /// All todos, active ones first, each group ordered by the current
+    /// `SortOrder` (oldest modification first by default).
     pub fn get_all_todos(&self) -> Vec<&Todo> {
-        let mut todos: Vec<&Todo> = self.todos.values().collect();
-        // Sort with active (incomplete) todos first, then completed todos
-        // Within each group, sort by last_modified_at ascending (oldest first)
-        todos.sort_by(|a, b| {
-            match (a.is_completed(), b.is_completed()) {
-                (false, true) => std::cmp::Ordering::Less,  // active before completed
-                (true, false) => std::cmp::Ordering::Greater, // completed after active
-                _ => a.last_modified_at.cmp(&b.last_modified_at), // same completion status, sort by date ascending
-            }
-        });
+        self.query(|_| true)
+    }
+
+    /// Todos matching `pred`, in the same order as `get_all_todos`.
+    pub fn query<F: Fn(&Todo) -> bool>(&self, pred: F) -> Vec<&Todo> {
+        let mut todos: Vec<&Todo> = self.todos.values().filter(|todo| pred(todo)).collect();
+        todos.sort_by(|a, b| self.sort_order.compare(a, b));
         todos
     }
 
+    /// Names of all lists that hold at least one todo, sorted.
+    pub fn list_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.todos.values().map(|todo| todo.list.clone()).collect();
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    /// Every tag in use with the number of todos carrying it, most used
+    /// first.
+    pub fn tag_counts(&self) -> Vec<(String, usize)> {
+        self.tag_breakdown()
+            .into_iter()
+            .map(|count| (count.tag, count.total))
+            .collect()
+    }
+
+    /// Every tag in use with its total and completed todo counts, most used
+    /// first and alphabetical among equals. A todo with several tags counts
+    /// towards each of them.
+    pub fn tag_breakdown(&self) -> Vec<TagCount> {
+        let mut counts: HashMap<&str, TagCount> = HashMap::new();
+        for todo in self.todos.values() {
+            for tag in &todo.tags {
+                let count = counts.entry(tag.as_str()).or_default();
+                count.total += 1;
+                if todo.is_completed() {
+                    count.completed += 1;
+                }
+            }
+        }
+        let mut counts: Vec<TagCount> = counts
+            .into_iter()
+            .map(|(tag, count)| TagCount { tag: tag.to_string(), ..count })
+            .collect();
+        counts.sort_by(|a, b| b.total.cmp(&a.total).then_with(|| a.tag.cmp(&b.tag)));
+        counts
+    }
+
+    /// The `limit` most recently completed todos, newest first.
+    pub fn recently_completed(&self, limit: usize) -> Vec<&Todo> {
+        let mut completed = self.query(Todo::is_completed);
+        completed.sort_by_key(|todo| std::cmp::Reverse(todo.closed_at));
+        completed.truncate(limit);
+        completed
+    }
+
+    pub fn counts(&self) -> Counts {
+        let mut counts = Counts::default();
+        for todo in self.todos.values() {
+            counts.total += 1;
+            if todo.is_completed() {
+                counts.completed += 1;
+            } else {
+                counts.active += 1;
+            }
+            if todo.is_overdue() {
+                counts.overdue += 1;
+            }
+        }
+        counts
+    }
+
+    pub fn stats(&self) -> Stats {
+        Stats {
+            counts: self.counts(),
+            due_soon: self.due_soon_count(Utc::now()),
+            completed_today: self.completed_today(),
+        }
+    }
+
+    /// Active todos within their reminder lead time of the due date (see
+    /// `Todo::is_due_soon`).
+    pub fn due_soon_count(&self, now: DateTime<Utc>) -> usize {
+        self.query(|todo| todo.is_due_soon(now)).len()
+    }
+
+    pub fn completed_today(&self) -> usize {
+        self.completed_on(Utc::now().date_naive())
+    }
+
+    /// Counts todos whose `closed_at` falls on `date` (UTC).
+    pub fn completed_on(&self, date: NaiveDate) -> usize {
+        self.query(|todo| matches!(todo.closed_at, Some(closed) if closed.date_naive() == date))
+            .len()
+    }
+
+    /// Writes an iCalendar feed with an all-day event on the due date of
+    /// each active todo, for subscribing to from a calendar app.
+    pub fn export_ics(&self, path: &Path) -> Result<()> {
+        fs::write(path, self.ics_calendar(Utc::now())).context("Could not write calendar file")
+    }
+
+    /// Writes a CSV with a row of total, completed and active counts per tag
+    /// (see `tag_breakdown`), to show which areas have a backlog.
+    pub fn export_tag_report(&self, path: &Path) -> Result<()> {
+        fs::write(path, self.tag_report_csv()).context("Could not write tag report")
+    }
+
+    fn tag_report_csv(&self) -> String {
+        let mut csv = String::from("tag,total,completed,active\n");
+        for count in self.tag_breakdown() {
+            csv.push_str(&format!(
+                "{},{},{},{}\n",
+                csv_field(&count.tag),
+                count.total,
+                count.completed,
+                count.active()
+            ));
+        }
+        csv
+    }
+
+    fn ics_calendar(&self, now: DateTime<Utc>) -> String {
+        let stamp = now.format("%Y%m%dT%H%M%SZ");
+        let mut ics = String::from("BEGIN:VCALENDAR\r\nVERSION:2.0\r\nPRODID:-//todocli//EN\r\n");
+        for todo in self.query(|todo| !todo.is_completed()) {
+            let Some(due) = todo.due_date else {
+                continue;
+            };
+            let day = due.date_naive();
+            ics.push_str("BEGIN:VEVENT\r\n");
+            ics.push_str(&format!("UID:{}@todocli\r\n", todo.id));
+            ics.push_str(&format!("DTSTAMP:{}\r\n", stamp));
+            ics.push_str(&format!("DTSTART;VALUE=DATE:{}\r\n", day.format("%Y%m%d")));
+            if let Some(next_day) = day.succ_opt() {
+                ics.push_str(&format!("DTEND;VALUE=DATE:{}\r\n", next_day.format("%Y%m%d")));
+            }
+            ics.push_str(&format!("SUMMARY:{}\r\n", escape_ics_text(&todo.subject)));
+            ics.push_str("END:VEVENT\r\n");
+        }
+        ics.push_str("END:VCALENDAR\r\n");
+        ics
+    }
 
     #[cfg(test)]
-    pub fn new_in_memory() -> Result<Self> {
-        // Create a database that doesn't persist to disk for testing
-        Ok(Self {
-            file_path: std::path::PathBuf::from("/tmp/test_todo.gdbm"),
-            todos: HashMap::new(),
-        })
+    pub fn set_file_path_for_test(&mut self, path: PathBuf) {
+        self.file_path = Some(path);
     }
 
     #[cfg(test)]
@@ -102,19 +678,100 @@ impl Database {
     }
 }
 
+/// Start of the sequential ids of `list`: the list name as a tag (see
+/// `normalize_tag`), or `todo` when nothing is left of it.
+fn sequence_prefix(list: &str) -> String {
+    normalize_tag(list).unwrap_or_else(|| "todo".to_string())
+}
+
+/// Quotes a CSV field when it contains a comma, quote or line break.
+fn csv_field(text: &str) -> String {
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+/// Escapes backslashes, semicolons, commas and newlines in an iCalendar
+/// TEXT value (RFC 5545, section 3.3.11).
+fn escape_ics_text(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            ';' => escaped.push_str("\\;"),
+            ',' => escaped.push_str("\\,"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Contents of the archive file at `path` with `todos` added, starting a
+/// new archive if there is none yet.
+fn archive_with(path: &Path, todos: &[Todo]) -> Result<Vec<u8>> {
+    let mut archive: HashMap<String, Todo> = if path.exists() {
+        let content = fs::read(path).context("Could not read archive file")?;
+        serde_json::from_slice(&content).context("Could not parse archive file")?
+    } else {
+        HashMap::new()
+    };
+    archive.extend(todos.iter().map(|todo| (todo.id.clone(), todo.clone())));
+    serde_json::to_vec(&archive).context("Could not serialize archive")
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::data::Todo;
+    use crate::data::sort::SortMode;
+    use crate::data::{Priority, SortDirection, Todo, DEFAULT_LIST};
 
     fn create_test_database() -> Database {
-        Database::new_in_memory().unwrap()
+        Database::in_memory()
     }
 
     fn create_test_todo(subject: &str, description: &str) -> Todo {
         Todo::new(subject.to_string(), description.to_string())
     }
 
+    /// A database saved as `todo.gdbm` in its own temp directory, which is
+    /// removed on drop even if the test fails.
+    struct TempDatabase {
+        db: Database,
+        dir: PathBuf,
+    }
+
+    impl std::ops::Deref for TempDatabase {
+        type Target = Database;
+
+        fn deref(&self) -> &Database {
+            &self.db
+        }
+    }
+
+    impl std::ops::DerefMut for TempDatabase {
+        fn deref_mut(&mut self) -> &mut Database {
+            &mut self.db
+        }
+    }
+
+    impl Drop for TempDatabase {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.dir);
+        }
+    }
+
+    fn temp_database() -> TempDatabase {
+        let dir = std::env::temp_dir().join(format!("todocli-db-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let mut db = Database::in_memory();
+        db.file_path = Some(dir.join("todo.gdbm"));
+        TempDatabase { db, dir }
+    }
+
     #[test]
     fn test_database_creation() {
         let db = create_test_database();
@@ -145,6 +802,36 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_add_todo_returns_id() {
+        let mut db = Database::in_memory();
+        let id = db.add_todo(create_test_todo("New", "")).unwrap();
+
+        let stored = db.get_todo(&id).unwrap();
+        assert_eq!(stored.id, id);
+        assert_eq!(stored.subject, "New");
+    }
+
+    #[test]
+    fn test_resolve_id_prefix() {
+        let mut db = create_test_database();
+        for id in ["abc123", "abd456", "xyz789"] {
+            let mut todo = create_test_todo(id, "");
+            todo.id = id.to_string();
+            db.insert_todo_for_test(todo);
+        }
+
+        assert_eq!(db.resolve_id_prefix("abc").unwrap(), "abc123");
+        assert_eq!(db.resolve_id_prefix("xyz789").unwrap(), "xyz789");
+
+        let ambiguous = db.resolve_id_prefix("ab").unwrap_err();
+        assert!(ambiguous.to_string().contains("more than one"));
+
+        let missing = db.resolve_id_prefix("nope").unwrap_err();
+        assert!(missing.to_string().contains("No todo"));
+        assert!(db.resolve_id_prefix("").is_err());
+    }
+
     #[test]
     fn test_get_todo() {
         let mut db = create_test_database();
@@ -196,6 +883,34 @@ mod tests {
         assert!(db.get_todo(&todo_id).is_none());
     }
 
+    #[test]
+    fn test_query_by_predicate() {
+        let mut db = create_test_database();
+        let mut done = create_test_todo("Done", "");
+        done.toggle_completion();
+        let mut tagged = create_test_todo("Tagged", "");
+        tagged.tags = vec!["home".to_string()];
+        tagged.last_modified_at = chrono::Utc::now() - chrono::Duration::hours(1);
+        let mut late = create_test_todo("Late", "");
+        late.due_date = Some(chrono::Utc::now() - chrono::Duration::days(1));
+        late.tags = vec!["home".to_string()];
+        db.insert_todo_for_test(done);
+        db.insert_todo_for_test(tagged);
+        db.insert_todo_for_test(late);
+
+        let subjects = |todos: Vec<&Todo>| -> Vec<String> {
+            todos.iter().map(|todo| todo.subject.clone()).collect()
+        };
+        assert_eq!(subjects(db.query(Todo::is_completed)), ["Done"]);
+        assert_eq!(subjects(db.query(Todo::is_overdue)), ["Late"]);
+        // Results keep the usual sort order
+        assert_eq!(
+            subjects(db.query(|todo| todo.tags.iter().any(|tag| tag == "home"))),
+            ["Tagged", "Late"]
+        );
+        assert!(db.query(|todo| todo.subject.is_empty()).is_empty());
+    }
+
     #[test]
     fn test_get_all_todos_sorting() {
         let mut db = create_test_database();
@@ -229,4 +944,777 @@ mod tests {
         // Check that active todos are sorted by last_modified_at ascending (oldest first)
         assert!(all_todos[0].last_modified_at <= all_todos[1].last_modified_at);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_completed_today() {
+        let mut db = create_test_database();
+        let now = Utc::now();
+
+        let mut done_today = create_test_todo("Done today", "");
+        done_today.closed_at = Some(now);
+        let mut done_yesterday = create_test_todo("Done yesterday", "");
+        done_yesterday.closed_at = Some(now - chrono::Duration::days(1));
+        let active = create_test_todo("Active", "");
+
+        db.insert_todo_for_test(done_today);
+        db.insert_todo_for_test(done_yesterday);
+        db.insert_todo_for_test(active);
+
+        assert_eq!(db.completed_today(), 1);
+    }
+
+    #[test]
+    fn test_completed_on_uses_utc_day_boundaries() {
+        let mut db = create_test_database();
+        let day = NaiveDate::from_ymd_opt(2024, 3, 15).unwrap();
+
+        let mut start_of_day = create_test_todo("Start", "");
+        start_of_day.closed_at = Some(day.and_hms_opt(0, 0, 0).unwrap().and_utc());
+        let mut end_of_day = create_test_todo("End", "");
+        end_of_day.closed_at = Some(day.and_hms_opt(23, 59, 59).unwrap().and_utc());
+        let mut next_day = create_test_todo("Next", "");
+        next_day.closed_at = Some(day.succ_opt().unwrap().and_hms_opt(0, 0, 0).unwrap().and_utc());
+
+        db.insert_todo_for_test(start_of_day);
+        db.insert_todo_for_test(end_of_day);
+        db.insert_todo_for_test(next_day);
+
+        assert_eq!(db.completed_on(day), 2);
+    }
+
+    #[test]
+    fn test_list_names() {
+        let mut db = create_test_database();
+        let mut work = create_test_todo("Work item", "");
+        work.list = "Work".to_string();
+        let mut more_work = create_test_todo("More work", "");
+        more_work.list = "Work".to_string();
+
+        db.insert_todo_for_test(work);
+        db.insert_todo_for_test(more_work);
+        db.insert_todo_for_test(create_test_todo("Inbox item", ""));
+
+        assert_eq!(db.list_names(), vec!["Inbox".to_string(), "Work".to_string()]);
+    }
+
+    #[test]
+    fn test_tag_counts() {
+        let mut db = create_test_database();
+        for tags in [vec!["work", "urgent"], vec!["work"], vec!["home"]] {
+            let mut todo = create_test_todo("Tagged", "");
+            todo.tags = tags.into_iter().map(String::from).collect();
+            db.insert_todo_for_test(todo);
+        }
+
+        assert_eq!(
+            db.tag_counts(),
+            vec![("work".to_string(), 2), ("home".to_string(), 1), ("urgent".to_string(), 1)]
+        );
+    }
+
+    #[test]
+    fn test_tag_breakdown_with_overlapping_tags() {
+        let mut db = create_test_database();
+        for (tags, done) in [
+            (vec!["work", "urgent"], true),
+            (vec!["work", "urgent"], false),
+            (vec!["work"], false),
+            (vec!["home", "urgent"], true),
+        ] {
+            let mut todo = create_test_todo("Tagged", "");
+            todo.tags = tags.into_iter().map(String::from).collect();
+            if done {
+                todo.toggle_completion();
+            }
+            db.insert_todo_for_test(todo);
+        }
+        db.insert_todo_for_test(create_test_todo("Untagged", ""));
+
+        let breakdown = db.tag_breakdown();
+        let row = |tag: &str| breakdown.iter().find(|count| count.tag == tag).unwrap();
+        assert_eq!(breakdown.len(), 3);
+        assert_eq!((row("work").total, row("work").completed, row("work").active()), (3, 1, 2));
+        assert_eq!((row("urgent").total, row("urgent").completed, row("urgent").active()), (3, 2, 1));
+        assert_eq!((row("home").total, row("home").completed, row("home").active()), (1, 1, 0));
+
+        assert_eq!(
+            db.tag_report_csv(),
+            "tag,total,completed,active\nurgent,3,2,1\nwork,3,1,2\nhome,1,1,0\n"
+        );
+    }
+
+    #[test]
+    fn test_csv_field_quoting() {
+        assert_eq!(csv_field("plain"), "plain");
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+    }
+
+    #[test]
+    fn test_import_lines() {
+        let mut db = create_test_database();
+        let added = db.import_lines("  Buy milk  \n\n\tCall mum\n   \nPay rent", false).unwrap();
+
+        assert_eq!(added, 3);
+        let mut subjects: Vec<&str> = db.todos.values().map(|todo| todo.subject.as_str()).collect();
+        subjects.sort();
+        assert_eq!(subjects, vec!["Buy milk", "Call mum", "Pay rent"]);
+    }
+
+    #[test]
+    fn test_import_lines_empty_input() {
+        let mut db = create_test_database();
+        assert_eq!(db.import_lines("\n  \n", false).unwrap(), 0);
+        assert!(db.todos.is_empty());
+    }
+
+    #[test]
+    fn test_find_by_subject_ignores_case_and_completed() {
+        let mut db = create_test_database();
+        let mut done = create_test_todo("Old chore", "");
+        done.toggle_completion();
+        db.insert_todo_for_test(done);
+        db.insert_todo_for_test(create_test_todo("Buy Milk", ""));
+
+        assert!(db.find_by_subject("buy milk").is_some());
+        assert!(db.find_by_subject("Old chore").is_none());
+        assert!(db.find_by_subject("Buy bread").is_none());
+    }
+
+    #[test]
+    fn test_import_lines_skips_duplicates() {
+        let mut db = create_test_database();
+        db.insert_todo_for_test(create_test_todo("Buy milk", ""));
+        let mut done = create_test_todo("Pay rent", "");
+        done.toggle_completion();
+        db.insert_todo_for_test(done);
+
+        let added = db.import_lines("BUY MILK\nPay rent\nCall mum\ncall mum", true).unwrap();
+
+        // Completed todos don't count as duplicates; repeated lines are created once
+        assert_eq!(added, 2);
+        assert_eq!(db.todos.len(), 4);
+        assert_eq!(db.todos.values().filter(|todo| todo.subject.eq_ignore_ascii_case("call mum")).count(), 1);
+    }
+
+    fn sort_fixture() -> Database {
+        let mut db = create_test_database();
+        let base = Utc::now() - chrono::Duration::days(10);
+        for (offset, subject, priority, completed) in [
+            (0, "banana", Priority::High, false),
+            (1, "Apple", Priority::Low, false),
+            (2, "cherry", Priority::Medium, false),
+            (3, "Done", Priority::High, true),
+        ] {
+            let mut todo = create_test_todo(subject, "");
+            todo.created_at = base + chrono::Duration::days(offset);
+            // Modification order is the reverse of creation order
+            todo.last_modified_at = base + chrono::Duration::days(10 - offset);
+            todo.priority = priority;
+            if completed {
+                todo.closed_at = Some(todo.last_modified_at);
+            }
+            db.insert_todo_for_test(todo);
+        }
+        db
+    }
+
+    fn sorted_subjects(db: &mut Database, mode: SortMode, direction: SortDirection) -> Vec<String> {
+        db.set_sort_order(SortOrder { mode, direction, ..SortOrder::default() });
+        db.get_all_todos().iter().map(|todo| todo.subject.clone()).collect()
+    }
+
+    #[test]
+    fn test_sort_modes_honor_direction() {
+        let mut db = sort_fixture();
+        let cases = [
+            (SortMode::Modified, ["cherry", "Apple", "banana"]),
+            (SortMode::Created, ["banana", "Apple", "cherry"]),
+            (SortMode::Subject, ["Apple", "banana", "cherry"]),
+            (SortMode::Priority, ["Apple", "cherry", "banana"]),
+        ];
+
+        for (mode, ascending) in cases {
+            let mut expected: Vec<String> = ascending.iter().map(|s| s.to_string()).collect();
+            expected.push("Done".to_string());
+            assert_eq!(sorted_subjects(&mut db, mode, SortDirection::Ascending), expected, "{:?}", mode);
+
+            expected.pop();
+            expected.reverse();
+            expected.push("Done".to_string());
+            assert_eq!(sorted_subjects(&mut db, mode, SortDirection::Descending), expected, "{:?}", mode);
+        }
+    }
+
+    #[test]
+    fn test_in_memory_crud_without_disk() {
+        let mut db = Database::in_memory();
+        let mut todo = create_test_todo("In memory", "");
+        let todo_id = todo.id.clone();
+
+        db.add_todo(todo.clone()).unwrap();
+        assert_eq!(db.get_todo(&todo_id).unwrap().subject, "In memory");
+
+        todo.update("Renamed".to_string(), String::new());
+        db.update_todo(todo).unwrap();
+        assert_eq!(db.get_todo(&todo_id).unwrap().subject, "Renamed");
+
+        db.delete_todo(&todo_id).unwrap();
+        assert!(db.get_todo(&todo_id).is_none());
+        assert!(db.file_path.is_none());
+        assert!(db.history_path().is_none());
+    }
+
+    #[test]
+    fn test_update_todo_logs_completion_history() {
+        let mut db = temp_database();
+
+        let mut todo = create_test_todo("Logged", "");
+        db.add_todo(todo.clone()).unwrap();
+        todo.toggle_completion();
+        db.update_todo(todo.clone()).unwrap();
+        todo.update("Renamed".to_string(), String::new());
+        db.update_todo(todo.clone()).unwrap();
+        todo.toggle_completion();
+        db.update_todo(todo.clone()).unwrap();
+
+        let log = fs::read_to_string(db.history_path().unwrap()).unwrap();
+        let actions: Vec<&str> = log.lines().map(|line| line.split(' ').nth(1).unwrap()).collect();
+        assert_eq!(actions, vec!["completed", "reopened"]);
+    }
+
+    #[test]
+    fn test_batch_writes_file_once() {
+        let mut db = temp_database();
+
+        let ids = db
+            .with_batch(|db| {
+                (0..5)
+                    .map(|n| db.add_todo(create_test_todo(&format!("Todo {}", n), "")))
+                    .collect::<Result<Vec<_>>>()
+            })
+            .unwrap();
+        assert_eq!(db.writes, 1);
+
+        let mut reloaded = Database::in_memory();
+        reloaded.file_path = db.file_path.clone();
+        reloaded.load().unwrap();
+        assert_eq!(reloaded.todos.len(), 5);
+        assert!(ids.iter().all(|id| reloaded.get_todo(id).is_some()));
+
+        // A batch that changes nothing doesn't write
+        db.with_batch(|_| Ok(())).unwrap();
+        assert_eq!(db.writes, 1);
+    }
+
+    #[test]
+    fn test_save_skips_unchanged_database() {
+        let mut db = temp_database();
+
+        let id = db.add_todo(create_test_todo("Saved once", "")).unwrap();
+        db.save().unwrap();
+        db.save().unwrap();
+        assert_eq!(db.writes, 1);
+
+        // Deleting an unknown id changes nothing either
+        db.delete_todo("missing").unwrap();
+        assert_eq!(db.writes, 1);
+
+        db.delete_todo(&id).unwrap();
+        assert_eq!(db.writes, 2);
+    }
+
+    #[test]
+    fn test_set_due_date_for_given_ids_only() {
+        let mut db = create_test_database();
+        let ids: Vec<String> = ["Sprint task", "Another sprint task", "Backlog"]
+            .iter()
+            .map(|subject| db.add_todo(create_test_todo(subject, "")).unwrap())
+            .collect();
+        let due = Utc::now() + chrono::Duration::days(14);
+
+        let sprint = ids[..2].to_vec();
+        let updated = db
+            .set_due_date_for(&[sprint.clone(), vec!["missing".to_string()]].concat(), Some(due))
+            .unwrap();
+        assert_eq!(updated, 2);
+        assert!(sprint.iter().all(|id| db.get_todo(id).unwrap().due_date == Some(due)));
+        assert_eq!(db.get_todo(&ids[2]).unwrap().due_date, None);
+
+        db.set_due_date_for(&sprint, None).unwrap();
+        assert!(sprint.iter().all(|id| db.get_todo(id).unwrap().due_date.is_none()));
+    }
+
+    #[test]
+    fn test_complete_all_given_ids_only() {
+        let mut db = temp_database();
+        let ids: Vec<String> = ["Pack", "Ship", "Unrelated"]
+            .iter()
+            .map(|subject| db.add_todo(create_test_todo(subject, "")).unwrap())
+            .collect();
+        let mut earlier = create_test_todo("Already done", "");
+        let closed = Utc::now() - chrono::Duration::days(2);
+        earlier.closed_at = Some(closed);
+        let earlier_id = earlier.id.clone();
+        db.insert_todo_for_test(earlier);
+
+        let project = vec![ids[0].clone(), ids[1].clone(), earlier_id.clone()];
+        assert_eq!(db.complete_all(&project).unwrap(), 2);
+        assert!(ids[..2].iter().all(|id| db.get_todo(id).unwrap().closed_at.is_some()));
+        assert!(db.get_todo(&ids[2]).unwrap().closed_at.is_none());
+        assert_eq!(db.get_todo(&earlier_id).unwrap().closed_at, Some(closed));
+
+        let log = fs::read_to_string(db.history_path().unwrap()).unwrap();
+        let mut logged: Vec<(&str, &str)> = log
+            .lines()
+            .map(|line| {
+                let mut fields = line.split(' ').skip(1);
+                (fields.next().unwrap(), fields.next().unwrap())
+            })
+            .collect();
+        logged.sort();
+        let mut expected = vec![("completed", ids[0].as_str()), ("completed", ids[1].as_str())];
+        expected.sort();
+        assert_eq!(logged, expected);
+
+        assert_eq!(db.complete_all(&project).unwrap(), 0);
+        assert_eq!(fs::read_to_string(db.history_path().unwrap()).unwrap(), log);
+    }
+
+    #[test]
+    fn test_duplicate_groups() {
+        let mut db = create_test_database();
+        for subject in ["Buy milk", "buy  MILK ", "Call mum", "Pay rent", "pay rent"] {
+            db.insert_todo_for_test(create_test_todo(subject, ""));
+        }
+
+        let groups = db.duplicate_groups();
+        assert_eq!(groups.len(), 2);
+        let mut milk: Vec<&str> = groups[0].iter().map(|todo| todo.subject.as_str()).collect();
+        milk.sort();
+        assert_eq!(milk, ["Buy milk", "buy  MILK "]);
+        assert_eq!(groups[1].len(), 2);
+        assert!(groups.iter().flatten().all(|todo| todo.subject != "Call mum"));
+    }
+
+    #[test]
+    fn test_due_soon_count_uses_each_lead_time() {
+        let mut db = create_test_database();
+        let now = Utc::now();
+        for (subject, lead) in [("Three day lead", chrono::Duration::days(3)), ("One hour lead", chrono::Duration::hours(1))] {
+            let mut todo = create_test_todo(subject, "");
+            todo.due_date = Some(now + chrono::Duration::days(2));
+            todo.remind_before = Some(lead);
+            db.insert_todo_for_test(todo);
+        }
+
+        assert_eq!(db.due_soon_count(now), 1);
+        assert_eq!(db.due_soon_count(now + chrono::Duration::days(2) - chrono::Duration::minutes(30)), 2);
+    }
+
+    #[test]
+    fn test_stats_serialize_to_json() {
+        let mut db = create_test_database();
+        let mut late = create_test_todo("Late", "");
+        late.due_date = Some(Utc::now() - chrono::Duration::days(1));
+        let mut done_today = create_test_todo("Done today", "");
+        done_today.toggle_completion();
+        let mut done_earlier = create_test_todo("Done earlier", "");
+        done_earlier.closed_at = Some(Utc::now() - chrono::Duration::days(3));
+        db.insert_todo_for_test(late);
+        db.insert_todo_for_test(create_test_todo("Open", ""));
+        db.insert_todo_for_test(done_today);
+        db.insert_todo_for_test(done_earlier);
+
+        let json: serde_json::Value = serde_json::to_value(db.stats()).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "total": 4,
+                "active": 2,
+                "completed": 2,
+                "overdue": 1,
+                "due_soon": 0,
+                "completed_today": 1,
+            })
+        );
+    }
+
+    #[test]
+    fn test_drain_inbox() {
+        let mut db = temp_database();
+        let inbox = db.inbox_path().unwrap();
+        assert_eq!(db.drain_inbox(&inbox).unwrap(), 0);
+
+        fs::write(&inbox, "Call plumber\n\n  Renew passport \n").unwrap();
+        assert_eq!(db.drain_inbox(&inbox).unwrap(), 2);
+        let mut subjects: Vec<String> = db.get_all_todos().iter().map(|todo| todo.subject.clone()).collect();
+        subjects.sort();
+        assert_eq!(subjects, ["Call plumber", "Renew passport"]);
+        assert!(!inbox.exists());
+        assert!(!inbox.with_extension("txt.importing").exists());
+
+        // A drained inbox adds nothing the next time
+        assert_eq!(db.drain_inbox(&inbox).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_drain_inbox_records_failure_to_move_it() {
+        let mut db = temp_database();
+        let inbox = db.inbox_path().unwrap();
+        fs::write(&inbox, "Call plumber\n").unwrap();
+        // A directory in the way makes moving the inbox aside fail
+        fs::create_dir(inbox.with_extension("txt.importing")).unwrap();
+        fs::write(inbox.with_extension("txt.importing").join("keep"), "").unwrap();
+
+        assert!(db.drain_inbox(&inbox).is_err());
+        let failure = db.take_save_failure().unwrap();
+        assert!(failure.contains("inbox.txt"), "{}", failure);
+        assert!(inbox.exists());
+    }
+
+    #[test]
+    fn test_drain_inbox_not_repeated_after_failed_save() {
+        let mut db = temp_database();
+        // A directory where the database should go makes saving fail
+        fs::create_dir(db.dir.join("todo.gdbm")).unwrap();
+        let inbox = db.inbox_path().unwrap();
+        fs::write(&inbox, "Call plumber\n").unwrap();
+
+        assert!(db.drain_inbox(&inbox).is_err());
+        assert!(db.take_save_failure().is_some());
+        assert_eq!(db.get_all_todos().len(), 1);
+        assert!(!inbox.exists());
+        assert_eq!(db.drain_inbox(&inbox).unwrap(), 0);
+        assert_eq!(db.get_all_todos().len(), 1);
+    }
+
+    #[test]
+    fn test_sequences_increment_and_survive_deletion() {
+        let mut db = create_test_database();
+        db.assign_sequences = true;
+        let add = |db: &mut Database, subject: &str, list: &str| {
+            let mut todo = create_test_todo(subject, "");
+            todo.list = list.to_string();
+            let id = db.add_todo(todo).unwrap();
+            (id.clone(), db.get_todo(&id).unwrap().seq_id.clone().unwrap())
+        };
+
+        assert_eq!(add(&mut db, "First", "Work").1, "work-1");
+        let (latest, seq_id) = add(&mut db, "Second", "Work");
+        assert_eq!(seq_id, "work-2");
+        assert_eq!(add(&mut db, "Elsewhere", "Side Project").1, "side-project-1");
+
+        // Deleting the latest doesn't free its number
+        db.delete_todo(&latest).unwrap();
+        assert_eq!(add(&mut db, "Third", "Work").1, "work-3");
+        assert_eq!(db.next_sequence("Work"), 4);
+    }
+
+    #[test]
+    fn test_sequences_saved_with_database() {
+        let mut db = temp_database();
+        db.assign_sequences = true;
+        let id = db.add_todo(create_test_todo("Numbered", "")).unwrap();
+        db.delete_todo(&id).unwrap();
+
+        let mut reloaded = create_test_database();
+        reloaded.file_path = db.file_path.clone();
+        reloaded.load().unwrap();
+        assert_eq!(reloaded.next_sequence(DEFAULT_LIST), 2);
+    }
+
+    #[test]
+    fn test_switching_storage_adopts_the_other_file_both_ways() {
+        let mut json = temp_database();
+        let dir = json.dir.clone();
+        let mut todo = create_test_todo("Kept", "");
+        todo.due_date = Some(Utc::now());
+        let id = json.add_todo(todo).unwrap();
+
+        let open = |format: StorageFormat| {
+            let mut db = create_test_database();
+            db.storage = format.storage();
+            db.file_path = Some(dir.join(format.file_name()));
+            db.load().unwrap();
+            db.adopt_other_file(format).unwrap();
+            db
+        };
+        let mut toml = open(StorageFormat::Toml);
+        assert_eq!(toml.get_todo(&id), json.get_todo(&id));
+        assert!(dir.join("todos.toml").exists());
+        assert!(!dir.join("todo.gdbm").exists());
+        assert!(dir.join("todo.gdbm.bak").exists());
+
+        let mut edited = toml.get_todo(&id).unwrap().clone();
+        edited.subject = "Edited".to_string();
+        toml.update_todo(edited).unwrap();
+        let reloaded = open(StorageFormat::Toml);
+        assert_eq!(reloaded.get_todo(&id), toml.get_todo(&id));
+
+        // Switching back picks up the edit, not the todos from before
+        let back = open(StorageFormat::Json);
+        assert_eq!(back.get_todo(&id).unwrap().subject, "Edited");
+        assert!(!dir.join("todos.toml").exists());
+        assert!(dir.join("todos.toml.bak").exists());
+    }
+
+    #[test]
+    fn test_failed_save_keeps_todos_in_memory() {
+        let mut db = create_test_database();
+        db.file_path = Some(std::env::temp_dir().join(format!("todocli-missing-{}", uuid::Uuid::new_v4())).join("todo.gdbm"));
+
+        let id = db.add_todo(create_test_todo("Unsaved", "")).unwrap_err();
+        assert!(id.to_string().contains("Could not write database file"));
+        assert!(db.take_save_failure().is_some());
+        assert!(db.take_save_failure().is_none());
+        assert_eq!(db.get_all_todos().len(), 1);
+        assert!(db.has_unsaved_changes());
+    }
+
+    #[test]
+    fn test_failed_sequences_write_names_that_file() {
+        let mut db = temp_database();
+        // A directory where the sequences file should go makes its write fail
+        fs::create_dir(db.dir.join("sequences.json")).unwrap();
+        db.assign_sequences = true;
+
+        let error = db.add_todo(create_test_todo("Numbered", "")).unwrap_err();
+        assert!(error.to_string().contains("sequences file"));
+        let failure = db.take_save_failure().unwrap();
+        assert!(failure.contains("sequences.json"), "{}", failure);
+        assert!(db.dir.join("todo.gdbm").exists());
+        assert!(db.has_unsaved_changes());
+    }
+
+    #[test]
+    fn test_resolve_sequential_id() {
+        let mut db = create_test_database();
+        db.assign_sequences = true;
+        let id = db.add_todo(create_test_todo("Numbered", "")).unwrap();
+        assert_eq!(db.resolve_id_prefix("INBOX-1").unwrap(), id);
+    }
+
+    #[test]
+    fn test_sequences_unique_across_lists_differing_in_case() {
+        let mut db = create_test_database();
+        db.assign_sequences = true;
+        let mut ids = Vec::new();
+        for list in ["Work", "work"] {
+            let mut todo = create_test_todo("Numbered", "");
+            todo.list = list.to_string();
+            let id = db.add_todo(todo).unwrap();
+            ids.push(db.get_todo(&id).unwrap().seq_id.clone().unwrap());
+        }
+        assert_eq!(ids, ["work-1", "work-2"]);
+
+        // Ids shared anyway, e.g. through hand edits, are refused
+        let mut clash = create_test_todo("Clash", "");
+        clash.seq_id = Some("work-1".to_string());
+        db.insert_todo_for_test(clash);
+        assert!(db.resolve_id_prefix("work-1").is_err());
+    }
+
+    #[test]
+    fn test_size_warning_due() {
+        assert!(!size_warning_due(0, false));
+        assert!(!size_warning_due(LARGE_FILE_BYTES, false));
+        assert!(size_warning_due(LARGE_FILE_BYTES + 1, false));
+        assert!(!size_warning_due(LARGE_FILE_BYTES + 1, true));
+        assert_eq!(create_test_database().file_size(), 0);
+    }
+
+    #[test]
+    fn test_counts_on_mixed_todos() {
+        let mut db = create_test_database();
+        assert_eq!(db.counts(), Counts::default());
+
+        db.insert_todo_for_test(create_test_todo("Open", ""));
+        let mut overdue = create_test_todo("Late", "");
+        overdue.due_date = Some(Utc::now() - chrono::Duration::days(1));
+        db.insert_todo_for_test(overdue);
+        let mut late_but_done = create_test_todo("Late but done", "");
+        late_but_done.due_date = Some(Utc::now() - chrono::Duration::days(1));
+        late_but_done.closed_at = Some(Utc::now());
+        db.insert_todo_for_test(late_but_done);
+
+        assert_eq!(
+            db.counts(),
+            Counts { total: 3, active: 2, completed: 1, overdue: 1 }
+        );
+    }
+
+    #[test]
+    fn test_ics_event_for_due_todo() {
+        let mut db = create_test_database();
+        let mut todo = create_test_todo("Pay rent, water; gas", "");
+        todo.due_date = Some(NaiveDate::from_ymd_opt(2024, 3, 1).unwrap().and_hms_opt(23, 59, 59).unwrap().and_utc());
+        let id = todo.id.clone();
+        db.insert_todo_for_test(todo);
+        db.insert_todo_for_test(create_test_todo("No deadline", ""));
+
+        let ics = db.ics_calendar(Utc::now());
+        assert!(ics.starts_with("BEGIN:VCALENDAR\r\n"));
+        assert!(ics.ends_with("END:VCALENDAR\r\n"));
+        assert_eq!(ics.matches("BEGIN:VEVENT").count(), 1);
+        assert!(ics.contains(&format!("UID:{}@todocli\r\n", id)));
+        assert!(ics.contains("SUMMARY:Pay rent\\, water\\; gas\r\n"));
+        assert!(ics.contains("DTSTART;VALUE=DATE:20240301\r\n"));
+        assert!(ics.contains("DTEND;VALUE=DATE:20240302\r\n"));
+        assert!(!ics.contains("No deadline"));
+    }
+
+    #[test]
+    fn test_escape_ics_text() {
+        assert_eq!(escape_ics_text("a\\b"), "a\\\\b");
+        assert_eq!(escape_ics_text("line one\r\nline two"), "line one\\nline two");
+        assert_eq!(escape_ics_text("plain"), "plain");
+    }
+
+    #[test]
+    fn test_recently_completed() {
+        let mut db = create_test_database();
+        let now = Utc::now();
+        for (hours_ago, subject) in [(5, "Oldest"), (1, "Newest"), (3, "Middle")] {
+            let mut todo = create_test_todo(subject, "");
+            todo.closed_at = Some(now - chrono::Duration::hours(hours_ago));
+            db.insert_todo_for_test(todo);
+        }
+        db.insert_todo_for_test(create_test_todo("Still open", ""));
+
+        let subjects = |todos: Vec<&Todo>| todos.iter().map(|todo| todo.subject.clone()).collect::<Vec<_>>();
+        assert_eq!(subjects(db.recently_completed(10)), vec!["Newest", "Middle", "Oldest"]);
+        assert_eq!(subjects(db.recently_completed(2)), vec!["Newest", "Middle"]);
+        assert!(db.recently_completed(0).is_empty());
+    }
+
+    fn retention_fixture(db: &mut Database, now: DateTime<Utc>) -> (String, String, String) {
+        let mut old = create_test_todo("Done long ago", "");
+        old.closed_at = Some(now - chrono::Duration::days(40));
+        let mut recent = create_test_todo("Done yesterday", "");
+        recent.closed_at = Some(now - chrono::Duration::days(1));
+        let active = create_test_todo("Still open", "");
+        let ids = (old.id.clone(), recent.id.clone(), active.id.clone());
+        for todo in [old, recent, active] {
+            db.insert_todo_for_test(todo);
+        }
+        ids
+    }
+
+    #[test]
+    fn test_cleanup_completed_delete() {
+        let mut db = create_test_database();
+        let now = Utc::now();
+        let (old, recent, active) = retention_fixture(&mut db, now);
+
+        let policy = RetentionPolicy { action: RetentionAction::Delete, days: 30 };
+        assert_eq!(db.cleanup_completed(policy, now).unwrap(), 1);
+        assert!(db.get_todo(&old).is_none());
+        assert!(db.get_todo(&recent).is_some());
+        assert!(db.get_todo(&active).is_some());
+    }
+
+    #[test]
+    fn test_cleanup_completed_archive() {
+        let mut db = temp_database();
+        let now = Utc::now();
+        let (old, recent, active) = retention_fixture(&mut db, now);
+
+        let policy = RetentionPolicy { action: RetentionAction::Archive, days: 30 };
+        assert_eq!(db.cleanup_completed(policy, now).unwrap(), 1);
+        assert!(db.get_todo(&old).is_none());
+        assert!(db.get_todo(&recent).is_some());
+        assert!(db.get_todo(&active).is_some());
+
+        let archive: HashMap<String, Todo> =
+            serde_json::from_slice(&fs::read(db.archive_path().unwrap()).unwrap()).unwrap();
+        assert_eq!(archive.len(), 1);
+        assert_eq!(archive[&old].subject, "Done long ago");
+
+        // Nothing else is old enough yet
+        assert_eq!(db.cleanup_completed(policy, now).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_cleanup_completed_records_failed_archive_write() {
+        let mut db = create_test_database();
+        let missing_dir = std::env::temp_dir().join(format!("todocli-missing-{}", uuid::Uuid::new_v4()));
+        db.file_path = Some(missing_dir.join("todo.gdbm"));
+        let now = Utc::now();
+        retention_fixture(&mut db, now);
+
+        let policy = RetentionPolicy { action: RetentionAction::Archive, days: 30 };
+        assert!(db.cleanup_completed(policy, now).is_err());
+        let failure = db.take_save_failure().unwrap();
+        assert!(failure.contains("archive.json"), "{}", failure);
+        assert_eq!(db.get_all_todos().len(), 3);
+    }
+
+    #[test]
+    fn test_cleanup_completed_keeps_todos_when_archive_fails() {
+        let mut db = temp_database();
+        // A directory where the archive should go makes it unwritable
+        fs::create_dir(db.dir.join("archive.json")).unwrap();
+        let now = Utc::now();
+        let (old, _, _) = retention_fixture(&mut db, now);
+
+        let policy = RetentionPolicy { action: RetentionAction::Archive, days: 30 };
+        assert!(db.cleanup_completed(policy, now).is_err());
+        assert!(db.get_todo(&old).is_some());
+        assert_eq!(db.get_all_todos().len(), 3);
+    }
+
+    #[test]
+    fn test_in_memory_save_and_load_are_noops() {
+        let mut db = Database::in_memory();
+        db.insert_todo_for_test(create_test_todo("Kept", ""));
+
+        assert!(db.save().is_ok());
+        db.load().unwrap();
+        assert_eq!(db.todos.len(), 1);
+    }
+
+    fn reopen_fixture() -> (Database, String) {
+        let mut db = create_test_database();
+        let base = Utc::now() - chrono::Duration::days(3);
+        let mut middle_id = String::new();
+        for (offset, subject) in ["First", "Middle", "Last"].iter().enumerate() {
+            let mut todo = create_test_todo(subject, "");
+            todo.created_at = base + chrono::Duration::hours(offset as i64);
+            todo.last_modified_at = todo.created_at;
+            if *subject == "Middle" {
+                middle_id = todo.id.clone();
+            }
+            db.insert_todo_for_test(todo);
+        }
+        (db, middle_id)
+    }
+
+    fn reopen(db: &mut Database, id: &str) -> Vec<String> {
+        for _ in 0..2 {
+            let mut todo = db.get_todo(id).unwrap().clone();
+            todo.toggle_completion();
+            db.insert_todo_for_test(todo);
+        }
+        db.get_all_todos().iter().map(|todo| todo.subject.clone()).collect()
+    }
+
+    #[test]
+    fn test_reopened_todo_moves_to_end_by_default() {
+        let (mut db, middle_id) = reopen_fixture();
+        assert_eq!(reopen(&mut db, &middle_id), vec!["First", "Last", "Middle"]);
+    }
+
+    #[test]
+    fn test_stable_reopen_keeps_original_position() {
+        let (mut db, middle_id) = reopen_fixture();
+        db.set_sort_order(SortOrder { stable_reopen: true, ..SortOrder::default() });
+        assert_eq!(reopen(&mut db, &middle_id), vec!["First", "Middle", "Last"]);
+
+        // Completed todos still sort by modification time
+        let mut done = db.get_todo(&middle_id).unwrap().clone();
+        done.toggle_completion();
+        db.insert_todo_for_test(done);
+        assert_eq!(db.get_all_todos().last().unwrap().subject, "Middle");
+    }
+}
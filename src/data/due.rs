@@ -0,0 +1,124 @@
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc, Weekday};
+
+/// Parses a due date relative to `now`: `today`, `tomorrow`, `+3d` (days),
+/// `+2w` (weeks), a weekday such as `friday` or `next monday` (its next
+/// occurrence after today), or an absolute `YYYY-MM-DD`. Todos are due at
+/// the end of the day (UTC).
+pub fn parse_due(input: &str, now: DateTime<Utc>) -> Result<DateTime<Utc>> {
+    let input = input.trim().to_lowercase();
+    let today = now.date_naive();
+
+    let date = match input.as_str() {
+        "today" => today,
+        "tomorrow" => today + Duration::days(1),
+        _ => {
+            if let Some(offset) = input.strip_prefix('+') {
+                today
+                    .checked_add_signed(parse_offset(offset)?)
+                    .context("Due date out of range")?
+            } else if let Some(weekday) = parse_weekday(input.strip_prefix("next ").unwrap_or(&input)) {
+                next_weekday(today, weekday)
+            } else {
+                NaiveDate::parse_from_str(&input, "%Y-%m-%d").with_context(|| {
+                    format!("Unknown due date '{}', expected YYYY-MM-DD, today, tomorrow, +3d or a weekday", input)
+                })?
+            }
+        }
+    };
+    end_of_day(date)
+}
+
+/// `3d` or `2w` as a duration. Only plain digits are accepted as the count,
+/// and counts too large for a date are rejected.
+fn parse_offset(offset: &str) -> Result<Duration> {
+    let invalid = || format!("Invalid offset '+{}', expected e.g. +3d or +2w", offset);
+    let (count, to_duration): (&str, fn(i64) -> Option<Duration>) =
+        if let Some(count) = offset.strip_suffix('d') {
+            (count, Duration::try_days)
+        } else if let Some(count) = offset.strip_suffix('w') {
+            (count, Duration::try_weeks)
+        } else {
+            bail!(invalid());
+        };
+    if count.is_empty() || !count.bytes().all(|byte| byte.is_ascii_digit()) {
+        bail!(invalid());
+    }
+    let count: i64 = count.parse().with_context(|| format!("Offset '+{}' is too large", offset))?;
+    to_duration(count).with_context(|| format!("Offset '+{}' is too large", offset))
+}
+
+fn parse_weekday(name: &str) -> Option<Weekday> {
+    match name {
+        "monday" | "mon" => Some(Weekday::Mon),
+        "tuesday" | "tue" => Some(Weekday::Tue),
+        "wednesday" | "wed" => Some(Weekday::Wed),
+        "thursday" | "thu" => Some(Weekday::Thu),
+        "friday" | "fri" => Some(Weekday::Fri),
+        "saturday" | "sat" => Some(Weekday::Sat),
+        "sunday" | "sun" => Some(Weekday::Sun),
+        _ => None,
+    }
+}
+
+/// The first `weekday` after `today`; a week ahead when today is that day.
+fn next_weekday(today: NaiveDate, weekday: Weekday) -> NaiveDate {
+    let ahead = (weekday.num_days_from_monday() + 7 - today.weekday().num_days_from_monday()) % 7;
+    today + Duration::days(if ahead == 0 { 7 } else { ahead as i64 })
+}
+
+fn end_of_day(date: NaiveDate) -> Result<DateTime<Utc>> {
+    date.and_hms_opt(23, 59, 59)
+        .map(|end| end.and_utc())
+        .context("Due date out of range")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(date: &str) -> DateTime<Utc> {
+        end_of_day(NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap()).unwrap()
+    }
+
+    // A Wednesday morning
+    fn now() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 3, 13, 9, 30, 0).unwrap()
+    }
+
+    #[test]
+    fn test_relative_days() {
+        assert_eq!(parse_due("today", now()).unwrap(), at("2024-03-13"));
+        assert_eq!(parse_due(" Tomorrow ", now()).unwrap(), at("2024-03-14"));
+        assert_eq!(parse_due("+3d", now()).unwrap(), at("2024-03-16"));
+        assert_eq!(parse_due("+2w", now()).unwrap(), at("2024-03-27"));
+    }
+
+    #[test]
+    fn test_weekdays() {
+        assert_eq!(parse_due("next monday", now()).unwrap(), at("2024-03-18"));
+        assert_eq!(parse_due("fri", now()).unwrap(), at("2024-03-15"));
+        // The same weekday means a week from today
+        assert_eq!(parse_due("wednesday", now()).unwrap(), at("2024-03-20"));
+    }
+
+    #[test]
+    fn test_absolute_date() {
+        assert_eq!(parse_due("2024-12-31", now()).unwrap(), at("2024-12-31"));
+    }
+
+    #[test]
+    fn test_rejects_gibberish() {
+        for input in ["someday", "+3x", "+d", "15/03/2024", "next", ""] {
+            assert!(parse_due(input, now()).is_err(), "{} was accepted", input);
+        }
+    }
+
+    #[test]
+    fn test_rejects_bad_offsets_without_panicking() {
+        for input in ["+3é", "+é", "+-3d", "++3d", "+99999999d", "+999999999999999d", "+99999999999999999999w"] {
+            assert!(parse_due(input, now()).is_err(), "{} was accepted", input);
+        }
+    }
+}
@@ -0,0 +1,31 @@
+use crate::data::Todo;
+
+/// Renders todos as a markdown checklist, one `- [ ]` / `- [x]` item per
+/// todo in the given order.
+pub fn markdown_checklist(todos: &[&Todo]) -> String {
+    todos
+        .iter()
+        .map(|todo| {
+            let mark = if todo.is_completed() { 'x' } else { ' ' };
+            format!("- [{}] {}\n", mark, todo.subject)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_markdown_checklist() {
+        let open = Todo::new("Review PR".to_string(), "Not exported".to_string());
+        let mut done = Todo::new("Write changelog".to_string(), String::new());
+        done.toggle_completion();
+
+        assert_eq!(
+            markdown_checklist(&[&open, &done]),
+            "- [ ] Review PR\n- [x] Write changelog\n"
+        );
+        assert_eq!(markdown_checklist(&[]), "");
+    }
+}
@@ -0,0 +1,355 @@
+use crate::data::{Priority, Todo};
+use serde::{Deserialize, Serialize};
+
+/// Top-level tabs of the main list.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ListTab {
+    #[default]
+    All,
+    Active,
+    Completed,
+}
+
+impl ListTab {
+    pub const ALL: [ListTab; 3] = [ListTab::All, ListTab::Active, ListTab::Completed];
+
+    pub fn title(self) -> &'static str {
+        match self {
+            ListTab::All => "All",
+            ListTab::Active => "Active",
+            ListTab::Completed => "Completed",
+        }
+    }
+
+    pub fn index(self) -> usize {
+        Self::ALL.iter().position(|tab| *tab == self).unwrap_or(0)
+    }
+
+    pub fn next(self) -> Self {
+        Self::ALL[(self.index() + 1) % Self::ALL.len()]
+    }
+
+    pub fn previous(self) -> Self {
+        Self::ALL[(self.index() + Self::ALL.len() - 1) % Self::ALL.len()]
+    }
+
+    pub fn matches(self, todo: &Todo) -> bool {
+        match self {
+            ListTab::All => true,
+            ListTab::Active => !todo.is_completed(),
+            ListTab::Completed => todo.is_completed(),
+        }
+    }
+}
+
+/// Quick review filter flipped with `i`, on top of the tabs: only active
+/// or only completed todos.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CompletionView {
+    Active,
+    Completed,
+}
+
+impl CompletionView {
+    pub fn matches(self, todo: &Todo) -> bool {
+        match self {
+            CompletionView::Active => !todo.is_completed(),
+            CompletionView::Completed => todo.is_completed(),
+        }
+    }
+
+    pub fn inverted(self) -> Self {
+        match self {
+            CompletionView::Active => CompletionView::Completed,
+            CompletionView::Completed => CompletionView::Active,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            CompletionView::Active => "Only active",
+            CompletionView::Completed => "Only completed",
+        }
+    }
+}
+
+/// View filters applied on top of the sorted todo list. Each enabled filter
+/// narrows the list further, so they combine with AND semantics. Saved in
+/// prefs between runs (see `StickyFilters`), except for the search.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TodoFilter {
+    /// Named list to show; `None` shows todos from every list.
+    pub list: Option<String>,
+    pub tab: ListTab,
+    pub overdue_only: bool,
+    /// Only show todos with this priority.
+    pub priority: Option<Priority>,
+    /// Text that must appear in the subject or description; empty matches all.
+    #[serde(skip)]
+    pub query: String,
+    #[serde(skip)]
+    pub case_sensitive: bool,
+    /// Set while flipping between active and completed todos; not saved.
+    #[serde(skip)]
+    pub completion: Option<CompletionView>,
+}
+
+impl TodoFilter {
+    pub fn matches(&self, todo: &Todo) -> bool {
+        if self.list.as_ref().is_some_and(|list| *list != todo.list) {
+            return false;
+        }
+        if !self.tab.matches(todo) {
+            return false;
+        }
+        if self.completion.is_some_and(|view| !view.matches(todo)) {
+            return false;
+        }
+        if self.overdue_only && !todo.is_overdue() {
+            return false;
+        }
+        if self.priority.is_some_and(|priority| priority != todo.priority) {
+            return false;
+        }
+        if !todo_matches_query(todo, &self.query, self.case_sensitive) {
+            return false;
+        }
+        true
+    }
+
+    /// Cycles the priority filter: all → high → medium → low → all.
+    pub fn cycle_priority(&mut self) {
+        self.priority = match self.priority {
+            None => Some(Priority::High),
+            Some(Priority::High) => Some(Priority::Medium),
+            Some(Priority::Medium) => Some(Priority::Low),
+            Some(Priority::Low) => None,
+        };
+    }
+
+    /// Snaps between only active and only completed todos, starting with
+    /// the active ones.
+    pub fn invert_completion(&mut self) {
+        self.completion = Some(self.completion.map_or(CompletionView::Active, CompletionView::inverted));
+    }
+
+    pub fn apply<'a>(&self, todos: Vec<&'a Todo>) -> Vec<&'a Todo> {
+        todos.into_iter().filter(|todo| self.matches(todo)).collect()
+    }
+}
+
+/// Whether the subject or description contains `query`. Matching ignores
+/// case unless `case_sensitive` is set; an empty query matches every todo.
+pub fn todo_matches_query(todo: &Todo, query: &str, case_sensitive: bool) -> bool {
+    if query.is_empty() {
+        return true;
+    }
+    if case_sensitive {
+        todo.subject.contains(query) || todo.description.contains(query)
+    } else {
+        let query = query.to_lowercase();
+        todo.subject.to_lowercase().contains(&query)
+            || todo.description.to_lowercase().contains(&query)
+    }
+}
+
+/// Which filters are kept between runs; the others start cleared on the
+/// next launch. All are kept by default.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StickyFilters {
+    pub tab: bool,
+    pub list: bool,
+    pub overdue: bool,
+    pub priority: bool,
+}
+
+impl Default for StickyFilters {
+    fn default() -> Self {
+        Self { tab: true, list: true, overdue: true, priority: true }
+    }
+}
+
+impl StickyFilters {
+    /// `filter` with every non-sticky filter cleared, as it should be saved.
+    pub fn saved(&self, filter: &TodoFilter) -> TodoFilter {
+        let cleared = TodoFilter::default();
+        TodoFilter {
+            tab: if self.tab { filter.tab } else { cleared.tab },
+            list: if self.list { filter.list.clone() } else { cleared.list },
+            overdue_only: self.overdue && filter.overdue_only,
+            priority: if self.priority { filter.priority } else { cleared.priority },
+            ..cleared
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, Utc};
+
+    fn todo_due(subject: &str, offset: Duration) -> Todo {
+        let mut todo = Todo::new(subject.to_string(), String::new());
+        todo.due_date = Some(Utc::now() + offset);
+        todo
+    }
+
+    #[test]
+    fn test_default_filter_matches_everything() {
+        let filter = TodoFilter::default();
+        let todo = Todo::new("Test".to_string(), String::new());
+        assert!(filter.matches(&todo));
+    }
+
+    #[test]
+    fn test_overdue_only_filter() {
+        let overdue = todo_due("Overdue", -Duration::days(1));
+        let upcoming = todo_due("Upcoming", Duration::days(1));
+        let no_due = Todo::new("No due date".to_string(), String::new());
+        let mut completed = todo_due("Completed late", -Duration::days(2));
+        completed.toggle_completion();
+
+        let filter = TodoFilter { overdue_only: true, ..TodoFilter::default() };
+        let todos = vec![&overdue, &upcoming, &no_due, &completed];
+        let filtered = filter.apply(todos);
+
+        assert_eq!(filtered.len(), 1);
+        assert_eq!(filtered[0].subject, "Overdue");
+    }
+
+    #[test]
+    fn test_priority_filter() {
+        let mut high = Todo::new("Urgent".to_string(), String::new());
+        high.priority = Priority::High;
+        let medium = Todo::new("Normal".to_string(), String::new());
+        let mut low = Todo::new("Someday".to_string(), String::new());
+        low.priority = Priority::Low;
+        let todos = vec![&high, &medium, &low];
+
+        let mut filter = TodoFilter::default();
+        filter.cycle_priority();
+        assert_eq!(filter.priority, Some(Priority::High));
+        let filtered = filter.apply(todos.clone());
+        assert_eq!(filtered.len(), 1);
+        assert_eq!(filtered[0].subject, "Urgent");
+
+        filter.cycle_priority();
+        filter.cycle_priority();
+        assert_eq!(filter.apply(todos.clone())[0].subject, "Someday");
+        filter.cycle_priority();
+        assert_eq!(filter.priority, None);
+        assert_eq!(filter.apply(todos).len(), 3);
+    }
+
+    #[test]
+    fn test_tab_cycling() {
+        assert_eq!(ListTab::All.next(), ListTab::Active);
+        assert_eq!(ListTab::Completed.next(), ListTab::All);
+        assert_eq!(ListTab::All.previous(), ListTab::Completed);
+        assert_eq!(ListTab::Active.previous(), ListTab::All);
+    }
+
+    #[test]
+    fn test_tabs_combine_with_overdue_filter() {
+        let overdue = todo_due("Overdue", -Duration::days(1));
+        let active = Todo::new("Active".to_string(), String::new());
+        let mut completed = Todo::new("Completed".to_string(), String::new());
+        completed.toggle_completion();
+        let todos = vec![&overdue, &active, &completed];
+
+        let mut filter = TodoFilter { tab: ListTab::Active, ..TodoFilter::default() };
+        assert_eq!(filter.apply(todos.clone()).len(), 2);
+
+        filter.overdue_only = true;
+        assert_eq!(filter.apply(todos.clone()).len(), 1);
+
+        filter.tab = ListTab::Completed;
+        assert!(filter.apply(todos).is_empty());
+    }
+
+    #[test]
+    fn test_query_matches_subject_and_description() {
+        let todo = Todo::new("Buy milk".to_string(), "From the corner shop".to_string());
+        assert!(todo_matches_query(&todo, "", false));
+        assert!(todo_matches_query(&todo, "milk", false));
+        assert!(todo_matches_query(&todo, "corner", false));
+        assert!(!todo_matches_query(&todo, "bread", false));
+    }
+
+    #[test]
+    fn test_query_case_sensitivity() {
+        let todo = Todo::new("Review PR".to_string(), "Check the API docs".to_string());
+
+        assert!(todo_matches_query(&todo, "review pr", false));
+        assert!(!todo_matches_query(&todo, "review pr", true));
+
+        assert!(todo_matches_query(&todo, "api", false));
+        assert!(!todo_matches_query(&todo, "api", true));
+        assert!(todo_matches_query(&todo, "API", true));
+    }
+
+    #[test]
+    fn test_list_filter() {
+        let inbox = Todo::new("Inbox item".to_string(), String::new());
+        let mut work = Todo::new("Work item".to_string(), String::new());
+        work.list = "Work".to_string();
+        let todos = vec![&inbox, &work];
+
+        let mut filter = TodoFilter::default();
+        assert_eq!(filter.apply(todos.clone()).len(), 2);
+
+        filter.list = Some("Work".to_string());
+        let filtered = filter.apply(todos);
+        assert_eq!(filtered.len(), 1);
+        assert_eq!(filtered[0].subject, "Work item");
+    }
+
+    #[test]
+    fn test_sticky_filters_saved() {
+        let filter = TodoFilter {
+            list: Some("Work".to_string()),
+            tab: ListTab::Active,
+            overdue_only: true,
+            priority: Some(Priority::High),
+            query: "milk".to_string(),
+            case_sensitive: false,
+            completion: Some(CompletionView::Completed),
+        };
+        let sticky = StickyFilters { overdue: false, priority: false, ..StickyFilters::default() };
+
+        let saved = sticky.saved(&filter);
+        assert_eq!(saved.list.as_deref(), Some("Work"));
+        assert_eq!(saved.tab, ListTab::Active);
+        assert!(!saved.overdue_only);
+        assert_eq!(saved.priority, None);
+        assert!(saved.query.is_empty());
+        assert_eq!(saved.completion, None);
+    }
+
+    #[test]
+    fn test_inverted_completion_views_split_todos() {
+        let overdue = todo_due("Overdue", -Duration::days(1));
+        let active = Todo::new("Active".to_string(), String::new());
+        let mut completed = Todo::new("Completed".to_string(), String::new());
+        completed.toggle_completion();
+        let todos = vec![&overdue, &active, &completed];
+
+        let mut filter = TodoFilter::default();
+        filter.invert_completion();
+        assert_eq!(filter.completion, Some(CompletionView::Active));
+        let only_active = filter.apply(todos.clone());
+        filter.invert_completion();
+        assert_eq!(filter.completion, Some(CompletionView::Completed));
+        let only_completed = filter.apply(todos.clone());
+
+        assert!(only_active.iter().all(|todo| !only_completed.iter().any(|other| other.id == todo.id)));
+        assert_eq!(only_active.len() + only_completed.len(), todos.len());
+        assert_eq!(only_completed[0].subject, "Completed");
+
+        filter.invert_completion();
+        assert_eq!(filter.completion, Some(CompletionView::Active));
+    }
+}
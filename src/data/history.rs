@@ -0,0 +1,67 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, SecondsFormat, Utc};
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::Path;
+
+/// Changes recorded in `history.log`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HistoryAction {
+    Completed,
+    Reopened,
+}
+
+impl HistoryAction {
+    pub fn name(self) -> &'static str {
+        match self {
+            HistoryAction::Completed => "completed",
+            HistoryAction::Reopened => "reopened",
+        }
+    }
+}
+
+/// One log line: `<RFC 3339 timestamp> <action> <todo id>`.
+pub fn format_event(at: DateTime<Utc>, id: &str, action: HistoryAction) -> String {
+    format!("{} {} {}", at.to_rfc3339_opts(SecondsFormat::Secs, true), action.name(), id)
+}
+
+/// Appends an event for `id` to the log at `path`, creating it if needed.
+pub fn log_event(path: &Path, id: &str, action: HistoryAction) -> Result<()> {
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .context("Could not open history log")?;
+    writeln!(file, "{}", format_event(Utc::now(), id, action))
+        .context("Could not write history log")?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_format_completion_event() {
+        let at = Utc.with_ymd_and_hms(2024, 3, 15, 9, 30, 5).unwrap();
+        assert_eq!(
+            format_event(at, "3f2a9c1e", HistoryAction::Completed),
+            "2024-03-15T09:30:05Z completed 3f2a9c1e"
+        );
+    }
+
+    #[test]
+    fn test_log_event_appends() {
+        let path = std::env::temp_dir().join(format!("todocli-history-{}.log", uuid::Uuid::new_v4()));
+        log_event(&path, "first", HistoryAction::Completed).unwrap();
+        log_event(&path, "first", HistoryAction::Reopened).unwrap();
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = content.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].ends_with(" completed first"));
+        assert!(lines[1].ends_with(" reopened first"));
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,102 @@
+use crate::data::todo::compact_duration;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Language for relative times and status labels, chosen with the `locale`
+/// pref. Dates themselves are always shown as `YYYY-MM-DD`.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Locale {
+    #[default]
+    En,
+    De,
+    Fr,
+    Es,
+}
+
+/// Translated labels. `{}` in `ago` and `from_now` stands for a duration
+/// such as `3d`.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Strings {
+    pub ago: &'static str,
+    pub from_now: &'static str,
+    pub due: &'static str,
+    pub active: &'static str,
+    pub completed: &'static str,
+    pub overdue: &'static str,
+}
+
+impl Locale {
+    pub fn strings(self) -> &'static Strings {
+        match self {
+            Locale::En => &Strings {
+                ago: "{} ago",
+                from_now: "in {}",
+                due: "due",
+                active: "Active",
+                completed: "Completed",
+                overdue: "Overdue",
+            },
+            Locale::De => &Strings {
+                ago: "vor {}",
+                from_now: "in {}",
+                due: "fällig",
+                active: "Offen",
+                completed: "Erledigt",
+                overdue: "Überfällig",
+            },
+            Locale::Fr => &Strings {
+                ago: "il y a {}",
+                from_now: "dans {}",
+                due: "échéance",
+                active: "En cours",
+                completed: "Terminé",
+                overdue: "En retard",
+            },
+            Locale::Es => &Strings {
+                ago: "hace {}",
+                from_now: "en {}",
+                due: "vence",
+                active: "Activa",
+                completed: "Completada",
+                overdue: "Vencida",
+            },
+        }
+    }
+}
+
+/// `time` relative to `now` in the largest whole unit, e.g. `3d ago` or
+/// `in 5h`.
+pub fn humanize_time(time: DateTime<Utc>, now: DateTime<Utc>, locale: Locale) -> String {
+    let strings = locale.strings();
+    if time <= now {
+        strings.ago.replace("{}", &compact_duration(now - time))
+    } else {
+        strings.from_now.replace("{}", &compact_duration(time - now))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    #[test]
+    fn test_strings_for_locale_key() {
+        let locale: Locale = serde_json::from_str(r#""de""#).unwrap();
+        assert_eq!(locale, Locale::De);
+        assert_eq!(locale.strings().overdue, "Überfällig");
+        assert_eq!(Locale::Fr.strings().completed, "Terminé");
+        assert_eq!(Locale::default().strings().active, "Active");
+        assert!(serde_json::from_str::<Locale>(r#""xx""#).is_err());
+    }
+
+    #[test]
+    fn test_humanize_time() {
+        let now = Utc::now();
+        assert_eq!(humanize_time(now - Duration::days(3), now, Locale::En), "3d ago");
+        assert_eq!(humanize_time(now + Duration::hours(5), now, Locale::En), "in 5h");
+        assert_eq!(humanize_time(now - Duration::days(3), now, Locale::De), "vor 3d");
+        assert_eq!(humanize_time(now + Duration::minutes(90), now, Locale::Es), "en 1h");
+    }
+}
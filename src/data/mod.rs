@@ -1,5 +1,20 @@
 pub mod todo;
 pub mod database;
+pub mod due;
+pub mod export;
+pub mod filter;
+pub mod history;
+pub mod locale;
+pub mod paths;
+pub mod prefs;
+pub mod sort;
+pub mod storage;
+pub mod tags;
 
-pub use todo::Todo;
-pub use database::Database;
\ No newline at end of file
+pub use todo::{attachment_label, subtask_progress, Priority, Subtask, Todo, DEFAULT_LIST, DEFAULT_REMIND_BEFORE};
+pub use database::{size_warning_due, Counts, Database, RetentionAction, RetentionPolicy, Stats, TagCount, LARGE_FILE_BYTES};
+pub use filter::{CompletionView, ListTab, StickyFilters, TodoFilter};
+pub use locale::{humanize_time, Locale};
+pub use prefs::{ConfirmText, PopupSize, PriorityIcons, Prefs, DEFAULT_TAB_WIDTH};
+pub use sort::{SortDirection, SortMode, SortOrder};
+pub use storage::StorageFormat;
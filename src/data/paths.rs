@@ -0,0 +1,59 @@
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+static APP_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+/// Directory holding the database and other todocli files. Normally
+/// `<config dir>/todo`; resolved once per process.
+pub fn app_dir() -> PathBuf {
+    APP_DIR
+        .get_or_init(|| {
+            let config_dir = dirs::config_dir();
+            let dir = resolve_app_dir(config_dir.clone(), dirs::home_dir());
+            if config_dir.is_none() {
+                eprintln!(
+                    "todocli: could not find a config directory, storing data in {}",
+                    dir.display()
+                );
+            }
+            dir
+        })
+        .clone()
+}
+
+/// Picks where todocli keeps its files: the platform config directory when
+/// there is one, then a `.todocli` folder in the home directory, and finally
+/// the current working directory.
+pub fn resolve_app_dir(config_dir: Option<PathBuf>, home_dir: Option<PathBuf>) -> PathBuf {
+    match (config_dir, home_dir) {
+        (Some(config_dir), _) => config_dir.join("todo"),
+        (None, Some(home_dir)) => home_dir.join(".todocli"),
+        (None, None) => PathBuf::from("."),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_app_dir_uses_config_dir() {
+        let dir = resolve_app_dir(
+            Some(PathBuf::from("/home/user/.config")),
+            Some(PathBuf::from("/home/user")),
+        );
+        assert_eq!(dir, PathBuf::from("/home/user/.config/todo"));
+    }
+
+    #[test]
+    fn test_resolve_app_dir_falls_back_to_home() {
+        let dir = resolve_app_dir(None, Some(PathBuf::from("/home/user")));
+        assert_eq!(dir, PathBuf::from("/home/user/.todocli"));
+    }
+
+    #[test]
+    fn test_resolve_app_dir_falls_back_to_cwd() {
+        let dir = resolve_app_dir(None, None);
+        assert_eq!(dir, PathBuf::from("."));
+    }
+}
@@ -0,0 +1,346 @@
+use crate::data::{
+    paths, Locale, Priority, RetentionPolicy, SortDirection, SortMode, StickyFilters, StorageFormat, TodoFilter,
+};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Size of a centered popup as percentages of the terminal.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PopupSize {
+    pub width_percent: u16,
+    pub height_percent: u16,
+}
+
+impl PopupSize {
+    pub const DETAIL: PopupSize = PopupSize { width_percent: 80, height_percent: 70 };
+    pub const CONFIRM: PopupSize = PopupSize { width_percent: 50, height_percent: 30 };
+
+    /// Keeps both percentages within 10–100 so hand-edited values can't
+    /// shrink a popup to nothing or overflow the layout.
+    pub fn clamped(self) -> Self {
+        Self {
+            width_percent: self.width_percent.clamp(10, 100),
+            height_percent: self.height_percent.clamp(10, 100),
+        }
+    }
+
+    fn detail_default() -> Self {
+        Self::DETAIL
+    }
+
+    fn confirm_default() -> Self {
+        Self::CONFIRM
+    }
+}
+
+/// Wording of the confirmation dialog, e.g. for translating it. Unset
+/// strings fall back to English.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+#[serde(default)]
+pub struct ConfirmText {
+    pub question: Option<String>,
+    pub yes: Option<String>,
+    pub no: Option<String>,
+}
+
+impl ConfirmText {
+    pub fn question(&self) -> &str {
+        self.question.as_deref().unwrap_or("Are you sure?")
+    }
+
+    pub fn yes(&self) -> &str {
+        self.yes.as_deref().unwrap_or("Yes")
+    }
+
+    pub fn no(&self) -> &str {
+        self.no.as_deref().unwrap_or("No")
+    }
+}
+
+/// Icon drawn in the list for each priority, e.g. `🔥`/`🙂`/`💤`. Unset
+/// icons fall back to plain ASCII so they show on any terminal.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+#[serde(default)]
+pub struct PriorityIcons {
+    pub high: Option<String>,
+    pub medium: Option<String>,
+    pub low: Option<String>,
+}
+
+impl PriorityIcons {
+    pub fn icon(&self, priority: Priority) -> &str {
+        match priority {
+            Priority::High => self.high.as_deref().unwrap_or("!"),
+            Priority::Medium => self.medium.as_deref().unwrap_or("-"),
+            Priority::Low => self.low.as_deref().unwrap_or("."),
+        }
+    }
+}
+
+/// Tab stops used when no `tab_width` pref is set.
+pub const DEFAULT_TAB_WIDTH: usize = 4;
+
+fn default_tab_width() -> usize {
+    DEFAULT_TAB_WIDTH
+}
+
+/// User preferences stored as JSON in `prefs.json` next to the database.
+/// Every field has a default so older or hand-edited files keep loading.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Prefs {
+    /// Where these prefs are saved; `None` keeps them in memory only.
+    #[serde(skip)]
+    path: Option<PathBuf>,
+    /// Number of todos to complete each day; `None` hides the goal gauge.
+    #[serde(default)]
+    pub daily_goal: Option<usize>,
+    /// Id of the todo selected when the app last exited.
+    #[serde(default)]
+    pub last_selected_id: Option<String>,
+    #[serde(default = "PopupSize::detail_default")]
+    pub detail_popup: PopupSize,
+    #[serde(default = "PopupSize::confirm_default")]
+    pub confirm_popup: PopupSize,
+    #[serde(default)]
+    pub confirm_text: ConfirmText,
+    #[serde(default)]
+    pub priority_icons: PriorityIcons,
+    #[serde(default)]
+    pub sort_mode: SortMode,
+    #[serde(default)]
+    pub sort_direction: SortDirection,
+    /// Tab, list, overdue and priority filters from the last run.
+    #[serde(default)]
+    pub filter: TodoFilter,
+    /// Which of those filters are kept between runs.
+    #[serde(default)]
+    pub sticky_filters: StickyFilters,
+    /// Keep reopened todos in their original place (see `SortOrder::stable_reopen`).
+    #[serde(default)]
+    pub stable_reopen: bool,
+    /// Show the short id column in the main list.
+    #[serde(default)]
+    pub show_short_ids: bool,
+    /// Show the age / time-to-complete column in the main list.
+    #[serde(default)]
+    pub show_durations: bool,
+    /// Fit more todos on screen by dropping the gap under the list header.
+    #[serde(default)]
+    pub compact: bool,
+    /// Keep leading whitespace when wrapping descriptions and notes.
+    #[serde(default)]
+    pub keep_indentation: bool,
+    /// Columns between tab stops in descriptions and notes.
+    #[serde(default = "default_tab_width")]
+    pub tab_width: usize,
+    /// Priority given to todos created in the TUI or by `batch` without
+    /// one.
+    #[serde(default)]
+    pub default_priority: Priority,
+    /// Language of relative times and status labels.
+    #[serde(default)]
+    pub locale: Locale,
+    /// Archive or delete old completed todos at startup; `None` keeps them.
+    #[serde(default)]
+    pub completed_retention: Option<RetentionPolicy>,
+    /// Give new todos short sequential ids per list, like `work-3`.
+    #[serde(default)]
+    pub sequential_ids: bool,
+    /// Format of the todos file; read when the database is opened.
+    #[serde(default)]
+    pub storage: StorageFormat,
+    /// Accent color per list name, e.g. `"Work": "#7dcfff"`.
+    #[serde(default)]
+    pub list_colors: HashMap<String, String>,
+    /// Set once the large database warning has been shown.
+    #[serde(default)]
+    pub large_file_warned: bool,
+}
+
+impl Default for Prefs {
+    fn default() -> Self {
+        Self {
+            path: None,
+            daily_goal: None,
+            last_selected_id: None,
+            detail_popup: PopupSize::DETAIL,
+            confirm_popup: PopupSize::CONFIRM,
+            confirm_text: ConfirmText::default(),
+            priority_icons: PriorityIcons::default(),
+            sort_mode: SortMode::Modified,
+            sort_direction: SortDirection::Ascending,
+            filter: TodoFilter::default(),
+            sticky_filters: StickyFilters::default(),
+            stable_reopen: false,
+            show_short_ids: false,
+            show_durations: false,
+            compact: false,
+            keep_indentation: false,
+            tab_width: DEFAULT_TAB_WIDTH,
+            default_priority: Priority::Medium,
+            locale: Locale::En,
+            completed_retention: None,
+            sequential_ids: false,
+            storage: StorageFormat::default(),
+            list_colors: HashMap::new(),
+            large_file_warned: false,
+        }
+    }
+}
+
+impl Prefs {
+    pub fn load() -> Self {
+        Self::load_from(&paths::app_dir().join("prefs.json"))
+    }
+
+    /// Loads prefs from `path`, falling back to defaults when the file is
+    /// missing or unreadable so a bad prefs file never blocks startup.
+    pub fn load_from(path: &Path) -> Self {
+        let mut prefs: Prefs = fs::read_to_string(path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        prefs.path = Some(path.to_path_buf());
+        prefs
+    }
+
+    /// Where these prefs are saved; `None` keeps them in memory only.
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let content = serde_json::to_string_pretty(self)
+            .context("Could not serialize prefs")?;
+        fs::write(path, content)
+            .context("Could not write prefs file")?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::{ListTab, RetentionAction};
+
+    #[test]
+    fn test_defaults_when_missing() {
+        let prefs = Prefs::load_from(Path::new("/nonexistent/todocli/prefs.json"));
+        assert_eq!(prefs.daily_goal, None);
+        assert_eq!(prefs.detail_popup, PopupSize::DETAIL);
+        assert_eq!(prefs.confirm_popup, PopupSize::CONFIRM);
+        assert_eq!(prefs.tab_width, DEFAULT_TAB_WIDTH);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let path = std::env::temp_dir().join(format!("todocli-prefs-{}.json", uuid::Uuid::new_v4()));
+        let mut prefs = Prefs::load_from(&path);
+        prefs.daily_goal = Some(5);
+        prefs.sort_direction = SortDirection::Descending;
+        prefs.sort_mode = SortMode::Priority;
+        prefs.filter = TodoFilter {
+            list: Some("Work".to_string()),
+            tab: ListTab::Active,
+            overdue_only: true,
+            priority: Some(Priority::High),
+            query: "not saved".to_string(),
+            case_sensitive: true,
+            completion: None,
+        };
+        prefs.completed_retention = Some(RetentionPolicy { action: RetentionAction::Archive, days: 30 });
+        prefs.save().unwrap();
+
+        let loaded = Prefs::load_from(&path);
+        assert_eq!(loaded.daily_goal, Some(5));
+        assert_eq!(loaded.sort_direction, SortDirection::Descending);
+        assert_eq!(loaded.sort_mode, SortMode::Priority);
+        assert_eq!(loaded.filter.list.as_deref(), Some("Work"));
+        assert_eq!(loaded.filter.tab, ListTab::Active);
+        assert!(loaded.filter.overdue_only);
+        assert_eq!(loaded.filter.priority, Some(Priority::High));
+        assert!(loaded.filter.query.is_empty());
+        assert!(!loaded.filter.case_sensitive);
+        assert_eq!(loaded.completed_retention, prefs.completed_retention);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_in_memory_prefs_do_not_save() {
+        let prefs = Prefs { daily_goal: Some(3), ..Prefs::default() };
+        assert!(prefs.save().is_ok());
+    }
+
+    #[test]
+    fn test_confirm_text_custom_or_default() {
+        let prefs: Prefs = serde_json::from_str(
+            r#"{"confirm_text": {"question": "Êtes-vous sûr ?", "yes": "Oui"}}"#,
+        )
+        .unwrap();
+        assert_eq!(prefs.confirm_text.question(), "Êtes-vous sûr ?");
+        assert_eq!(prefs.confirm_text.yes(), "Oui");
+        assert_eq!(prefs.confirm_text.no(), "No");
+
+        let defaults = ConfirmText::default();
+        assert_eq!(defaults.question(), "Are you sure?");
+        assert_eq!(defaults.yes(), "Yes");
+        assert_eq!(defaults.no(), "No");
+    }
+
+    #[test]
+    fn test_priority_icons_custom_or_default() {
+        let defaults = PriorityIcons::default();
+        assert_eq!(defaults.icon(Priority::High), "!");
+        assert_eq!(defaults.icon(Priority::Medium), "-");
+        assert_eq!(defaults.icon(Priority::Low), ".");
+
+        let prefs: Prefs = serde_json::from_str(r#"{"priority_icons": {"high": "🔥", "low": "💤"}}"#).unwrap();
+        assert_eq!(prefs.priority_icons.icon(Priority::High), "🔥");
+        assert_eq!(prefs.priority_icons.icon(Priority::Medium), "-");
+        assert_eq!(prefs.priority_icons.icon(Priority::Low), "💤");
+    }
+
+    #[test]
+    fn test_popup_sizes_read_and_clamped() {
+        let prefs: Prefs = serde_json::from_str(
+            r#"{"detail_popup": {"width_percent": 5, "height_percent": 150}}"#,
+        )
+        .unwrap();
+
+        assert_eq!(prefs.detail_popup, PopupSize { width_percent: 5, height_percent: 150 });
+        assert_eq!(
+            prefs.detail_popup.clamped(),
+            PopupSize { width_percent: 10, height_percent: 100 }
+        );
+        // Missing sizes keep their defaults
+        assert_eq!(prefs.confirm_popup, PopupSize::CONFIRM);
+        assert_eq!(PopupSize::CONFIRM.clamped(), PopupSize::CONFIRM);
+    }
+
+    #[test]
+    fn test_transient_filter_reset_on_reload() {
+        let path = std::env::temp_dir().join(format!("todocli-prefs-{}.json", uuid::Uuid::new_v4()));
+        let mut prefs = Prefs::load_from(&path);
+        prefs.sticky_filters.overdue = false;
+        let filter = TodoFilter {
+            overdue_only: true,
+            priority: Some(Priority::Low),
+            ..TodoFilter::default()
+        };
+        prefs.filter = prefs.sticky_filters.saved(&filter);
+        prefs.save().unwrap();
+
+        let loaded = Prefs::load_from(&path);
+        assert_eq!(loaded.filter.priority, Some(Priority::Low));
+        assert!(!loaded.filter.overdue_only);
+        assert!(!loaded.sticky_filters.overdue);
+        assert!(loaded.sticky_filters.priority);
+        fs::remove_file(&path).unwrap();
+    }
+}
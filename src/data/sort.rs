@@ -0,0 +1,98 @@
+use crate::data::Todo;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+
+/// Field the todo list is ordered by within the active and completed groups.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortMode {
+    #[default]
+    Modified,
+    Created,
+    Subject,
+    Priority,
+}
+
+impl SortMode {
+    pub fn next(self) -> Self {
+        match self {
+            SortMode::Modified => SortMode::Created,
+            SortMode::Created => SortMode::Subject,
+            SortMode::Subject => SortMode::Priority,
+            SortMode::Priority => SortMode::Modified,
+        }
+    }
+
+    pub fn title(self) -> &'static str {
+        match self {
+            SortMode::Modified => "Modified",
+            SortMode::Created => "Created",
+            SortMode::Subject => "Subject",
+            SortMode::Priority => "Priority",
+        }
+    }
+
+    fn compare(self, a: &Todo, b: &Todo) -> Ordering {
+        match self {
+            SortMode::Modified => a.last_modified_at.cmp(&b.last_modified_at),
+            SortMode::Created => a.created_at.cmp(&b.created_at),
+            SortMode::Subject => a.subject.to_lowercase().cmp(&b.subject.to_lowercase()),
+            SortMode::Priority => a.priority.cmp(&b.priority),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortDirection {
+    #[default]
+    Ascending,
+    Descending,
+}
+
+impl SortDirection {
+    pub fn toggled(self) -> Self {
+        match self {
+            SortDirection::Ascending => SortDirection::Descending,
+            SortDirection::Descending => SortDirection::Ascending,
+        }
+    }
+
+    pub fn arrow(self) -> &'static str {
+        match self {
+            SortDirection::Ascending => "↑",
+            SortDirection::Descending => "↓",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SortOrder {
+    pub mode: SortMode,
+    pub direction: SortDirection,
+    /// In `Modified` mode, order active todos by creation instead, so a
+    /// reopened todo returns to where it was rather than jumping to the end.
+    pub stable_reopen: bool,
+}
+
+impl SortOrder {
+    /// Active todos always come before completed ones; the mode and
+    /// direction only order todos within each group.
+    pub fn compare(&self, a: &Todo, b: &Todo) -> Ordering {
+        let both_active = !a.is_completed() && !b.is_completed();
+        let mode = if self.stable_reopen && self.mode == SortMode::Modified && both_active {
+            SortMode::Created
+        } else {
+            self.mode
+        };
+        let within_group = match self.direction {
+            SortDirection::Ascending => mode.compare(a, b),
+            SortDirection::Descending => mode.compare(b, a),
+        };
+        a.is_completed().cmp(&b.is_completed()).then(within_group)
+    }
+
+    pub fn label(&self) -> String {
+        format!("{} {}", self.mode.title(), self.direction.arrow())
+    }
+}
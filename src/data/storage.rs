@@ -0,0 +1,258 @@
+use crate::data::{Priority, Todo, DEFAULT_LIST};
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+/// Reads and writes the todos file. `Database` keeps one of these, chosen
+/// by `StorageFormat::storage`, and leaves the file format to it.
+pub trait Storage {
+    /// Todos in the file at `path`. An empty file holds none.
+    fn load(&self, path: &Path) -> Result<HashMap<String, Todo>>;
+
+    /// Replaces the file at `path` with `todos`.
+    fn save(&self, path: &Path, todos: &HashMap<String, Todo>) -> Result<()>;
+}
+
+/// How the todos file is written, chosen with the `storage` pref.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageFormat {
+    /// Compact JSON in `todo.gdbm`.
+    #[default]
+    Json,
+    /// `todos.toml`, for editing by hand and keeping in git.
+    Toml,
+}
+
+impl StorageFormat {
+    /// Name of the todos file in the app directory.
+    pub fn file_name(self) -> &'static str {
+        match self {
+            StorageFormat::Json => "todo.gdbm",
+            StorageFormat::Toml => "todos.toml",
+        }
+    }
+
+    /// The format switched from when switching to this one.
+    pub fn other(self) -> StorageFormat {
+        match self {
+            StorageFormat::Json => StorageFormat::Toml,
+            StorageFormat::Toml => StorageFormat::Json,
+        }
+    }
+
+    pub fn storage(self) -> Box<dyn Storage> {
+        match self {
+            StorageFormat::Json => Box::new(JsonStorage),
+            StorageFormat::Toml => Box::new(TomlStorage),
+        }
+    }
+}
+
+/// Compact JSON; older bincode files are read too and rewritten as JSON on
+/// the next save.
+pub struct JsonStorage;
+
+impl Storage for JsonStorage {
+    fn load(&self, path: &Path) -> Result<HashMap<String, Todo>> {
+        let content = fs::read(path).context("Could not read database file")?;
+        if content.is_empty() {
+            return Ok(HashMap::new());
+        }
+        decode_todos(&content)
+    }
+
+    fn save(&self, path: &Path, todos: &HashMap<String, Todo>) -> Result<()> {
+        let content = serde_json::to_vec(todos).context("Could not serialize todos")?;
+        Ok(fs::write(path, content)?)
+    }
+}
+
+/// `todos.toml`, see `to_toml`.
+pub struct TomlStorage;
+
+impl Storage for TomlStorage {
+    fn load(&self, path: &Path) -> Result<HashMap<String, Todo>> {
+        let content = fs::read_to_string(path).context("Could not read todos.toml")?;
+        from_toml(&content)
+    }
+
+    fn save(&self, path: &Path, todos: &HashMap<String, Todo>) -> Result<()> {
+        Ok(fs::write(path, to_toml(todos)?)?)
+    }
+}
+
+/// Layout of `todos.toml`: one `[[todo]]` table per todo.
+#[derive(Serialize, Deserialize, Default)]
+struct TomlFile {
+    #[serde(default)]
+    todo: Vec<Todo>,
+}
+
+/// Todos as TOML, oldest first so hand edits and git diffs stay stable.
+pub fn to_toml(todos: &HashMap<String, Todo>) -> Result<String> {
+    let mut todo: Vec<Todo> = todos.values().cloned().collect();
+    todo.sort_by(|a, b| a.created_at.cmp(&b.created_at).then_with(|| a.id.cmp(&b.id)));
+    toml::to_string(&TomlFile { todo }).context("Could not serialize todos as TOML")
+}
+
+/// Todos read from TOML. Two todos with the same id, e.g. from a copied
+/// `[[todo]]` block, are an error rather than one silently replacing the
+/// other.
+pub fn from_toml(content: &str) -> Result<HashMap<String, Todo>> {
+    let file: TomlFile = toml::from_str(content).context("Could not parse todos.toml")?;
+    let mut todos = HashMap::with_capacity(file.todo.len());
+    for todo in file.todo {
+        if todos.contains_key(&todo.id) {
+            bail!("todos.toml has more than one todo with id {}", todo.id);
+        }
+        todos.insert(todo.id.clone(), todo);
+    }
+    Ok(todos)
+}
+
+fn decode_todos(content: &[u8]) -> Result<HashMap<String, Todo>> {
+    if let Ok(todos) = serde_json::from_slice(content) {
+        return Ok(todos);
+    }
+
+    let legacy: HashMap<String, LegacyTodo> = bincode::deserialize(content)
+        .context("Could not deserialize database file")?;
+    Ok(legacy
+        .into_iter()
+        .map(|(id, todo)| (id, todo.into()))
+        .collect())
+}
+
+/// Todo layout written by versions that stored the database as bincode.
+/// Bincode isn't self-describing, so it can't pick up new fields via
+/// `#[serde(default)]`; files in this format are read once and rewritten as
+/// JSON on the next save.
+#[derive(Deserialize)]
+struct LegacyTodo {
+    id: String,
+    subject: String,
+    description: String,
+    created_at: DateTime<Utc>,
+    closed_at: Option<DateTime<Utc>>,
+    last_modified_at: DateTime<Utc>,
+}
+
+impl From<LegacyTodo> for Todo {
+    fn from(legacy: LegacyTodo) -> Self {
+        Todo {
+            id: legacy.id,
+            subject: legacy.subject,
+            description: legacy.description,
+            created_at: legacy.created_at,
+            closed_at: legacy.closed_at,
+            last_modified_at: legacy.last_modified_at,
+            due_date: None,
+            attachments: Vec::new(),
+            list: DEFAULT_LIST.to_string(),
+            priority: Priority::default(),
+            tags: Vec::new(),
+            notes: String::new(),
+            subtasks: Vec::new(),
+            remind_before: None,
+            is_template: false,
+            seq_id: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::{Priority, Subtask};
+    use chrono::Duration;
+
+    #[test]
+    fn test_decode_legacy_bincode() {
+        #[derive(serde::Serialize)]
+        struct OldTodo {
+            id: String,
+            subject: String,
+            description: String,
+            created_at: DateTime<Utc>,
+            closed_at: Option<DateTime<Utc>>,
+            last_modified_at: DateTime<Utc>,
+        }
+
+        let now = Utc::now();
+        let mut old = HashMap::new();
+        old.insert("abc".to_string(), OldTodo {
+            id: "abc".to_string(),
+            subject: "Legacy".to_string(),
+            description: "Stored as bincode".to_string(),
+            created_at: now,
+            closed_at: None,
+            last_modified_at: now,
+        });
+        let content = bincode::serialize(&old).unwrap();
+
+        let todos = decode_todos(&content).unwrap();
+        let todo = todos.get("abc").unwrap();
+        assert_eq!(todo.subject, "Legacy");
+        assert_eq!(todo.due_date, None);
+        assert_eq!(todo.list, DEFAULT_LIST);
+    }
+
+    #[test]
+    fn test_decode_json_round_trip() {
+        let mut todo = Todo::new("Due soon".to_string(), "Description".to_string());
+        todo.due_date = Some(Utc::now());
+        let mut todos = HashMap::new();
+        todos.insert(todo.id.clone(), todo.clone());
+
+        let content = serde_json::to_vec(&todos).unwrap();
+        let decoded = decode_todos(&content).unwrap();
+        assert_eq!(decoded.get(&todo.id).unwrap().due_date, todo.due_date);
+    }
+
+    #[test]
+    fn test_toml_round_trip() {
+        let plain = Todo::new("Buy milk".to_string(), String::new());
+        let mut full = Todo::new("Ship release".to_string(), "Line one\nLine \"two\"".to_string());
+        full.due_date = Some(Utc::now() + Duration::days(2));
+        full.attachments = vec!["notes/release.md".into()];
+        full.list = "Work".to_string();
+        full.priority = Priority::High;
+        full.tags = vec!["release".to_string()];
+        full.notes = "Ask QA first".to_string();
+        full.subtasks = vec![Subtask::new("Tag".to_string())];
+        full.remind_before = Some(Duration::hours(6));
+        full.is_template = true;
+        full.seq_id = Some("work-1".to_string());
+        let mut done = Todo::new("Done".to_string(), String::new());
+        done.toggle_completion();
+
+        let todos: HashMap<String, Todo> = [plain, full, done]
+            .into_iter()
+            .map(|todo| (todo.id.clone(), todo))
+            .collect();
+        let toml = to_toml(&todos).unwrap();
+        assert!(toml.contains("[[todo]]"));
+        assert_eq!(from_toml(&toml).unwrap(), todos);
+    }
+
+    #[test]
+    fn test_duplicate_ids_rejected() {
+        let todo = Todo::new("Copied".to_string(), String::new());
+        let todos = HashMap::from([(todo.id.clone(), todo.clone())]);
+        let toml = to_toml(&todos).unwrap();
+        let pasted_twice = format!("{}\n{}", toml, toml);
+
+        let error = from_toml(&pasted_twice).unwrap_err();
+        assert!(error.to_string().contains(&todo.id));
+    }
+
+    #[test]
+    fn test_empty_toml_file() {
+        assert!(from_toml("").unwrap().is_empty());
+        assert!(from_toml("[[todo]]\nsubject = 1").is_err());
+    }
+}
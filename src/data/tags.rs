@@ -0,0 +1,78 @@
+/// Canonical form of a tag: without a leading `#`, trimmed, lowercase, and
+/// with inner whitespace replaced by `-`. `None` if nothing is left.
+pub fn normalize_tag(tag: &str) -> Option<String> {
+    let tag = tag.trim();
+    let tag = tag.strip_prefix('#').unwrap_or(tag);
+    let normalized = tag.split_whitespace().collect::<Vec<_>>().join("-").to_lowercase();
+    if normalized.is_empty() {
+        None
+    } else {
+        Some(normalized)
+    }
+}
+
+/// Splits a comma-separated tag input into normalized tags (see
+/// `normalize_tag`), keeping the first occurrence of each.
+pub fn parse_tags(input: &str) -> Vec<String> {
+    let mut tags: Vec<String> = Vec::new();
+    for tag in input.split(',').filter_map(normalize_tag) {
+        if !tags.contains(&tag) {
+            tags.push(tag);
+        }
+    }
+    tags
+}
+
+/// Known tags starting with `prefix` (ignoring case), most used first and
+/// alphabetical among equals. `known` pairs each tag with its use count, as
+/// returned by `Database::tag_counts`. An empty prefix suggests nothing.
+pub fn suggest_tags(prefix: &str, known: &[(String, usize)]) -> Vec<String> {
+    let Some(prefix) = normalize_tag(prefix) else {
+        return Vec::new();
+    };
+
+    let mut matches: Vec<&(String, usize)> = known
+        .iter()
+        .filter(|(tag, _)| tag.to_lowercase().starts_with(&prefix))
+        .collect();
+    matches.sort_by(|(a, a_count), (b, b_count)| b_count.cmp(a_count).then_with(|| a.cmp(b)));
+    matches.into_iter().map(|(tag, _)| tag.clone()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn known(tags: &[(&str, usize)]) -> Vec<(String, usize)> {
+        tags.iter().map(|(tag, count)| (tag.to_string(), *count)).collect()
+    }
+
+    #[test]
+    fn test_parse_tags() {
+        assert_eq!(parse_tags(" work, home ,, work,errands "), vec!["work", "home", "errands"]);
+        assert!(parse_tags("  ").is_empty());
+        assert_eq!(parse_tags("#Work, work, Side Project"), vec!["work", "side-project"]);
+    }
+
+    #[test]
+    fn test_normalize_tag() {
+        assert_eq!(normalize_tag("#urgent"), Some("urgent".to_string()));
+        assert_eq!(normalize_tag("  home  "), Some("home".to_string()));
+        assert_eq!(normalize_tag("Errands"), Some("errands".to_string()));
+        assert_eq!(normalize_tag("side  project"), Some("side-project".to_string()));
+        assert_eq!(normalize_tag(" # Deep Work "), Some("deep-work".to_string()));
+        assert_eq!(normalize_tag(""), None);
+        assert_eq!(normalize_tag("  #  "), None);
+    }
+
+    #[test]
+    fn test_suggest_tags_by_prefix_and_frequency() {
+        let known = known(&[("work", 2), ("writing", 5), ("home", 9), ("Web", 2)]);
+
+        assert_eq!(suggest_tags("w", &known), vec!["writing", "Web", "work"]);
+        assert_eq!(suggest_tags("WO", &known), vec!["work"]);
+        assert!(suggest_tags("x", &known).is_empty());
+        assert!(suggest_tags("", &known).is_empty());
+        assert_eq!(suggest_tags("#wr", &known), vec!["writing"]);
+    }
+}
@@ -1,8 +1,93 @@
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Duration, Utc};
 use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
 use uuid::Uuid;
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
+/// List that todos belong to unless another one is given.
+pub const DEFAULT_LIST: &str = "Inbox";
+
+fn default_list() -> String {
+    DEFAULT_LIST.to_string()
+}
+
+/// How long before its due date a todo counts as due soon, unless it sets
+/// its own `remind_before`.
+pub const DEFAULT_REMIND_BEFORE: Duration = Duration::hours(24);
+
+/// Stores an optional lead time as whole seconds. Negative or out-of-range
+/// values are rejected when reading.
+mod lead_seconds {
+    use chrono::Duration;
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(value: &Option<Duration>, serializer: S) -> Result<S::Ok, S::Error> {
+        value.map(|lead| lead.num_seconds()).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Duration>, D::Error> {
+        let Some(seconds) = Option::<i64>::deserialize(deserializer)? else {
+            return Ok(None);
+        };
+        match Duration::try_seconds(seconds) {
+            Some(lead) if seconds >= 0 => Ok(Some(lead)),
+            _ => Err(D::Error::custom(format!("invalid remind_before_secs {}", seconds))),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum Priority {
+    Low,
+    #[default]
+    Medium,
+    High,
+}
+
+impl Priority {
+    /// Cycles Low → Medium → High → Low.
+    pub fn next(self) -> Self {
+        match self {
+            Priority::Low => Priority::Medium,
+            Priority::Medium => Priority::High,
+            Priority::High => Priority::Low,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Priority::Low => "low",
+            Priority::Medium => "medium",
+            Priority::High => "high",
+        }
+    }
+}
+
+/// Ticked and total subtasks in `subtasks`, or `None` when it's empty.
+pub fn subtask_progress(subtasks: &[Subtask]) -> Option<(usize, usize)> {
+    if subtasks.is_empty() {
+        return None;
+    }
+    let done = subtasks.iter().filter(|subtask| subtask.done).count();
+    Some((done, subtasks.len()))
+}
+
+/// A checklist item inside a todo.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Subtask {
+    pub subject: String,
+    #[serde(default)]
+    pub done: bool,
+}
+
+impl Subtask {
+    pub fn new(subject: String) -> Self {
+        Self { subject, done: false }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct Todo {
     pub id: String,
     pub subject: String,
@@ -10,6 +95,34 @@ pub struct Todo {
     pub created_at: DateTime<Utc>,
     pub closed_at: Option<DateTime<Utc>>,
     pub last_modified_at: DateTime<Utc>,
+    #[serde(default)]
+    pub due_date: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub attachments: Vec<PathBuf>,
+    /// Named list (project) the todo belongs to.
+    #[serde(default = "default_list")]
+    pub list: String,
+    #[serde(default)]
+    pub priority: Priority,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Longer free-form notes, kept apart from the description.
+    #[serde(default)]
+    pub notes: String,
+    /// Checklist items; ticking the last open one completes the todo.
+    #[serde(default)]
+    pub subtasks: Vec<Subtask>,
+    /// How long before the due date to flag the todo as due soon;
+    /// `None` uses `DEFAULT_REMIND_BEFORE`.
+    #[serde(default, rename = "remind_before_secs", with = "lead_seconds")]
+    pub remind_before: Option<Duration>,
+    /// Kept as a pattern for new todos (see `from_template`).
+    #[serde(default)]
+    pub is_template: bool,
+    /// Short sequential reference such as `work-3`, given to new todos
+    /// when the `sequential_ids` pref is on.
+    #[serde(default)]
+    pub seq_id: Option<String>,
 }
 
 impl Todo {
@@ -22,13 +135,104 @@ impl Todo {
             created_at: now,
             closed_at: None,
             last_modified_at: now,
+            due_date: None,
+            attachments: Vec::new(),
+            list: default_list(),
+            priority: Priority::default(),
+            tags: Vec::new(),
+            notes: String::new(),
+            subtasks: Vec::new(),
+            remind_before: None,
+            is_template: false,
+            seq_id: None,
         }
     }
 
+    /// A new todo with this one's subject, description, list, priority,
+    /// tags and subtasks (all unticked), but its own id and dates.
+    pub fn from_template(&self) -> Todo {
+        let mut todo = Todo::new(self.subject.clone(), self.description.clone());
+        todo.list = self.list.clone();
+        todo.priority = self.priority;
+        todo.tags = self.tags.clone();
+        todo.subtasks = self
+            .subtasks
+            .iter()
+            .map(|subtask| Subtask::new(subtask.subject.clone()))
+            .collect();
+        todo
+    }
+
+    /// The sequential id if the todo has one, otherwise the first 8
+    /// characters of the id; either is enough to tell todos apart in the
+    /// list and to pass to `todocli done`. Shorter ids are returned whole.
+    pub fn short_id(&self) -> &str {
+        match &self.seq_id {
+            Some(seq_id) => seq_id,
+            None => self.id.get(..8).unwrap_or(&self.id),
+        }
+    }
+
+    /// How long the todo has been open (active) or took to complete
+    /// (completed), in its largest whole unit: `2d`, `3h`, `5m`.
+    pub fn duration_label(&self, now: DateTime<Utc>) -> String {
+        let end = self.closed_at.unwrap_or(now);
+        compact_duration(end.signed_duration_since(self.created_at))
+    }
+
     pub fn is_completed(&self) -> bool {
         self.closed_at.is_some()
     }
 
+    pub fn is_overdue(&self) -> bool {
+        match self.due_date {
+            Some(due) => !self.is_completed() && due < Utc::now(),
+            None => false,
+        }
+    }
+
+    /// Active, not yet overdue, and within its reminder lead time of the
+    /// due date.
+    pub fn is_due_soon(&self, now: DateTime<Utc>) -> bool {
+        let Some(due) = self.due_date else {
+            return false;
+        };
+        let lead = self.remind_before.unwrap_or(DEFAULT_REMIND_BEFORE);
+        !self.is_completed() && due >= now && due - now <= lead
+    }
+
+    /// One line for desktop notifications: status, subject and priority,
+    /// e.g. `⚠ Overdue: Buy milk (high)`.
+    pub fn notification_summary(&self) -> String {
+        let status = if self.is_completed() {
+            "✓ Done"
+        } else if self.is_overdue() {
+            "⚠ Overdue"
+        } else if self.is_due_soon(Utc::now()) {
+            "⏰ Due soon"
+        } else {
+            "○ Active"
+        };
+        format!("{}: {} ({})", status, self.subject, self.priority.label())
+    }
+
+    /// Whether `created_at` is later than `now`, e.g. after clock skew or a
+    /// bad import.
+    pub fn has_future_created(&self, now: DateTime<Utc>) -> bool {
+        self.created_at > now
+    }
+
+    /// Moves a future `created_at` back to `now`, or to the completion
+    /// time if that is earlier. Returns whether anything changed.
+    pub fn clamp_created_at(&mut self, now: DateTime<Utc>) -> bool {
+        if !self.has_future_created(now) {
+            return false;
+        }
+        self.created_at = self.closed_at.map_or(now, |closed| closed.min(now));
+        self.last_modified_at = now;
+        true
+    }
+
     pub fn toggle_completion(&mut self) {
         let now = Utc::now();
         if self.is_completed() {
@@ -39,10 +243,81 @@ impl Todo {
         self.last_modified_at = now;
     }
 
-    pub fn update(&mut self, subject: String, description: String) {
+    /// Swaps the subtask at `index` with the one above (`up`) or below it.
+    /// Returns false, changing nothing, when there is no such neighbour.
+    pub fn move_subtask(&mut self, index: usize, up: bool) -> bool {
+        let target = if up { index.checked_sub(1) } else { Some(index + 1) };
+        match target {
+            Some(target) if index < self.subtasks.len() && target < self.subtasks.len() => {
+                self.subtasks.swap(index, target);
+                self.last_modified_at = Utc::now();
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Ticked and total subtasks, or `None` without any.
+    pub fn subtask_progress(&self) -> Option<(usize, usize)> {
+        subtask_progress(&self.subtasks)
+    }
+
+    pub fn has_incomplete_subtasks(&self) -> bool {
+        self.subtasks.iter().any(|subtask| !subtask.done)
+    }
+
+    /// Ticks or unticks the subtask at `index`. Ticking the last open
+    /// subtask completes the todo; unticking one reopens it. Returns false
+    /// when there is no such subtask.
+    pub fn toggle_subtask(&mut self, index: usize) -> bool {
+        let Some(subtask) = self.subtasks.get_mut(index) else {
+            return false;
+        };
+        subtask.done = !subtask.done;
+        let done = subtask.done;
+        let now = Utc::now();
+        if done && !self.has_incomplete_subtasks() && !self.is_completed() {
+            self.closed_at = Some(now);
+        } else if !done && self.is_completed() {
+            self.closed_at = None;
+        }
+        self.last_modified_at = now;
+        true
+    }
+
+    /// Replaces subject and description. `last_modified_at` only moves when
+    /// either actually changed, so saving an untouched edit keeps the todo's
+    /// place in the list. Returns whether anything changed.
+    pub fn update(&mut self, subject: String, description: String) -> bool {
+        if self.subject == subject && self.description == description {
+            return false;
+        }
         self.subject = subject;
         self.description = description;
         self.last_modified_at = Utc::now();
+        true
+    }
+
+    /// Adds a file reference unless it is already attached. Returns whether
+    /// the list changed.
+    pub fn add_attachment(&mut self, path: PathBuf) -> bool {
+        if self.attachments.contains(&path) {
+            return false;
+        }
+        self.attachments.push(path);
+        self.last_modified_at = Utc::now();
+        true
+    }
+
+    /// Removes a file reference. Returns whether it was attached.
+    pub fn remove_attachment(&mut self, path: &Path) -> bool {
+        let before = self.attachments.len();
+        self.attachments.retain(|attached| attached != path);
+        let removed = self.attachments.len() != before;
+        if removed {
+            self.last_modified_at = Utc::now();
+        }
+        removed
     }
 
     pub fn status_icon(&self) -> &'static str {
@@ -54,6 +329,26 @@ impl Todo {
     }
 }
 
+/// Short label for an attachment: the file name, or the whole path when it
+/// has none (e.g. `/` or `..`).
+pub fn attachment_label(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.display().to_string())
+}
+
+/// A duration in its largest whole unit: `2d`, `3h` or `5m`. Negative
+/// durations show as `0m`.
+pub(crate) fn compact_duration(duration: Duration) -> String {
+    if duration.num_days() > 0 {
+        format!("{}d", duration.num_days())
+    } else if duration.num_hours() > 0 {
+        format!("{}h", duration.num_hours())
+    } else {
+        format!("{}m", duration.num_minutes().max(0))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -71,6 +366,7 @@ mod tests {
         assert!(!todo.is_completed());
         assert_eq!(todo.closed_at, None);
         assert_eq!(todo.created_at, todo.last_modified_at);
+        assert_eq!(todo.list, DEFAULT_LIST);
     }
 
     #[test]
@@ -119,6 +415,20 @@ mod tests {
         assert!(todo.last_modified_at > original_last_modified);
     }
 
+    #[test]
+    fn test_identical_update_keeps_timestamp() {
+        let mut todo = Todo::new("Same".to_string(), "Same description".to_string());
+        let original_last_modified = todo.last_modified_at;
+
+        std::thread::sleep(std::time::Duration::from_millis(1));
+
+        assert!(!todo.update("Same".to_string(), "Same description".to_string()));
+        assert_eq!(todo.last_modified_at, original_last_modified);
+
+        assert!(todo.update("Same".to_string(), "Changed".to_string()));
+        assert!(todo.last_modified_at > original_last_modified);
+    }
+
     #[test]
     fn test_status_icon() {
         let mut todo = Todo::new("Test".to_string(), "Description".to_string());
@@ -136,6 +446,22 @@ mod tests {
         assert_eq!(todo.status_icon(), incomplete_icon);
     }
 
+    #[test]
+    fn test_is_overdue() {
+        let mut todo = Todo::new("Test".to_string(), "Description".to_string());
+        assert!(!todo.is_overdue());
+
+        todo.due_date = Some(Utc::now() + chrono::Duration::days(1));
+        assert!(!todo.is_overdue());
+
+        todo.due_date = Some(Utc::now() - chrono::Duration::days(1));
+        assert!(todo.is_overdue());
+
+        // Completed todos are never overdue
+        todo.toggle_completion();
+        assert!(!todo.is_overdue());
+    }
+
     #[test]
     fn test_is_completed() {
         let mut todo = Todo::new("Test".to_string(), "Description".to_string());
@@ -148,4 +474,229 @@ mod tests {
         todo.toggle_completion();
         assert!(!todo.is_completed());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_add_and_remove_attachment() {
+        let mut todo = Todo::new("Test".to_string(), "Description".to_string());
+        let report = PathBuf::from("/home/user/docs/report.pdf");
+        let notes = PathBuf::from("notes.txt");
+
+        assert!(todo.add_attachment(report.clone()));
+        assert!(todo.add_attachment(notes.clone()));
+        // Attaching the same path twice is a no-op
+        assert!(!todo.add_attachment(report.clone()));
+        assert_eq!(todo.attachments, vec![report.clone(), notes.clone()]);
+
+        assert!(todo.remove_attachment(&report));
+        assert!(!todo.remove_attachment(&report));
+        assert_eq!(todo.attachments, vec![notes]);
+    }
+
+    #[test]
+    fn test_attachment_label() {
+        assert_eq!(attachment_label(Path::new("/home/user/docs/report.pdf")), "report.pdf");
+        assert_eq!(attachment_label(Path::new("notes.txt")), "notes.txt");
+        assert_eq!(attachment_label(Path::new("/")), "/");
+    }
+
+    #[test]
+    fn test_priority_cycle_and_default() {
+        let todo = Todo::new("Test".to_string(), String::new());
+        assert_eq!(todo.priority, Priority::Medium);
+        assert_eq!(Priority::Low.next(), Priority::Medium);
+        assert_eq!(Priority::Medium.next(), Priority::High);
+        assert_eq!(Priority::High.next(), Priority::Low);
+    }
+
+    #[test]
+    fn test_duration_label() {
+        let now = Utc::now();
+        let mut active = Todo::new("Active".to_string(), String::new());
+        active.created_at = now - chrono::Duration::days(2) - chrono::Duration::hours(5);
+        assert_eq!(active.duration_label(now), "2d");
+
+        let mut completed = Todo::new("Completed".to_string(), String::new());
+        completed.created_at = now - chrono::Duration::days(10);
+        completed.closed_at = Some(completed.created_at + chrono::Duration::hours(3));
+        assert_eq!(completed.duration_label(now), "3h");
+
+        let fresh = Todo::new("Fresh".to_string(), String::new());
+        assert_eq!(fresh.duration_label(fresh.created_at), "0m");
+    }
+
+    #[test]
+    fn test_completing_last_subtask_closes_todo() {
+        let mut todo = Todo::new("Move house".to_string(), String::new());
+        todo.subtasks = vec![Subtask::new("Pack".to_string()), Subtask::new("Ship".to_string())];
+
+        assert!(todo.toggle_subtask(0));
+        assert!(todo.has_incomplete_subtasks());
+        assert!(!todo.is_completed());
+
+        assert!(todo.toggle_subtask(1));
+        assert!(!todo.has_incomplete_subtasks());
+        assert!(todo.is_completed());
+
+        assert!(!todo.toggle_subtask(2));
+    }
+
+    #[test]
+    fn test_reopening_subtask_reopens_todo() {
+        let mut todo = Todo::new("Move house".to_string(), String::new());
+        todo.subtasks = vec![Subtask::new("Pack".to_string())];
+        todo.toggle_subtask(0);
+        assert!(todo.is_completed());
+
+        todo.toggle_subtask(0);
+        assert!(!todo.subtasks[0].done);
+        assert!(!todo.is_completed());
+    }
+
+    #[test]
+    fn test_due_soon_uses_own_lead_time() {
+        let now = Utc::now();
+        let mut long_lead = Todo::new("Passport".to_string(), String::new());
+        long_lead.due_date = Some(now + Duration::days(2));
+        long_lead.remind_before = Some(Duration::days(3));
+        let mut short_lead = long_lead.clone();
+        short_lead.remind_before = Some(Duration::hours(1));
+
+        assert!(long_lead.is_due_soon(now));
+        assert!(!short_lead.is_due_soon(now));
+        assert!(short_lead.is_due_soon(now + Duration::days(2) - Duration::minutes(30)));
+
+        // Without a lead time of its own the default day applies
+        short_lead.remind_before = None;
+        assert!(!short_lead.is_due_soon(now));
+        assert!(short_lead.is_due_soon(now + Duration::days(1) + Duration::hours(1)));
+        // Overdue is not "soon"
+        assert!(!short_lead.is_due_soon(now + Duration::days(3)));
+    }
+
+    #[test]
+    fn test_subtask_progress() {
+        let mut todo = Todo::new("Trip".to_string(), String::new());
+        assert_eq!(todo.subtask_progress(), None);
+        todo.subtasks = ["Pack", "Go", "Rest"].map(|subject| Subtask::new(subject.to_string())).into();
+        todo.toggle_subtask(1);
+        assert_eq!(todo.subtask_progress(), Some((1, 3)));
+    }
+
+    #[test]
+    fn test_remind_before_serde() {
+        let mut todo = Todo::new("Passport".to_string(), String::new());
+        todo.remind_before = Some(Duration::days(3));
+        let json = serde_json::to_string(&todo).unwrap();
+        assert!(json.contains("\"remind_before_secs\":259200"));
+        let loaded: Todo = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.remind_before, Some(Duration::days(3)));
+
+        let old = json.replace(",\"remind_before_secs\":259200", "");
+        let loaded: Todo = serde_json::from_str(&old).unwrap();
+        assert_eq!(loaded.remind_before, None);
+
+        for bad in ["-60", "99999999999999999"] {
+            let edited = json.replace("259200", bad);
+            let error = serde_json::from_str::<Todo>(&edited).unwrap_err();
+            assert!(error.to_string().contains("invalid remind_before_secs"));
+        }
+    }
+
+    #[test]
+    fn test_short_id() {
+        let mut todo = Todo::new("Test".to_string(), String::new());
+        assert_eq!(todo.short_id().len(), 8);
+        assert!(todo.id.starts_with(todo.short_id()));
+
+        todo.id = "abc".to_string();
+        assert_eq!(todo.short_id(), "abc");
+
+        todo.seq_id = Some("work-3".to_string());
+        assert_eq!(todo.short_id(), "work-3");
+    }
+
+    #[test]
+    fn test_future_created_detected_and_clamped() {
+        let now = Utc::now();
+        let mut todo = Todo::new("Skewed".to_string(), String::new());
+        todo.created_at = now + Duration::days(3);
+        assert!(todo.has_future_created(now));
+
+        assert!(todo.clamp_created_at(now));
+        assert_eq!(todo.created_at, now);
+        assert!(!todo.has_future_created(now));
+        assert!(!todo.clamp_created_at(now));
+
+        // A completed todo isn't left closed before it was created
+        let mut done = Todo::new("Done".to_string(), String::new());
+        done.created_at = now + Duration::days(1);
+        done.closed_at = Some(now - Duration::hours(2));
+        assert!(done.clamp_created_at(now));
+        assert_eq!(done.created_at, now - Duration::hours(2));
+    }
+
+    #[test]
+    fn test_from_template_copies_content_not_state() {
+        let mut template = Todo::new("Weekly review".to_string(), "Go through inbox".to_string());
+        template.is_template = true;
+        template.list = "Work".to_string();
+        template.priority = Priority::High;
+        template.tags = vec!["review".to_string()];
+        template.subtasks = vec![Subtask::new("Inbox".to_string()), Subtask::new("Calendar".to_string())];
+        template.subtasks[0].done = true;
+        template.created_at = Utc::now() - Duration::days(30);
+        template.due_date = Some(Utc::now() - Duration::days(1));
+        template.closed_at = Some(Utc::now());
+
+        let todo = template.from_template();
+        assert_ne!(todo.id, template.id);
+        assert_eq!(todo.subject, "Weekly review");
+        assert_eq!(todo.description, "Go through inbox");
+        assert_eq!(todo.list, "Work");
+        assert_eq!(todo.priority, Priority::High);
+        assert_eq!(todo.tags, ["review"]);
+        assert_eq!(todo.subtasks.len(), 2);
+        assert!(todo.subtasks.iter().all(|subtask| !subtask.done));
+        assert!(todo.created_at > template.created_at);
+        assert_eq!(todo.due_date, None);
+        assert!(!todo.is_completed());
+        assert!(!todo.is_template);
+    }
+
+    #[test]
+    fn test_notification_summary() {
+        let mut overdue = Todo::new("Buy milk".to_string(), String::new());
+        overdue.priority = Priority::High;
+        overdue.due_date = Some(Utc::now() - Duration::hours(1));
+        assert_eq!(overdue.notification_summary(), "⚠ Overdue: Buy milk (high)");
+
+        let active = Todo::new("Call mum".to_string(), String::new());
+        assert_eq!(active.notification_summary(), "○ Active: Call mum (medium)");
+
+        let mut soon = Todo::new("Pay rent".to_string(), String::new());
+        soon.due_date = Some(Utc::now() + Duration::hours(2));
+        assert_eq!(soon.notification_summary(), "⏰ Due soon: Pay rent (medium)");
+
+        let mut done = overdue.clone();
+        done.closed_at = Some(Utc::now());
+        assert_eq!(done.notification_summary(), "✓ Done: Buy milk (high)");
+    }
+
+    #[test]
+    fn test_move_subtask() {
+        let mut todo = Todo::new("Trip".to_string(), String::new());
+        todo.subtasks = ["Pack", "Book", "Go"].map(|subject| Subtask::new(subject.to_string())).into();
+        let order = |todo: &Todo| todo.subtasks.iter().map(|subtask| subtask.subject.clone()).collect::<Vec<_>>();
+
+        assert!(todo.move_subtask(1, true));
+        assert_eq!(order(&todo), ["Book", "Pack", "Go"]);
+        assert!(todo.move_subtask(1, false));
+        assert_eq!(order(&todo), ["Book", "Go", "Pack"]);
+
+        // The first can't move up, the last can't move down
+        assert!(!todo.move_subtask(0, true));
+        assert!(!todo.move_subtask(2, false));
+        assert!(!todo.move_subtask(5, true));
+        assert_eq!(order(&todo), ["Book", "Go", "Pack"]);
+    }
+}
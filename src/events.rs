@@ -7,6 +7,7 @@ pub enum AppEvent {
     Tick,
 }
 
+#[derive(Default)]
 pub struct EventHandler;
 
 impl EventHandler {
@@ -27,30 +28,76 @@ impl EventHandler {
 }
 
 pub fn handle_key_event(app: &mut crate::app::App, key: KeyEvent) -> Result<(), Box<dyn std::error::Error>> {
+    crate::logging::log("key", &format!("{:?} {:?} in {:?}", key.code, key.modifiers, app.state));
+    // A read-only config dir shouldn't end the session
+    match dispatch_key(app, key) {
+        Err(err) if !app.report_save_failure() => Err(err),
+        _ => Ok(()),
+    }
+}
+
+fn dispatch_key(app: &mut crate::app::App, key: KeyEvent) -> Result<(), Box<dyn std::error::Error>> {
     use crate::app::AppState;
 
     match app.state {
         AppState::Main => handle_main_keys(app, key)?,
         AppState::Detail => handle_detail_keys(app, key)?,
         AppState::Confirm => handle_confirm_keys(app, key)?,
+        AppState::Search => handle_search_keys(app, key),
+        AppState::Input => handle_input_keys(app, key)?,
+        AppState::Help | AppState::ThemePreview | AppState::RecentlyCompleted => app.close_overlay(),
     }
 
     Ok(())
 }
 
 fn handle_main_keys(app: &mut crate::app::App, key: KeyEvent) -> Result<(), Box<dyn std::error::Error>> {
-    let todos = app.get_current_todos();
-    let len = todos.len();
+    let len = app.get_current_todo_refs().len();
 
     match key.code {
         KeyCode::Char('q') => app.quit(),
+        // Raw mode turns Ctrl+C into a key press instead of SIGINT
+        KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => app.quit(),
         KeyCode::Char('j') | KeyCode::Down => app.main_view.next(len),
         KeyCode::Char('k') | KeyCode::Up => app.main_view.previous(len),
         KeyCode::Enter => app.open_detail_view(),
         KeyCode::Char('d') => app.toggle_selected_todo()?,
         KeyCode::Char('n') => app.open_new_todo(),
+        KeyCode::Char('M') => app.toggle_selected_template()?,
+        KeyCode::Char('N') => app.new_from_selected_template()?,
+        KeyCode::Char(' ') => app.toggle_mark_selected(),
+        KeyCode::Esc => {
+            app.clear_marks();
+            app.clear_completion_view();
+        }
         KeyCode::Char('x') => app.confirm_delete_selected(),
+        KeyCode::Char('C') => app.confirm_clear_completed(),
+        KeyCode::Char('D') => app.prompt_due_date_for_view(),
+        KeyCode::Char('F') => app.confirm_complete_view(),
+        KeyCode::Char('f') => app.toggle_focus(std::time::Instant::now()),
         KeyCode::Char('e') => app.open_edit_view(),
+        KeyCode::Char('o') => app.toggle_overdue_filter(),
+        KeyCode::Char(']') => app.select_next_overdue(),
+        KeyCode::Char('p') => app.cycle_selected_priority()?,
+        KeyCode::Char('P') => app.cycle_priority_filter(),
+        KeyCode::Char('K') => app.toggle_priority_filter_sticky()?,
+        KeyCode::Char('i') => app.invert_completion_view(),
+        KeyCode::Char('/') => app.start_search(),
+        KeyCode::Char('L') => app.next_list(),
+        KeyCode::Tab | KeyCode::Right => app.next_tab(),
+        KeyCode::BackTab | KeyCode::Left => app.previous_tab(),
+        KeyCode::Char('+') => app.adjust_daily_goal(true)?,
+        KeyCode::Char('-') => app.adjust_daily_goal(false)?,
+        KeyCode::Char('s') => app.cycle_sort_mode(),
+        KeyCode::Char('S') => app.toggle_sort_direction()?,
+        KeyCode::Char('I') => app.toggle_short_ids()?,
+        KeyCode::Char('A') => app.toggle_durations()?,
+        KeyCode::Char('c') => app.toggle_compact()?,
+        KeyCode::Char('z') => app.toggle_completed_collapsed(),
+        KeyCode::Char('T') => app.show_theme_preview(),
+        KeyCode::Char('R') => app.show_recently_completed(),
+        KeyCode::Char('y') => app.copy_view_as_markdown(),
+        KeyCode::Char('?') => app.show_help(),
         _ => {}
     }
 
@@ -62,24 +109,54 @@ fn handle_detail_keys(app: &mut crate::app::App, key: KeyEvent) -> Result<(), Bo
         match detail_view.mode {
             DetailMode::View => {
                 match key.code {
-                    KeyCode::Esc => app.close_detail_view_with_save()?,
+                    KeyCode::Esc => app.close_detail_view(),
                     KeyCode::Char('e') => {
                         detail_view.mode = DetailMode::Edit;
                     }
+                    // A missing opener shouldn't end the session, so failures are ignored
+                    KeyCode::Char('o') => {
+                        let _ = app.open_first_attachment();
+                    }
+                    KeyCode::Char('N') => detail_view.toggle_notes(),
+                    KeyCode::Char('r') => app.reset_future_created()?,
+                    KeyCode::Up if key.modifiers.contains(KeyModifiers::SHIFT) => app.move_selected_subtask(true)?,
+                    KeyCode::Down if key.modifiers.contains(KeyModifiers::SHIFT) => app.move_selected_subtask(false)?,
+                    KeyCode::Char(' ') => app.toggle_selected_subtask()?,
+                    KeyCode::Tab => detail_view.cycle_subtask(true),
+                    KeyCode::BackTab => detail_view.cycle_subtask(false),
+                    KeyCode::Char('j') | KeyCode::Down => detail_view.next_heading(),
+                    KeyCode::Char('k') | KeyCode::Up => detail_view.previous_heading(),
+                    KeyCode::Enter => detail_view.toggle_section(),
+                    KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => app.request_quit(),
                     _ => {}
                 }
             }
             DetailMode::Edit | DetailMode::New => {
                 match key.code {
-                    KeyCode::Esc => app.close_detail_view_with_save()?,
+                    // Leaving without saving discards the edits
+                    KeyCode::Esc => app.close_detail_view(),
                     KeyCode::Char('s') if key.modifiers.contains(KeyModifiers::CONTROL) => {
                         app.save_current_todo()?;
                     }
+                    KeyCode::Char('d') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        app.save_and_close_detail_view()?;
+                    }
+                    KeyCode::Char('p') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        detail_view.cycle_priority();
+                    }
+                    KeyCode::Char('n') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        detail_view.toggle_notes_field();
+                    }
+                    // Typed `q`s are text here, so only Ctrl+C quits
+                    KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => app.request_quit(),
+                    // Tab completes a suggested tag before moving on
+                    KeyCode::Tab if detail_view.accept_tag_suggestion() => {}
                     KeyCode::Tab => detail_view.next_field(),
                     KeyCode::BackTab => detail_view.previous_field(),
                     KeyCode::Char(c) => detail_view.add_char(c),
                     KeyCode::Backspace => detail_view.delete_char(),
-                    KeyCode::Enter if detail_view.current_field == 1 => detail_view.add_char('\n'),
+                    KeyCode::Enter if detail_view.on_subject_field() => app.save_current_todo()?,
+                    KeyCode::Enter if detail_view.accepts_newlines() => detail_view.add_char('\n'),
                     _ => {}
                 }
             }
@@ -89,9 +166,38 @@ fn handle_detail_keys(app: &mut crate::app::App, key: KeyEvent) -> Result<(), Bo
     Ok(())
 }
 
+fn handle_search_keys(app: &mut crate::app::App, key: KeyEvent) {
+    match key.code {
+        KeyCode::Enter => app.finish_search(),
+        KeyCode::Esc => app.cancel_search(),
+        KeyCode::Char('t') if key.modifiers.contains(KeyModifiers::CONTROL) => app.toggle_search_case(),
+        KeyCode::Char(c) => app.push_search_char(c),
+        KeyCode::Backspace => app.pop_search_char(),
+        _ => {}
+    }
+}
+
+fn handle_input_keys(app: &mut crate::app::App, key: KeyEvent) -> Result<(), Box<dyn std::error::Error>> {
+    let Some(dialog) = &mut app.input_dialog else {
+        app.close_input_dialog();
+        return Ok(());
+    };
+    match key.code {
+        KeyCode::Enter => app.submit_input()?,
+        KeyCode::Esc => app.close_input_dialog(),
+        KeyCode::Char(c) => dialog.value.push(c),
+        KeyCode::Backspace => {
+            dialog.value.pop();
+        }
+        _ => {}
+    }
+
+    Ok(())
+}
+
 fn handle_confirm_keys(app: &mut crate::app::App, key: KeyEvent) -> Result<(), Box<dyn std::error::Error>> {
     match key.code {
-        KeyCode::Char('y') => app.delete_confirmed_todo()?,
+        KeyCode::Char('y') => app.run_confirmed_action()?,
         KeyCode::Char('n') | KeyCode::Esc => app.close_confirm_dialog(),
         _ => {}
     }
@@ -103,21 +209,12 @@ fn handle_confirm_keys(app: &mut crate::app::App, key: KeyEvent) -> Result<(), B
 mod tests {
     use super::*;
     use crate::app::{App, AppState};
-    use crate::data::{Database, Todo};
-    use crate::ui::{MainView, DetailMode};
+    use crate::data::{Database, ListTab, Todo};
+    use crate::ui::DetailMode;
 
     fn create_test_app() -> App {
-        let database = Database::new_in_memory().unwrap();
-        App {
-            state: AppState::Main,
-            main_view: MainView::new(),
-            detail_view: None,
-            confirm_dialog: None,
-            database,
-            should_quit: false,
-            current_todo_id: None,
-            pending_delete_id: None,
-        }
+        let database = Database::in_memory();
+        App::with_database(database)
     }
 
     fn create_key_event(code: KeyCode) -> KeyEvent {
@@ -128,6 +225,133 @@ mod tests {
         KeyEvent::new(code, modifiers)
     }
 
+    /// Keys a help label such as `"q/Ctrl+C"` or `"j/k ↑/↓"` stands for.
+    fn listed_keys(label: &str) -> Vec<KeyEvent> {
+        let tokens: Vec<&str> = if label == "/" {
+            vec!["/"]
+        } else {
+            label.split([' ', '/']).collect()
+        };
+        tokens
+            .into_iter()
+            .map(|token| match token {
+                "Space" => create_key_event(KeyCode::Char(' ')),
+                "Enter" => create_key_event(KeyCode::Enter),
+                "Esc" => create_key_event(KeyCode::Esc),
+                "Tab" => create_key_event(KeyCode::Tab),
+                "Shift+Tab" => create_key_event_with_modifiers(KeyCode::BackTab, KeyModifiers::SHIFT),
+                "←" => create_key_event(KeyCode::Left),
+                "→" => create_key_event(KeyCode::Right),
+                "↑" => create_key_event(KeyCode::Up),
+                "↓" => create_key_event(KeyCode::Down),
+                _ => match token.strip_prefix("Ctrl+") {
+                    Some(key) => create_key_event_with_modifiers(
+                        KeyCode::Char(key.to_ascii_lowercase().chars().next().unwrap()),
+                        KeyModifiers::CONTROL,
+                    ),
+                    None => {
+                        let mut chars = token.chars();
+                        let c = chars.next().unwrap();
+                        assert!(chars.next().is_none(), "unknown key {:?} in {:?}", token, label);
+                        create_key_event(KeyCode::Char(c))
+                    }
+                },
+            })
+            .collect()
+    }
+
+    /// A main view with a selection, a mark, a daily goal, several lists,
+    /// an overdue todo, a template and a completed one, so that every main
+    /// view key has something to act on.
+    fn key_fixture_app() -> App {
+        let mut app = create_test_app();
+        let mut overdue = Todo::new("Overdue".to_string(), String::new());
+        overdue.due_date = Some(chrono::Utc::now() - chrono::Duration::days(1));
+        overdue.list = "Work".to_string();
+        let mut template = Todo::new("Template".to_string(), String::new());
+        template.is_template = true;
+        let mut done = Todo::new("Done".to_string(), String::new());
+        done.toggle_completion();
+        app.main_view.marked.insert(done.id.clone());
+        for todo in [overdue, template, done] {
+            app.database.insert_todo_for_test(todo);
+        }
+        app.main_view.table_state.select(Some(1));
+        app.prefs.daily_goal = Some(2);
+        app
+    }
+
+    /// What a key press can change, to tell whether it did anything.
+    fn observable_state(app: &App) -> String {
+        let mut marked: Vec<&String> = app.main_view.marked.iter().collect();
+        marked.sort();
+        format!(
+            "{:?} {} {:?} {:?} {:?} {:?} {:?} {:?} {:?} {:?} {} {} {} {} {} {:?} {} {} {}",
+            app.state,
+            app.should_quit,
+            app.filter,
+            app.prefs,
+            app.main_view.table_state.selected(),
+            marked,
+            app.status_message.as_ref().map(|(message, _)| message),
+            app.get_current_todos(),
+            app.pending_action,
+            app.focus_todo_id,
+            app.main_view.show_short_ids,
+            app.main_view.show_durations,
+            app.main_view.compact,
+            app.completed_collapsed,
+            app.detail_view.is_some(),
+            app.database.get_all_todos(),
+            app.confirm_dialog.is_some(),
+            app.input_dialog.is_some(),
+            app.search_input,
+        )
+    }
+
+    #[test]
+    fn test_main_commands_cover_handled_keys() {
+        let does_something = |key: KeyEvent| {
+            let mut app = key_fixture_app();
+            let before = observable_state(&app);
+            handle_main_keys(&mut app, key).unwrap();
+            observable_state(&app) != before
+        };
+        let listed: Vec<KeyEvent> = crate::ui::help::commands_for(&AppState::Main)
+            .iter()
+            .flat_map(|(label, _)| listed_keys(label))
+            .collect();
+
+        for key in &listed {
+            assert!(does_something(*key), "{:?} is listed but not handled", key);
+        }
+
+        let mut keys: Vec<KeyEvent> = (' '..='~').map(|c| create_key_event(KeyCode::Char(c))).collect();
+        keys.extend(
+            [
+                KeyCode::Enter,
+                KeyCode::Esc,
+                KeyCode::Tab,
+                KeyCode::BackTab,
+                KeyCode::Backspace,
+                KeyCode::Delete,
+                KeyCode::Left,
+                KeyCode::Right,
+                KeyCode::Up,
+                KeyCode::Down,
+                KeyCode::Home,
+                KeyCode::End,
+                KeyCode::PageUp,
+                KeyCode::PageDown,
+            ]
+            .map(create_key_event),
+        );
+        for key in keys {
+            let is_listed = listed.iter().any(|listed| listed.code == key.code);
+            assert!(is_listed || !does_something(key), "{:?} is handled but not listed", key);
+        }
+    }
+
     #[test]
     fn test_event_handler_creation() {
         let handler = EventHandler::new();
@@ -145,6 +369,17 @@ mod tests {
         assert!(app.should_quit);
     }
 
+    #[test]
+    fn test_main_keys_ctrl_c_quits() {
+        let mut app = create_test_app();
+        handle_main_keys(&mut app, create_key_event(KeyCode::Char('c'))).unwrap();
+        assert!(!app.should_quit);
+
+        let key = create_key_event_with_modifiers(KeyCode::Char('c'), KeyModifiers::CONTROL);
+        handle_main_keys(&mut app, key).unwrap();
+        assert!(app.should_quit);
+    }
+
     #[test]
     fn test_main_keys_navigation() {
         let mut app = create_test_app();
@@ -190,6 +425,23 @@ mod tests {
         assert!(app.detail_view.is_some());
     }
 
+    #[test]
+    fn test_main_keys_switch_tabs() {
+        let mut app = create_test_app();
+
+        handle_main_keys(&mut app, create_key_event(KeyCode::Right)).unwrap();
+        assert_eq!(app.filter.tab, ListTab::Active);
+
+        handle_main_keys(&mut app, create_key_event(KeyCode::Tab)).unwrap();
+        assert_eq!(app.filter.tab, ListTab::Completed);
+
+        handle_main_keys(&mut app, create_key_event(KeyCode::Left)).unwrap();
+        assert_eq!(app.filter.tab, ListTab::Active);
+
+        handle_main_keys(&mut app, create_key_event(KeyCode::BackTab)).unwrap();
+        assert_eq!(app.filter.tab, ListTab::All);
+    }
+
     #[test]
     fn test_main_keys_new_todo() {
         let mut app = create_test_app();
@@ -267,6 +519,72 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_save_failure_is_reported_not_propagated() {
+        let mut database = Database::in_memory();
+        let missing_dir = std::env::temp_dir().join(format!("todocli-missing-{}", uuid::Uuid::new_v4()));
+        database.set_file_path_for_test(missing_dir.join("todo.gdbm"));
+        database.insert_todo_for_test(Todo::new("Keep me".to_string(), String::new()));
+        let mut app = App::with_database(database);
+        app.main_view.table_state.select(Some(0));
+
+        assert!(handle_key_event(&mut app, create_key_event(KeyCode::Char('p'))).is_ok());
+        assert!(app.status_is_error);
+        assert!(app.status_message.as_ref().unwrap().0.starts_with("Not saved"));
+        assert!(!app.should_quit);
+        // The change is kept for the rest of the session
+        assert_eq!(app.get_current_todos()[0].priority, crate::data::Priority::High);
+    }
+
+    #[test]
+    fn test_failed_save_of_new_todo_does_not_add_it_twice() {
+        let mut database = Database::in_memory();
+        let missing_dir = std::env::temp_dir().join(format!("todocli-missing-{}", uuid::Uuid::new_v4()));
+        database.set_file_path_for_test(missing_dir.join("todo.gdbm"));
+        let mut app = App::with_database(database);
+        app.open_new_todo();
+        for c in "Once".chars() {
+            handle_key_event(&mut app, create_key_event(KeyCode::Char(c))).unwrap();
+        }
+
+        let ctrl_s = create_key_event_with_modifiers(KeyCode::Char('s'), KeyModifiers::CONTROL);
+        assert!(handle_key_event(&mut app, ctrl_s).is_ok());
+        assert!(app.status_is_error);
+        assert!(matches!(app.detail_view.as_ref().unwrap().mode, DetailMode::Edit));
+        assert!(handle_key_event(&mut app, ctrl_s).is_ok());
+        assert_eq!(app.database.get_all_todos().len(), 1);
+    }
+
+    #[test]
+    fn test_failed_save_closes_confirm_dialog() {
+        let mut database = Database::in_memory();
+        let missing_dir = std::env::temp_dir().join(format!("todocli-missing-{}", uuid::Uuid::new_v4()));
+        database.set_file_path_for_test(missing_dir.join("todo.gdbm"));
+        database.insert_todo_for_test(Todo::new("Delete me".to_string(), String::new()));
+        let mut app = App::with_database(database);
+        app.main_view.table_state.select(Some(0));
+
+        app.confirm_delete_selected();
+        assert!(handle_key_event(&mut app, create_key_event(KeyCode::Char('y'))).is_ok());
+        assert!(app.status_is_error);
+        assert!(app.confirm_dialog.is_none());
+        assert!(matches!(app.state, AppState::Main));
+    }
+
+    #[test]
+    fn test_prefs_save_failure_is_reported_not_propagated() {
+        let mut app = create_test_app();
+        let missing_dir = std::env::temp_dir().join(format!("todocli-missing-{}", uuid::Uuid::new_v4()));
+        app.prefs = crate::data::Prefs::load_from(&missing_dir.join("prefs.json"));
+        let direction = app.prefs.sort_direction;
+
+        assert!(handle_key_event(&mut app, create_key_event(KeyCode::Char('S'))).is_ok());
+        assert!(app.status_is_error);
+        assert!(app.status_message.as_ref().unwrap().0.contains("prefs.json"));
+        assert!(!app.should_quit);
+        assert_ne!(app.prefs.sort_direction, direction);
+    }
+
     #[test]
     fn test_detail_keys_edit_mode() {
         let mut app = create_test_app();
@@ -293,6 +611,101 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn test_ctrl_n_jumps_to_notes() {
+        let mut app = create_test_app();
+        app.open_new_todo();
+        let ctrl_n = create_key_event_with_modifiers(KeyCode::Char('n'), KeyModifiers::CONTROL);
+
+        for field in [0, 1, 3, 5] {
+            app.detail_view.as_mut().unwrap().current_field = field;
+            handle_detail_keys(&mut app, ctrl_n).unwrap();
+            assert_eq!(app.detail_view.as_ref().unwrap().current_field, 2);
+        }
+
+        // From the notes it goes back to the description, without typing an `n`
+        handle_detail_keys(&mut app, ctrl_n).unwrap();
+        let detail_view = app.detail_view.as_ref().unwrap();
+        assert_eq!(detail_view.current_field, 1);
+        assert!(detail_view.notes.is_empty());
+    }
+
+    #[test]
+    fn test_enter_in_subject_field_saves() {
+        let mut app = create_test_app();
+        app.open_new_todo();
+        for c in "Buy milk".chars() {
+            handle_detail_keys(&mut app, create_key_event(KeyCode::Char(c))).unwrap();
+        }
+
+        handle_detail_keys(&mut app, create_key_event(KeyCode::Enter)).unwrap();
+        let todos = app.get_current_todos();
+        assert_eq!(todos.len(), 1);
+        assert_eq!(todos[0].subject, "Buy milk");
+        assert!(!todos[0].description.contains('\n'));
+    }
+
+    #[test]
+    fn test_ctrl_c_with_unsaved_edits_asks_first() {
+        let mut app = create_test_app();
+        app.open_new_todo();
+        handle_detail_keys(&mut app, create_key_event(KeyCode::Char('q'))).unwrap();
+        assert_eq!(app.detail_view.as_ref().unwrap().subject, "q");
+
+        let ctrl_c = create_key_event_with_modifiers(KeyCode::Char('c'), KeyModifiers::CONTROL);
+        handle_detail_keys(&mut app, ctrl_c).unwrap();
+        assert!(!app.should_quit);
+        assert!(matches!(app.state, AppState::Confirm));
+
+        // Declining returns to the form with the edits kept
+        handle_confirm_keys(&mut app, create_key_event(KeyCode::Char('n'))).unwrap();
+        assert!(matches!(app.state, AppState::Detail));
+        assert_eq!(app.detail_view.as_ref().unwrap().subject, "q");
+
+        handle_detail_keys(&mut app, ctrl_c).unwrap();
+        handle_confirm_keys(&mut app, create_key_event(KeyCode::Char('y'))).unwrap();
+        assert!(app.should_quit);
+        assert!(app.database.get_all_todos().is_empty());
+    }
+
+    #[test]
+    fn test_ctrl_c_without_changes_quits() {
+        let mut app = create_test_app();
+        app.database.insert_todo_for_test(Todo::new("Saved".to_string(), String::new()));
+        app.open_edit_view();
+
+        let ctrl_c = create_key_event_with_modifiers(KeyCode::Char('c'), KeyModifiers::CONTROL);
+        handle_detail_keys(&mut app, ctrl_c).unwrap();
+        assert!(app.should_quit);
+        assert!(app.confirm_dialog.is_none());
+    }
+
+    #[test]
+    fn test_search_keys() {
+        let mut app = create_test_app();
+        app.database.insert_todo_for_test(Todo::new("Write Docs".to_string(), String::new()));
+        app.database.insert_todo_for_test(Todo::new("Fix tests".to_string(), String::new()));
+
+        handle_main_keys(&mut app, create_key_event(KeyCode::Char('/'))).unwrap();
+        assert!(matches!(app.state, AppState::Search));
+
+        for c in "docs".chars() {
+            handle_search_keys(&mut app, create_key_event(KeyCode::Char(c)));
+        }
+        assert_eq!(app.search_input, "docs");
+        app.apply_search_if_due(std::time::Instant::now() + crate::app::SEARCH_DEBOUNCE);
+        assert_eq!(app.filter.query, "docs");
+        assert_eq!(app.get_current_todos().len(), 1);
+
+        handle_search_keys(&mut app, create_key_event_with_modifiers(KeyCode::Char('t'), KeyModifiers::CONTROL));
+        assert!(app.filter.case_sensitive);
+        assert!(app.get_current_todos().is_empty());
+
+        handle_search_keys(&mut app, create_key_event(KeyCode::Enter));
+        assert!(matches!(app.state, AppState::Main));
+        assert_eq!(app.filter.query, "docs");
+    }
+
     #[test]
     fn test_confirm_keys() {
         let mut app = create_test_app();
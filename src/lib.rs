@@ -0,0 +1,25 @@
+//! Todo storage and the terminal UI behind the `todocli` binary.
+//!
+//! The data layer (`Todo`, `Database` and friends in [`data`]) has no
+//! terminal dependencies and can be used on its own:
+//!
+//! ```
+//! use todocli::{Database, Todo};
+//!
+//! let mut database = Database::in_memory();
+//! let todo = Todo::new("From the library".to_string(), "Via the public API".to_string());
+//! let id = database.add_todo(todo).unwrap();
+//!
+//! let stored = database.get_todo(&id).unwrap();
+//! assert_eq!(stored.subject, "From the library");
+//! assert_eq!(database.get_all_todos().len(), 1);
+//! ```
+
+pub mod app;
+pub mod cli;
+pub mod data;
+pub mod events;
+pub mod logging;
+pub mod ui;
+
+pub use data::{Database, Todo};
@@ -0,0 +1,65 @@
+//! Opt-in debug log. The TUI owns the terminal, so anything printed to
+//! stderr is lost; with `TODOCLI_LOG` set to a non-empty value, events are
+//! appended to `todocli.log` next to the database instead.
+
+use crate::data::paths;
+use chrono::{DateTime, SecondsFormat, Utc};
+use std::ffi::OsStr;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::sync::{Mutex, OnceLock};
+
+static LOG_FILE: OnceLock<Option<Mutex<File>>> = OnceLock::new();
+
+/// Whether a `TODOCLI_LOG` value turns logging on.
+pub fn enabled_by(value: Option<&OsStr>) -> bool {
+    value.is_some_and(|value| !value.is_empty())
+}
+
+/// One log line: RFC 3339 timestamp, event kind and message.
+pub fn format_event(now: DateTime<Utc>, kind: &str, message: &str) -> String {
+    format!("{} {:<5} {}\n", now.to_rfc3339_opts(SecondsFormat::Secs, true), kind, message)
+}
+
+/// Appends an event to the log when logging is enabled. Failing to log
+/// never interrupts the app.
+pub fn log(kind: &str, message: &str) {
+    let file = LOG_FILE.get_or_init(|| {
+        if !enabled_by(std::env::var_os("TODOCLI_LOG").as_deref()) {
+            return None;
+        }
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(paths::app_dir().join("todocli.log"))
+            .ok()
+            .map(Mutex::new)
+    });
+    if let Some(file) = file {
+        if let Ok(mut file) = file.lock() {
+            let _ = file.write_all(format_event(Utc::now(), kind, message).as_bytes());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_format_event() {
+        let now = Utc.with_ymd_and_hms(2024, 3, 1, 9, 30, 5).unwrap();
+        assert_eq!(
+            format_event(now, "key", "Char('q') in Main"),
+            "2024-03-01T09:30:05Z key   Char('q') in Main\n"
+        );
+    }
+
+    #[test]
+    fn test_enabled_by() {
+        assert!(!enabled_by(None));
+        assert!(!enabled_by(Some(OsStr::new(""))));
+        assert!(enabled_by(Some(OsStr::new("1"))));
+    }
+}
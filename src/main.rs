@@ -1,23 +1,30 @@
-mod app;
-mod data;
-mod events;
-mod ui;
-
-use app::{App, AppState};
-use data::Todo;
+use todocli::app::{App, AppState};
+use todocli::cli::{self, Command};
+use todocli::events::{self, AppEvent, EventHandler};
+use todocli::{logging, ui};
 use crossterm::{
     event::DisableMouseCapture,
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
-use events::{AppEvent, EventHandler};
 use ratatui::{
     backend::CrosstermBackend,
     Terminal,
 };
-use std::io;
+use std::io::{self, Write};
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let command = cli::parse_args(std::env::args().skip(1))?;
+    if command != Command::Tui {
+        cli::run(command)?;
+        return Ok(());
+    }
+
+    // Create app before touching the terminal so startup errors and notices
+    // are printed to a normal screen
+    let mut app = App::new()?;
+    let event_handler = EventHandler::new();
+
     // Setup terminal
     enable_raw_mode()?;
     let mut stdout = io::stdout();
@@ -25,12 +32,10 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
-    // Create app
-    let mut app = App::new()?;
-    let event_handler = EventHandler::new();
-
     // Main loop
     let result = run_app(&mut terminal, &mut app, &event_handler);
+    // Remembering the selection is best effort; it must not mask a run error
+    let _ = app.remember_selection();
 
     // Restore terminal
     disable_raw_mode()?;
@@ -42,9 +47,18 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     terminal.show_cursor()?;
 
     if let Err(err) = result {
+        logging::log("error", &err.to_string());
         eprintln!("Error: {}", err);
     }
 
+    // Changes kept in memory after a failed save get one more try
+    if app.database.has_unsaved_changes() {
+        if let Err(err) = app.database.save() {
+            logging::log("error", &format!("{:#}", err));
+            eprintln!("Warning: your latest changes could not be saved: {:#}", err);
+        }
+    }
+
     Ok(())
 }
 
@@ -56,29 +70,51 @@ fn run_app(
     loop {
         terminal.draw(|frame| {
             let area = frame.size();
-            
-            match app.state.clone() {
-                AppState::Main => {
-                    let todos = app.get_current_todos();
-                    let todo_refs: Vec<&Todo> = todos.iter().collect();
-                    app.main_view.render(frame, area, &todo_refs);
-                }
+            if ui::layout::is_too_small(area) {
+                ui::layout::render_too_small(frame, area);
+                return;
+            }
+
+            // The main list is always drawn; popups render on top of it
+            // Taken out for the duration of the render so the todos can be
+            // borrowed from `app` at the same time
+            let mut main_view = std::mem::take(&mut app.main_view);
+            main_view.focus_label = app.focus_label(std::time::Instant::now());
+            main_view.collapsed_completed = app.collapsed_completed_count();
+            main_view.accent = ui::theme::Theme::current().list_accent(app.filter.list.as_deref(), &app.prefs.list_colors);
+            let todos = app.get_current_todo_refs();
+            let daily_progress = app.daily_progress();
+            let footer = match (&app.state, &app.status_message) {
+                (AppState::Search, _) => ui::Footer::Searching(&app.search_input),
+                (_, Some((message, _))) if app.status_is_error => ui::Footer::Error(message),
+                (_, Some((message, _))) => ui::Footer::Status(message),
+                _ => ui::Footer::Controls,
+            };
+            main_view.render(frame, area, &todos, &app.filter, daily_progress, footer);
+            app.main_view = main_view;
+
+            match app.state {
+                AppState::Main | AppState::Search => {}
+                AppState::Help => ui::help::render_help(frame, area),
+                AppState::ThemePreview => ui::theme_preview::render_theme_preview(frame, area),
+                AppState::RecentlyCompleted => ui::recent::render_recently_completed(
+                    frame,
+                    area,
+                    &app.database.recently_completed(ui::recent::RECENT_LIMIT),
+                ),
                 AppState::Detail => {
-                    let todos = app.get_current_todos();
-                    let todo_refs: Vec<&Todo> = todos.iter().collect();
-                    app.main_view.render(frame, area, &todo_refs);
-                    
                     if let Some(detail_view) = &app.detail_view {
-                        detail_view.render(frame, area);
+                        detail_view.render(frame, area, app.prefs.detail_popup, app.prefs.keep_indentation);
+                    }
+                }
+                AppState::Input => {
+                    if let Some(input_dialog) = &app.input_dialog {
+                        input_dialog.render(frame, area, app.prefs.confirm_popup);
                     }
                 }
                 AppState::Confirm => {
-                    let todos = app.get_current_todos();
-                    let todo_refs: Vec<&Todo> = todos.iter().collect();
-                    app.main_view.render(frame, area, &todo_refs);
-                    
                     if let Some(confirm_dialog) = &app.confirm_dialog {
-                        confirm_dialog.render(frame, area);
+                        confirm_dialog.render(frame, area, app.prefs.confirm_popup, &app.prefs.confirm_text);
                     }
                 }
             }
@@ -89,7 +125,14 @@ fn run_app(
                 events::handle_key_event(app, key)?;
             }
             AppEvent::Tick => {
-                // Handle periodic updates if needed
+                let now = std::time::Instant::now();
+                app.expire_status_message(now);
+                app.apply_search_if_due(now);
+                if app.update_focus(now) {
+                    // Terminal bell
+                    print!("\x07");
+                    io::stdout().flush()?;
+                }
             }
         }
 
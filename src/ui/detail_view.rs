@@ -1,10 +1,20 @@
-use crate::data::Todo;
+use crate::data::due::parse_due;
+use crate::data::tags::{normalize_tag, parse_tags, suggest_tags};
+use crate::data::{attachment_label, humanize_time, subtask_progress, Locale, PopupSize, Priority, Subtask, Todo, DEFAULT_LIST, DEFAULT_TAB_WIDTH};
+use crate::ui::main_view::progress_bar_string;
 use crate::ui::theme::TokyoNightTheme;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::rc::Rc;
 use ratatui::{
     layout::{Constraint, Direction, Layout, Rect},
     text::{Line, Span},
-    widgets::{Block, Borders, Clear, Paragraph, Wrap},
+    widgets::{
+        block::{Position, Title},
+        Block, Borders, Clear, Paragraph, Wrap,
+    },
     Frame,
 };
 
@@ -19,71 +29,261 @@ pub struct DetailView {
     pub mode: DetailMode,
     pub subject: String,
     pub description: String,
+    pub notes: String,
+    /// Whether the notes section is expanded in view mode. It is always
+    /// expanded while the notes field has focus.
+    pub notes_expanded: bool,
+    /// Indexes of the description headings whose sections are collapsed in
+    /// view mode. All sections start expanded.
+    pub collapsed_sections: HashSet<usize>,
+    /// Heading that Enter collapses or expands in view mode.
+    pub selected_heading: usize,
     pub created_at: Option<DateTime<Utc>>,
     pub closed_at: Option<DateTime<Utc>>,
     pub last_modified_at: Option<DateTime<Utc>>,
-    pub current_field: usize, // 0 = subject, 1 = description
+    pub due_date: String,
+    pub list: String,
+    /// Comma-separated tags as typed.
+    pub tags: String,
+    /// Tags in use across all todos with their counts, for suggestions.
+    pub known_tags: Vec<(String, usize)>,
+    /// Language of the status label and relative times.
+    pub locale: Locale,
+    /// Columns between tab stops when showing the description and notes.
+    pub tab_width: usize,
+    pub closed_date: String,
+    pub priority: Priority,
+    pub attachments: Vec<PathBuf>,
+    pub subtasks: Vec<Subtask>,
+    /// Subtask that Shift+↑/↓ moves in view mode.
+    pub selected_subtask: usize,
+    /// Set after a save is refused so the reason is shown until it's fixed.
+    pub show_errors: bool,
+    /// The form as opened or last saved, to detect unsaved edits.
+    saved_form: FormValues,
+    /// Description lines wrapped for the last render, reused until the
+    /// text, width or folding changes.
+    wrap_cache: RefCell<Option<WrapCache>>,
+    pub current_field: usize, // 0 = subject, 1 = description, 2 = notes, 3 = due date, 4 = list, 5 = tags, 6 = closed (completed only)
+}
+
+/// The editable parts of the form.
+#[derive(Clone, Default, PartialEq)]
+struct FormValues {
+    subject: String,
+    description: String,
+    notes: String,
+    due_date: String,
+    list: String,
+    tags: String,
+    closed_date: String,
+    priority: Priority,
+}
+
+/// Wrapped description lines and what they were built from.
+struct WrapCache {
+    description: String,
+    width: u16,
+    keep_indentation: bool,
+    tab_width: usize,
+    view: bool,
+    collapsed_sections: HashSet<usize>,
+    selected_heading: usize,
+    lines: Rc<[Line<'static>]>,
+}
+
+impl WrapCache {
+    fn is_for(&self, view: &DetailView, width: u16, keep_indentation: bool) -> bool {
+        self.width == width
+            && self.keep_indentation == keep_indentation
+            && self.tab_width == view.tab_width
+            && self.view == matches!(view.mode, DetailMode::View)
+            && self.selected_heading == view.selected_heading
+            && self.collapsed_sections == view.collapsed_sections
+            && self.description == view.description
+    }
+}
+
+const FIELD_COUNT: usize = 6;
+const SUBTASK_BAR_WIDTH: usize = 10;
+/// Most subtasks listed at once in view mode; the list scrolls with the
+/// selection so a long one can't push the description out of the popup.
+const MAX_SUBTASK_LINES: usize = 5;
+const DESCRIPTION_FIELD: usize = 1;
+const NOTES_FIELD: usize = 2;
+const DUE_FIELD: usize = 3;
+const LIST_FIELD: usize = 4;
+const TAGS_FIELD: usize = 5;
+const CLOSED_FIELD: usize = 6;
+const CLOSED_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+#[derive(Debug, PartialEq)]
+pub enum ClosedAtError {
+    Invalid,
+    BeforeCreated,
+}
+
+/// Parses an edited completion time (`YYYY-MM-DD HH:MM:SS`, UTC) and checks
+/// it isn't earlier than the todo's creation. The comparison is done at
+/// whole-second precision, matching what the field can express.
+pub fn parse_closed_at(input: &str, created_at: DateTime<Utc>) -> Result<DateTime<Utc>, ClosedAtError> {
+    let closed_at = NaiveDateTime::parse_from_str(input.trim(), CLOSED_FORMAT)
+        .map_err(|_| ClosedAtError::Invalid)?
+        .and_utc();
+    if closed_at.timestamp() < created_at.timestamp() {
+        return Err(ClosedAtError::BeforeCreated);
+    }
+    Ok(closed_at)
+}
+
+/// Parses a due date as typed (see `parse_due` for the accepted forms);
+/// an empty input means no due date.
+pub fn parse_due_date(input: &str) -> anyhow::Result<Option<DateTime<Utc>>> {
+    if input.trim().is_empty() {
+        return Ok(None);
+    }
+    parse_due(input, Utc::now()).map(Some)
+}
+
+/// Wrapping for multi-line text. Trimming tidies prose but flattens the
+/// indentation of pasted code and nested lists.
+pub fn text_wrap(keep_indentation: bool) -> Wrap {
+    Wrap { trim: !keep_indentation }
+}
+
+fn format_due_date(due_date: Option<DateTime<Utc>>) -> String {
+    due_date
+        .map(|due| due.format("%Y-%m-%d").to_string())
+        .unwrap_or_default()
 }
 
 impl DetailView {
     pub fn new_for_viewing(todo: &Todo) -> Self {
-        Self {
+        let mut view = Self {
             mode: DetailMode::View,
             subject: todo.subject.clone(),
             description: todo.description.clone(),
+            notes: todo.notes.clone(),
+            notes_expanded: false,
+            collapsed_sections: HashSet::new(),
+            selected_heading: 0,
             created_at: Some(todo.created_at),
             closed_at: todo.closed_at,
             last_modified_at: Some(todo.last_modified_at),
+            due_date: format_due_date(todo.due_date),
+            list: todo.list.clone(),
+            tags: todo.tags.join(", "),
+            known_tags: Vec::new(),
+            locale: Locale::default(),
+            tab_width: DEFAULT_TAB_WIDTH,
+            priority: todo.priority,
+            closed_date: todo
+                .closed_at
+                .map(|closed| closed.format(CLOSED_FORMAT).to_string())
+                .unwrap_or_default(),
+            attachments: todo.attachments.clone(),
+            subtasks: todo.subtasks.clone(),
+            selected_subtask: 0,
+            show_errors: false,
+            saved_form: FormValues::default(),
+            wrap_cache: RefCell::new(None),
             current_field: 0,
-        }
+        };
+        view.mark_saved();
+        view
     }
 
     pub fn new_for_editing(todo: &Todo) -> Self {
-        Self {
+        let mut view = Self {
             mode: DetailMode::Edit,
             subject: todo.subject.clone(),
             description: todo.description.clone(),
+            notes: todo.notes.clone(),
+            notes_expanded: false,
+            collapsed_sections: HashSet::new(),
+            selected_heading: 0,
             created_at: Some(todo.created_at),
             closed_at: todo.closed_at,
             last_modified_at: Some(todo.last_modified_at),
+            due_date: format_due_date(todo.due_date),
+            list: todo.list.clone(),
+            tags: todo.tags.join(", "),
+            known_tags: Vec::new(),
+            locale: Locale::default(),
+            tab_width: DEFAULT_TAB_WIDTH,
+            priority: todo.priority,
+            closed_date: todo
+                .closed_at
+                .map(|closed| closed.format(CLOSED_FORMAT).to_string())
+                .unwrap_or_default(),
+            attachments: todo.attachments.clone(),
+            subtasks: todo.subtasks.clone(),
+            selected_subtask: 0,
+            show_errors: false,
+            saved_form: FormValues::default(),
+            wrap_cache: RefCell::new(None),
             current_field: 0,
-        }
+        };
+        view.mark_saved();
+        view
     }
 
     pub fn new_for_creation() -> Self {
-        Self {
+        let mut view = Self {
             mode: DetailMode::New,
             subject: String::new(),
             description: String::new(),
+            notes: String::new(),
+            notes_expanded: false,
+            collapsed_sections: HashSet::new(),
+            selected_heading: 0,
             created_at: None,
             closed_at: None,
             last_modified_at: None,
+            due_date: String::new(),
+            list: DEFAULT_LIST.to_string(),
+            tags: String::new(),
+            known_tags: Vec::new(),
+            locale: Locale::default(),
+            tab_width: DEFAULT_TAB_WIDTH,
+            priority: Priority::default(),
+            closed_date: String::new(),
+            attachments: Vec::new(),
+            subtasks: Vec::new(),
+            selected_subtask: 0,
+            show_errors: false,
+            saved_form: FormValues::default(),
+            wrap_cache: RefCell::new(None),
             current_field: 0,
-        }
+        };
+        view.mark_saved();
+        view
     }
 
-    pub fn render(&self, frame: &mut Frame, area: Rect) {
+    /// Draws the popup. With `keep_indentation`, wrapped description and
+    /// notes keep their leading spaces (see `text_wrap`).
+    pub fn render(&self, frame: &mut Frame, area: Rect, size: PopupSize, keep_indentation: bool) {
         // Create a centered popup
-        let popup_area = centered_rect(80, 70, area);
+        let size = size.clamped();
+        let popup_area = centered_rect(size.width_percent, size.height_percent, area);
         
         // Clear the background
         frame.render_widget(Clear, popup_area);
 
+        let metadata_lines = self.metadata_lines();
         let chunks = Layout::default()
             .direction(Direction::Vertical)
             .constraints([
-                Constraint::Length(3),  // Subject
+                Constraint::Length(subject_height(&self.subject, popup_area.width)),  // Subject
                 Constraint::Min(8),     // Description
-                Constraint::Length(6),  // Metadata
+                Constraint::Length(self.notes_height()),  // Notes
+                Constraint::Length(3),  // Due date and list
+                Constraint::Length(block_height(metadata_lines.len())),  // Metadata
                 Constraint::Length(3),  // Controls
             ])
             .split(popup_area);
 
-        let title = match self.mode {
-            DetailMode::View => "Todo Details",
-            DetailMode::Edit => "Edit Todo",
-            DetailMode::New => "New Todo",
-        };
+        let title = self.title();
 
         // Subject field
         let subject_style = if self.current_field == 0 && !matches!(self.mode, DetailMode::View) {
@@ -94,6 +294,7 @@ impl DetailView {
 
         let subject = Paragraph::new(self.subject.as_str())
             .style(subject_style)
+            .wrap(Wrap { trim: true })
             .block(
                 Block::default()
                     .borders(Borders::ALL)
@@ -110,9 +311,20 @@ impl DetailView {
             TokyoNightTheme::default()
         };
 
-        let description = Paragraph::new(self.description.as_str())
+        // Wrapping a long description is slow, so it is wrapped once and
+        // only the rows that fit are drawn
+        let visible_rows = usize::from(chunks[1].height.saturating_sub(2));
+        let wrapped = self.wrapped_description(chunks[1].width.saturating_sub(2), keep_indentation);
+        let description_text: Vec<Line> = wrapped
+            .iter()
+            .take(visible_rows)
+            .map(|line| {
+                let spans: Vec<Span> = line.spans.iter().map(|span| Span::styled(span.content.as_ref(), span.style)).collect();
+                Line::from(spans)
+            })
+            .collect();
+        let description = Paragraph::new(description_text)
             .style(description_style)
-            .wrap(Wrap { trim: true })
             .block(
                 Block::default()
                     .borders(Borders::ALL)
@@ -122,41 +334,115 @@ impl DetailView {
             );
         frame.render_widget(description, chunks[1]);
 
-        // Metadata
-        let mut metadata_lines = vec![];
-        
-        if let Some(created) = self.created_at {
-            metadata_lines.push(Line::from(vec![
-                Span::styled("Created: ", TokyoNightTheme::accent()),
-                Span::styled(created.format("%Y-%m-%d %H:%M:%S").to_string(), TokyoNightTheme::default()),
-            ]));
-        }
+        // Notes, collapsed to their first line unless expanded or focused
+        let editing_notes = self.current_field == NOTES_FIELD && !matches!(self.mode, DetailMode::View);
+        let notes_style = if editing_notes {
+            TokyoNightTheme::selected()
+        } else {
+            TokyoNightTheme::default()
+        };
+        let notes = expand_tabs(&self.notes, self.tab_width);
+        let notes_text = if self.notes_open() {
+            notes.as_str()
+        } else {
+            notes.lines().next().unwrap_or_default()
+        };
+        let notes_title = if self.notes_open() {
+            "Notes ▾".to_string()
+        } else {
+            format!("Notes ▸ ({} lines)", self.notes.lines().count())
+        };
 
-        if let Some(modified) = self.last_modified_at {
-            metadata_lines.push(Line::from(vec![
-                Span::styled("Modified: ", TokyoNightTheme::accent()),
-                Span::styled(modified.format("%Y-%m-%d %H:%M:%S").to_string(), TokyoNightTheme::default()),
-            ]));
-        }
+        let notes = Paragraph::new(notes_text)
+            .style(notes_style)
+            .wrap(text_wrap(keep_indentation))
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_style(TokyoNightTheme::border())
+                    .title(notes_title)
+                    .title_style(TokyoNightTheme::accent()),
+            );
+        frame.render_widget(notes, chunks[2]);
 
-        let status = if self.closed_at.is_some() {
-            ("Completed", TokyoNightTheme::completed())
+        // Due date field
+        let due_style = if self.current_field == DUE_FIELD && !matches!(self.mode, DetailMode::View) {
+            TokyoNightTheme::selected()
         } else {
-            ("Active", TokyoNightTheme::success())
+            TokyoNightTheme::default()
         };
 
-        metadata_lines.push(Line::from(vec![
-            Span::styled("Status: ", TokyoNightTheme::accent()),
-            Span::styled(status.0, status.1),
-        ]));
+        let due_title_style = if parse_due_date(&self.due_date).is_ok() {
+            TokyoNightTheme::accent()
+        } else {
+            TokyoNightTheme::error()
+        };
 
-        if let Some(closed) = self.closed_at {
-            metadata_lines.push(Line::from(vec![
-                Span::styled("Closed: ", TokyoNightTheme::accent()),
-                Span::styled(closed.format("%Y-%m-%d %H:%M:%S").to_string(), TokyoNightTheme::completed()),
-            ]));
+        let due = Paragraph::new(self.due_date.as_str())
+            .style(due_style)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_style(TokyoNightTheme::border())
+                    .title("Due (date, +3d, fri)")
+                    .title_style(due_title_style),
+            );
+
+        // Due date, list and tags share a row
+        let field_row = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([
+                Constraint::Percentage(30),
+                Constraint::Percentage(30),
+                Constraint::Percentage(40),
+            ])
+            .split(chunks[3]);
+        frame.render_widget(due, field_row[0]);
+
+        // List field
+        let list_style = if self.current_field == LIST_FIELD && !matches!(self.mode, DetailMode::View) {
+            TokyoNightTheme::selected()
+        } else {
+            TokyoNightTheme::default()
+        };
+
+        let list = Paragraph::new(self.list.as_str())
+            .style(list_style)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_style(TokyoNightTheme::border())
+                    .title("List")
+                    .title_style(TokyoNightTheme::accent()),
+            );
+        frame.render_widget(list, field_row[1]);
+
+        // Tags field, with suggestions for the tag being typed on its bottom border
+        let editing_tags = self.current_field == TAGS_FIELD && !matches!(self.mode, DetailMode::View);
+        let tags_style = if editing_tags {
+            TokyoNightTheme::selected()
+        } else {
+            TokyoNightTheme::default()
+        };
+
+        let mut tags_block = Block::default()
+            .borders(Borders::ALL)
+            .border_style(TokyoNightTheme::border())
+            .title("Tags")
+            .title_style(TokyoNightTheme::accent());
+        let suggestions = self.tag_suggestions();
+        if editing_tags && !suggestions.is_empty() {
+            tags_block = tags_block.title(
+                Title::from(Span::styled(format!(" Tab: {} ", suggestions.join(" ")), TokyoNightTheme::warning()))
+                    .position(Position::Bottom),
+            );
         }
 
+        let tags = Paragraph::new(self.tags.as_str())
+            .style(tags_style)
+            .block(tags_block);
+        frame.render_widget(tags, field_row[2]);
+
         let metadata = Paragraph::new(metadata_lines)
             .block(
                 Block::default()
@@ -165,30 +451,66 @@ impl DetailView {
                     .title("Information")
                     .title_style(TokyoNightTheme::accent()),
             );
-        frame.render_widget(metadata, chunks[2]);
+        frame.render_widget(metadata, chunks[4]);
 
         // Controls
         let controls_text = match self.mode {
-            DetailMode::View => vec![
-                Line::from(vec![
+            DetailMode::View => {
+                let mut spans = vec![
                     Span::styled("Controls: ", TokyoNightTheme::accent()),
                     Span::styled("e", TokyoNightTheme::active()),
                     Span::styled("=Edit  ", TokyoNightTheme::default()),
-                    Span::styled("Esc", TokyoNightTheme::warning()),
-                    Span::styled("=Back", TokyoNightTheme::default()),
-                ]),
-            ],
-            DetailMode::Edit | DetailMode::New => vec![
-                Line::from(vec![
+                ];
+                if !self.attachments.is_empty() {
+                    spans.push(Span::styled("o", TokyoNightTheme::active()));
+                    spans.push(Span::styled("=Open Attachment  ", TokyoNightTheme::default()));
+                }
+                if self.has_future_created(Utc::now()) {
+                    spans.push(Span::styled("r", TokyoNightTheme::active()));
+                    spans.push(Span::styled("=Reset Created to Now  ", TokyoNightTheme::default()));
+                }
+                if !self.subtasks.is_empty() {
+                    spans.push(Span::styled("Space", TokyoNightTheme::active()));
+                    spans.push(Span::styled("=Tick  ", TokyoNightTheme::default()));
+                }
+                if self.subtasks.len() > 1 {
+                    spans.push(Span::styled("Tab", TokyoNightTheme::active()));
+                    spans.push(Span::styled("=Subtask  ", TokyoNightTheme::default()));
+                    spans.push(Span::styled("Shift+↑/↓", TokyoNightTheme::active()));
+                    spans.push(Span::styled("=Move  ", TokyoNightTheme::default()));
+                }
+                if self.heading_count() > 0 {
+                    spans.push(Span::styled("j/k", TokyoNightTheme::active()));
+                    spans.push(Span::styled("=Heading  ", TokyoNightTheme::default()));
+                    spans.push(Span::styled("Enter", TokyoNightTheme::active()));
+                    spans.push(Span::styled("=Fold  ", TokyoNightTheme::default()));
+                }
+                if !self.notes.is_empty() {
+                    spans.push(Span::styled("N", TokyoNightTheme::active()));
+                    spans.push(Span::styled("=Notes  ", TokyoNightTheme::default()));
+                }
+                spans.push(Span::styled("Esc", TokyoNightTheme::warning()));
+                spans.push(Span::styled("=Back", TokyoNightTheme::default()));
+                vec![Line::from(spans)]
+            }
+            DetailMode::Edit | DetailMode::New => {
+                let mut spans = Vec::new();
+                if let Some(error) = self.validation_error().filter(|_| self.show_errors) {
+                    spans.push(Span::styled(format!("⚠ {}  ", error), TokyoNightTheme::error()));
+                }
+                spans.extend([
                     Span::styled("Controls: ", TokyoNightTheme::accent()),
                     Span::styled("Tab", TokyoNightTheme::active()),
                     Span::styled("=Switch Field  ", TokyoNightTheme::default()),
                     Span::styled("Ctrl+S", TokyoNightTheme::success()),
                     Span::styled("=Save  ", TokyoNightTheme::default()),
+                    Span::styled("Ctrl+D", TokyoNightTheme::success()),
+                    Span::styled("=Save & Close  ", TokyoNightTheme::default()),
                     Span::styled("Esc", TokyoNightTheme::warning()),
-                    Span::styled("=Cancel", TokyoNightTheme::default()),
-                ]),
-            ],
+                    Span::styled("=Discard", TokyoNightTheme::default()),
+                ]);
+                vec![Line::from(spans)]
+            }
         };
 
         let controls = Paragraph::new(controls_text)
@@ -199,21 +521,343 @@ impl DetailView {
                     .title(title)
                     .title_style(TokyoNightTheme::accent()),
             );
-        frame.render_widget(controls, chunks[3]);
+        frame.render_widget(controls, chunks[5]);
+    }
+
+    /// Information lines for the populated metadata only: a new todo has
+    /// just its status, a completed one also shows timestamps and more.
+    fn metadata_lines(&self) -> Vec<Line<'_>> {
+        let mut metadata_lines = vec![];
+        let now = Utc::now();
+        let strings = self.locale.strings();
+
+        if let Some(created) = self.created_at {
+            let mut created_spans = vec![
+                Span::styled("Created: ", TokyoNightTheme::accent()),
+                Span::styled(created.format("%Y-%m-%d %H:%M:%S").to_string(), TokyoNightTheme::default()),
+                Span::styled(format!("  ({})", humanize_time(created, now, self.locale)), TokyoNightTheme::default()),
+            ];
+            if self.has_future_created(now) {
+                created_spans.push(Span::styled("  ⚠ in the future", TokyoNightTheme::warning()));
+            }
+            metadata_lines.push(Line::from(created_spans));
+        }
+
+        if let Some(modified) = self.last_modified_at {
+            metadata_lines.push(Line::from(vec![
+                Span::styled("Modified: ", TokyoNightTheme::accent()),
+                Span::styled(modified.format("%Y-%m-%d %H:%M:%S").to_string(), TokyoNightTheme::default()),
+            ]));
+        }
+
+        let due = self.due_date_value();
+        let is_overdue = matches!(due, Some(due) if due < now);
+        let status = if self.closed_at.is_some() {
+            (strings.completed, TokyoNightTheme::completed())
+        } else if is_overdue {
+            (strings.overdue, TokyoNightTheme::error())
+        } else {
+            (strings.active, TokyoNightTheme::success())
+        };
+
+        let mut status_spans = vec![
+            Span::styled("Status: ", TokyoNightTheme::accent()),
+            Span::styled(status.0, status.1),
+            Span::styled(format!("  ({} priority)", self.priority.label()), TokyoNightTheme::default()),
+        ];
+        if let Some(due) = due.filter(|_| self.closed_at.is_none()) {
+            status_spans.push(Span::styled(
+                format!("  {} {}", strings.due, humanize_time(due, now, self.locale)),
+                status.1,
+            ));
+        }
+        metadata_lines.push(Line::from(status_spans));
+
+        if self.closed_at.is_some() {
+            let closed_style = if self.current_field == CLOSED_FIELD && !matches!(self.mode, DetailMode::View) {
+                TokyoNightTheme::selected()
+            } else if self.closed_at_value().is_err() {
+                TokyoNightTheme::error()
+            } else {
+                TokyoNightTheme::completed()
+            };
+            metadata_lines.push(Line::from(vec![
+                Span::styled("Closed: ", TokyoNightTheme::accent()),
+                Span::styled(self.closed_date.as_str(), closed_style),
+            ]));
+        }
+
+        if let Some((done, total)) = subtask_progress(&self.subtasks) {
+            let view = matches!(self.mode, DetailMode::View);
+            let shown = subtask_window(total, self.selected_subtask);
+            let mut spans = vec![
+                Span::styled("Subtasks: ", TokyoNightTheme::accent()),
+                Span::styled(format!("{}/{} done ", done, total), TokyoNightTheme::default()),
+                Span::styled(progress_bar_string(done, total, SUBTASK_BAR_WIDTH), TokyoNightTheme::success()),
+            ];
+            if view && shown.len() < total {
+                let label = format!(" {}-{} of {}", shown.start + 1, shown.end, total);
+                spans.push(Span::styled(label, TokyoNightTheme::completed()));
+            }
+            metadata_lines.push(Line::from(spans));
+            // Listed in view mode, where they can be reordered
+            if view {
+                for (index, subtask) in self.subtasks.iter().enumerate().skip(shown.start).take(shown.len()) {
+                    let style = if index == self.selected_subtask {
+                        TokyoNightTheme::selected()
+                    } else {
+                        TokyoNightTheme::default()
+                    };
+                    let check = if subtask.done { "☑" } else { "☐" };
+                    metadata_lines.push(Line::from(Span::styled(format!("  {} {}", check, subtask.subject), style)));
+                }
+            }
+        }
+
+        if !self.attachments.is_empty() {
+            let labels: Vec<String> = self.attachments.iter().map(|path| attachment_label(path)).collect();
+            metadata_lines.push(Line::from(vec![
+                Span::styled("Attachments: ", TokyoNightTheme::accent()),
+                Span::styled(labels.join(", "), TokyoNightTheme::default()),
+            ]));
+        }
+
+        metadata_lines
+    }
+
+    /// Whether the creation time is later than `now` (see
+    /// `Todo::has_future_created`).
+    pub fn has_future_created(&self, now: DateTime<Utc>) -> bool {
+        self.created_at.is_some_and(|created| created > now)
+    }
+
+    /// Whether the notes section shows all of its text.
+    fn notes_open(&self) -> bool {
+        self.notes_expanded || (self.current_field == NOTES_FIELD && !matches!(self.mode, DetailMode::View))
+    }
+
+    /// Height of the notes block: one line when collapsed, room for a few
+    /// more when open.
+    fn notes_height(&self) -> u16 {
+        if self.notes_open() {
+            8
+        } else {
+            3
+        }
+    }
+
+    pub fn cycle_priority(&mut self) {
+        self.priority = self.priority.next();
+    }
+
+    pub fn toggle_notes(&mut self) {
+        self.notes_expanded = !self.notes_expanded;
+    }
+
+    /// Takes over the subtasks of the viewed todo after they were ticked or
+    /// reordered, along with the closed and modified times that may have
+    /// changed with them.
+    pub fn sync_subtasks(&mut self, todo: &Todo) {
+        self.subtasks = todo.subtasks.clone();
+        self.closed_at = todo.closed_at;
+        self.closed_date = todo
+            .closed_at
+            .map(|closed| closed.format(CLOSED_FORMAT).to_string())
+            .unwrap_or_default();
+        self.last_modified_at = Some(todo.last_modified_at);
+        self.mark_saved();
+    }
+
+    /// Moves the subtask selection down (`forward`) or up, wrapping around.
+    pub fn cycle_subtask(&mut self, forward: bool) {
+        let len = self.subtasks.len();
+        if len == 0 {
+            return;
+        }
+        self.selected_subtask = if forward {
+            (self.selected_subtask + 1) % len
+        } else {
+            (self.selected_subtask + len - 1) % len
+        };
+    }
+
+    fn heading_count(&self) -> usize {
+        description_sections(&self.description)
+            .iter()
+            .filter(|section| section.heading.is_some())
+            .count()
+    }
+
+    pub fn next_heading(&mut self) {
+        self.selected_heading = (self.selected_heading + 1).min(self.heading_count().saturating_sub(1));
+    }
+
+    pub fn previous_heading(&mut self) {
+        self.selected_heading = self.selected_heading.saturating_sub(1);
+    }
+
+    /// Collapses or expands the section under the selected heading.
+    pub fn toggle_section(&mut self) {
+        if self.selected_heading >= self.heading_count() {
+            return;
+        }
+        if !self.collapsed_sections.remove(&self.selected_heading) {
+            self.collapsed_sections.insert(self.selected_heading);
+        }
+    }
+
+    pub fn is_section_collapsed(&self, heading: usize) -> bool {
+        self.collapsed_sections.contains(&heading)
+    }
+
+    /// The description wrapped to `width` columns: with foldable headings
+    /// in view mode, reflowed unless `keep_indentation` is set, and as typed
+    /// while editing. Cached between calls with the same inputs.
+    pub fn wrapped_description(&self, width: u16, keep_indentation: bool) -> Rc<[Line<'static>]> {
+        let mut cache = self.wrap_cache.borrow_mut();
+        if let Some(cache) = cache.as_ref().filter(|cache| cache.is_for(self, width, keep_indentation)) {
+            return Rc::clone(&cache.lines);
+        }
+
+        let view = matches!(self.mode, DetailMode::View);
+        let description = expand_tabs(&self.description, self.tab_width);
+        let source: Vec<Line> = if view {
+            self.description_lines(&description, keep_indentation)
+        } else {
+            description.lines().map(Line::from).collect()
+        };
+        let lines: Rc<[Line<'static>]> = source
+            .iter()
+            .flat_map(|line| wrap_line(line, width, !keep_indentation))
+            .collect();
+        *cache = Some(WrapCache {
+            description: self.description.clone(),
+            width,
+            keep_indentation,
+            tab_width: self.tab_width,
+            view,
+            collapsed_sections: self.collapsed_sections.clone(),
+            selected_heading: self.selected_heading,
+            lines: Rc::clone(&lines),
+        });
+        lines
+    }
+
+    /// The description for view mode: paragraphs (see `paragraph_lines`),
+    /// with each heading marked ▾ or ▸ and a collapsed section reduced to
+    /// its line count.
+    fn description_lines<'a>(&self, description: &'a str, keep_indentation: bool) -> Vec<Line<'a>> {
+        let mut lines = Vec::new();
+        let mut heading_index = 0;
+        for section in description_sections(description) {
+            let Some(heading) = section.heading else {
+                lines.extend(paragraph_lines(section.body, keep_indentation));
+                continue;
+            };
+            if !lines.is_empty() {
+                lines.push(Line::from(""));
+            }
+            let style = if heading_index == self.selected_heading {
+                TokyoNightTheme::selected()
+            } else {
+                TokyoNightTheme::accent()
+            };
+            if self.is_section_collapsed(heading_index) {
+                let label = format!("▸ {} ({} lines)", heading, section.body.lines().count());
+                lines.push(Line::from(Span::styled(label, style)));
+            } else {
+                lines.push(Line::from(Span::styled(format!("▾ {}", heading), style)));
+                lines.extend(paragraph_lines(section.body, keep_indentation));
+            }
+            heading_index += 1;
+        }
+        lines
+    }
+
+    fn form_values(&self) -> FormValues {
+        FormValues {
+            subject: self.subject.clone(),
+            description: self.description.clone(),
+            notes: self.notes.clone(),
+            due_date: self.due_date.clone(),
+            list: self.list.clone(),
+            tags: self.tags.clone(),
+            closed_date: self.closed_date.clone(),
+            priority: self.priority,
+        }
+    }
+
+    /// Takes the current form as the saved state.
+    pub fn mark_saved(&mut self) {
+        self.saved_form = self.form_values();
+    }
+
+    /// Whether the form differs from when it was opened or last saved.
+    pub fn has_unsaved_changes(&self) -> bool {
+        self.form_values() != self.saved_form
+    }
+
+    /// Popup title, with a trailing `*` while there are unsaved edits.
+    pub fn title(&self) -> String {
+        let title = match self.mode {
+            DetailMode::View => "Todo Details",
+            DetailMode::Edit => "Edit Todo",
+            DetailMode::New => "New Todo",
+        };
+        if self.has_unsaved_changes() {
+            format!("{} *", title)
+        } else {
+            title.to_string()
+        }
+    }
+
+    /// The single-line subject field, where Enter saves.
+    pub fn on_subject_field(&self) -> bool {
+        self.current_field == 0
+    }
+
+    /// Fields where Enter inserts a line break.
+    pub fn accepts_newlines(&self) -> bool {
+        matches!(self.current_field, DESCRIPTION_FIELD | NOTES_FIELD)
+    }
+
+    /// Completed todos get an extra field for correcting their closing time.
+    fn field_count(&self) -> usize {
+        if self.closed_at.is_some() {
+            FIELD_COUNT + 1
+        } else {
+            FIELD_COUNT
+        }
     }
 
     pub fn next_field(&mut self) {
-        self.current_field = (self.current_field + 1) % 2;
+        self.current_field = (self.current_field + 1) % self.field_count();
     }
 
     pub fn previous_field(&mut self) {
-        self.current_field = if self.current_field == 0 { 1 } else { 0 };
+        let count = self.field_count();
+        self.current_field = (self.current_field + count - 1) % count;
+    }
+
+    /// Jumps straight to the notes, or back to the description when the
+    /// notes already have focus.
+    pub fn toggle_notes_field(&mut self) {
+        self.current_field = if self.current_field == NOTES_FIELD {
+            DESCRIPTION_FIELD
+        } else {
+            NOTES_FIELD
+        };
     }
 
     pub fn add_char(&mut self, c: char) {
         match self.current_field {
             0 => self.subject.push(c),
-            1 => self.description.push(c),
+            DESCRIPTION_FIELD => self.description.push(c),
+            NOTES_FIELD => self.notes.push(c),
+            DUE_FIELD => self.due_date.push(c),
+            LIST_FIELD => self.list.push(c),
+            TAGS_FIELD => self.tags.push(c),
+            CLOSED_FIELD => self.closed_date.push(c),
             _ => {}
         }
     }
@@ -221,24 +865,349 @@ impl DetailView {
     pub fn delete_char(&mut self) {
         match self.current_field {
             0 => { self.subject.pop(); },
-            1 => { self.description.pop(); },
+            DESCRIPTION_FIELD => { self.description.pop(); },
+            NOTES_FIELD => { self.notes.pop(); },
+            DUE_FIELD => { self.due_date.pop(); },
+            LIST_FIELD => { self.list.pop(); },
+            TAGS_FIELD => { self.tags.pop(); },
+            CLOSED_FIELD => { self.closed_date.pop(); },
             _ => {}
         }
     }
 
+    pub fn tags_value(&self) -> Vec<String> {
+        parse_tags(&self.tags)
+    }
+
+    /// Known tags completing the one currently being typed, leaving out
+    /// tags already entered.
+    pub fn tag_suggestions(&self) -> Vec<String> {
+        let (entered, prefix) = match self.tags.rsplit_once(',') {
+            Some((entered, prefix)) => (parse_tags(entered), prefix),
+            None => (Vec::new(), self.tags.as_str()),
+        };
+        let typed = normalize_tag(prefix);
+        suggest_tags(prefix, &self.known_tags)
+            .into_iter()
+            .filter(|tag| !entered.contains(tag) && typed.as_ref() != Some(tag))
+            .collect()
+    }
+
+    /// Completes the tag being typed with the top suggestion. Returns
+    /// whether there was one, so Tab can fall back to switching fields.
+    pub fn accept_tag_suggestion(&mut self) -> bool {
+        if self.current_field != TAGS_FIELD {
+            return false;
+        }
+        let Some(suggestion) = self.tag_suggestions().into_iter().next() else {
+            return false;
+        };
+        let entered = match self.tags.rsplit_once(',') {
+            Some((entered, _)) => format!("{}, ", entered.trim_end()),
+            None => String::new(),
+        };
+        self.tags = format!("{}{}", entered, suggestion);
+        true
+    }
+
+    pub fn due_date_value(&self) -> Option<DateTime<Utc>> {
+        parse_due_date(&self.due_date).ok().flatten()
+    }
+
+    /// Subject to save, without surrounding whitespace.
+    pub fn subject_value(&self) -> String {
+        self.subject.trim().to_string()
+    }
+
+    /// List name to save, falling back to the default list when left blank.
+    pub fn list_value(&self) -> String {
+        let list = self.list.trim();
+        if list.is_empty() {
+            DEFAULT_LIST.to_string()
+        } else {
+            list.to_string()
+        }
+    }
+
+    /// Completion time to save. An untouched field keeps the stored value
+    /// exactly rather than round-tripping it through the text format.
+    pub fn closed_at_value(&self) -> Result<Option<DateTime<Utc>>, ClosedAtError> {
+        let (Some(closed_at), Some(created_at)) = (self.closed_at, self.created_at) else {
+            return Ok(self.closed_at);
+        };
+        if self.closed_date == closed_at.format(CLOSED_FORMAT).to_string() {
+            return Ok(Some(closed_at));
+        }
+        parse_closed_at(&self.closed_date, created_at).map(Some)
+    }
+
+    /// First reason the form can't be saved, if any.
+    pub fn validation_error(&self) -> Option<&'static str> {
+        if self.subject.trim().is_empty() {
+            return Some("Subject required");
+        }
+        if parse_due_date(&self.due_date).is_err() {
+            return Some("Due date must be YYYY-MM-DD, today, tomorrow, +3d or a weekday");
+        }
+        match self.closed_at_value() {
+            Err(ClosedAtError::Invalid) => Some("Closed time must be YYYY-MM-DD HH:MM:SS"),
+            Err(ClosedAtError::BeforeCreated) => Some("Closed time is before creation"),
+            Ok(_) => None,
+        }
+    }
+
     pub fn is_valid(&self) -> bool {
-        !self.subject.trim().is_empty()
+        self.validation_error().is_none()
+    }
+}
+
+/// Word-wraps a line to `width` columns the way `Paragraph` does, keeping
+/// the style of its first span. Words wider than a row are split. With
+/// `trim`, rows lose their leading whitespace (see `text_wrap`).
+pub fn wrap_line(line: &Line, width: u16, trim: bool) -> Vec<Line<'static>> {
+    let style = line.spans.first().map(|span| span.style).unwrap_or_default();
+    let text: String = line.spans.iter().map(|span| span.content.as_ref()).collect();
+    let width = usize::from(width.max(1));
+
+    let mut rows = Vec::new();
+    let mut row = String::new();
+    let mut row_width = 0;
+    for word in text.split_inclusive(' ') {
+        if row_width + text_width(word.trim_end()) > width && !row.trim().is_empty() {
+            rows.push(std::mem::take(&mut row));
+            row_width = 0;
+        }
+        for c in word.chars() {
+            let char_width = text_width(c.encode_utf8(&mut [0; 4]));
+            if row_width + char_width > width && c != ' ' && !row.is_empty() {
+                rows.push(std::mem::take(&mut row));
+                row_width = 0;
+            }
+            row.push(c);
+            row_width += char_width;
+        }
+    }
+    rows.push(row);
+
+    rows.into_iter()
+        .map(|row| {
+            let row = if trim { row.trim() } else { row.trim_end() };
+            Line::from(Span::styled(row.to_string(), style))
+        })
+        .collect()
+}
+
+fn text_width(text: &str) -> usize {
+    Span::raw(text).width()
+}
+
+/// Replaces tabs with spaces up to the next multiple of `width` columns,
+/// counting from the start of each line, so tabbed text lines up as it
+/// would in an editor.
+pub fn expand_tabs(text: &str, width: usize) -> String {
+    let width = width.max(1);
+    let mut expanded = String::with_capacity(text.len());
+    let mut column = 0;
+    for c in text.chars() {
+        match c {
+            '\t' => {
+                let spaces = width - column % width;
+                expanded.extend(std::iter::repeat_n(' ', spaces));
+                column += spaces;
+            }
+            '\n' => {
+                expanded.push(c);
+                column = 0;
+            }
+            c => {
+                expanded.push(c);
+                column += text_width(c.encode_utf8(&mut [0; 4]));
+            }
+        }
+    }
+    expanded
+}
+
+/// Splits text into paragraphs at blank lines. Single line breaks inside a
+/// paragraph are joined with a space so the text reflows to the width,
+/// while each paragraph break is kept as one empty line. With
+/// `keep_indentation`, every line is kept as written instead, leading
+/// whitespace included, so indented code keeps its shape.
+pub fn paragraph_lines(text: &str, keep_indentation: bool) -> Vec<Line<'_>> {
+    if keep_indentation {
+        return text.trim_matches('\n').lines().map(|line| Line::from(line.trim_end())).collect();
+    }
+    let mut lines = Vec::new();
+    for (index, paragraph) in text.split("\n\n").enumerate() {
+        if index > 0 {
+            lines.push(Line::from(""));
+        }
+        let joined: Vec<&str> = paragraph.lines().map(str::trim).filter(|line| !line.is_empty()).collect();
+        if !joined.is_empty() {
+            lines.push(Line::from(joined.join(" ")));
+        }
+    }
+    lines
+}
+
+/// A part of a description: the text under one markdown `#` heading, or
+/// the text before the first heading.
+#[derive(Debug, PartialEq)]
+pub struct Section<'a> {
+    pub heading: Option<&'a str>,
+    pub body: &'a str,
+}
+
+/// Splits a description at markdown headings (lines starting with `#`s and
+/// a space). Text before the first heading becomes a section without one,
+/// and is left out when blank.
+pub fn description_sections(text: &str) -> Vec<Section<'_>> {
+    let mut sections = Vec::new();
+    let mut heading = None;
+    let mut body_start = 0;
+    let mut offset = 0;
+    for line in text.split_inclusive('\n') {
+        if let Some(title) = heading_text(line.trim_end()) {
+            push_section(&mut sections, heading, &text[body_start..offset]);
+            heading = Some(title);
+            body_start = offset + line.len();
+        }
+        offset += line.len();
+    }
+    push_section(&mut sections, heading, &text[body_start..]);
+    sections
+}
+
+fn push_section<'a>(sections: &mut Vec<Section<'a>>, heading: Option<&'a str>, body: &'a str) {
+    if heading.is_some() || !body.trim().is_empty() {
+        sections.push(Section {
+            heading,
+            body: body.trim_matches('\n'),
+        });
     }
 }
 
+fn heading_text(line: &str) -> Option<&str> {
+    let rest = line.trim_start_matches('#');
+    let is_heading = rest.len() < line.len() && (rest.is_empty() || rest.starts_with(' '));
+    is_heading.then(|| rest.trim())
+}
+
+/// Height of a bordered block showing `lines` lines of text.
+pub fn block_height(lines: usize) -> u16 {
+    lines as u16 + 2
+}
+
+/// Most subject lines shown before the rest is clipped, so a very long
+/// subject can't squeeze out the description.
+const MAX_SUBJECT_LINES: u16 = 3;
+
+/// Indices of the subtasks listed in view mode: at most
+/// `MAX_SUBTASK_LINES`, scrolled so that `selected` is among them.
+fn subtask_window(len: usize, selected: usize) -> std::ops::Range<usize> {
+    let start = (selected + 1).saturating_sub(MAX_SUBTASK_LINES).min(len.saturating_sub(MAX_SUBTASK_LINES));
+    start..len.min(start + MAX_SUBTASK_LINES)
+}
+
+/// Height of the bordered subject block for a popup `width` columns wide:
+/// one line per wrapped row of text, up to `MAX_SUBJECT_LINES`.
+pub fn subject_height(subject: &str, width: u16) -> u16 {
+    let inner_width = width.saturating_sub(2).max(1) as usize;
+    let lines = subject.chars().count().div_ceil(inner_width).max(1);
+    lines.min(MAX_SUBJECT_LINES as usize) as u16 + 2
+}
+
+pub(crate) fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
+    let popup_layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Percentage((100 - percent_y) / 2),
+            Constraint::Percentage(percent_y),
+            Constraint::Percentage((100 - percent_y) / 2),
+        ])
+        .split(r);
+
+    Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Percentage((100 - percent_x) / 2),
+            Constraint::Percentage(percent_x),
+            Constraint::Percentage((100 - percent_x) / 2),
+        ])
+        .split(popup_layout[1])[1]
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::data::Subtask;
 
     fn create_test_todo() -> Todo {
         Todo::new("Test Subject".to_string(), "Test Description".to_string())
     }
 
+    #[test]
+    fn test_title_marks_unsaved_changes() {
+        let mut detail_view = DetailView::new_for_editing(&create_test_todo());
+        assert_eq!(detail_view.title(), "Edit Todo");
+
+        detail_view.add_char('!');
+        assert_eq!(detail_view.title(), "Edit Todo *");
+
+        detail_view.mark_saved();
+        assert_eq!(detail_view.title(), "Edit Todo");
+
+        // Undoing the edit by hand also clears it
+        detail_view.add_char('?');
+        detail_view.delete_char();
+        assert_eq!(detail_view.title(), "Edit Todo");
+    }
+
+    #[test]
+    fn test_wrap_line() {
+        let rows = |text: &str, width: u16, trim: bool| -> Vec<String> {
+            wrap_line(&Line::from(text), width, trim).iter().map(|line| line.spans[0].content.to_string()).collect()
+        };
+        assert_eq!(rows("the quick brown fox", 10, true), ["the quick", "brown fox"]);
+        assert_eq!(rows("abcdefghij", 4, true), ["abcd", "efgh", "ij"]);
+        assert_eq!(rows("    indented text", 14, false), ["    indented", "text"]);
+        assert_eq!(rows("    indented", 20, true), ["indented"]);
+        assert_eq!(rows("", 10, true), [""]);
+    }
+
+    #[test]
+    fn test_expand_tabs_to_tab_stops() {
+        assert_eq!(expand_tabs("\tx", 4), "    x");
+        assert_eq!(expand_tabs("ab\tc", 4), "ab  c");
+        assert_eq!(expand_tabs("abcd\te", 4), "abcd    e");
+        assert_eq!(expand_tabs("a\tb\tc", 8), "a       b       c");
+        assert_eq!(expand_tabs("x\n\ty", 2), "x\n  y");
+        assert_eq!(expand_tabs("no tabs", 4), "no tabs");
+    }
+
+    #[test]
+    fn test_wrapped_description_cached_until_inputs_change() {
+        let mut todo = create_test_todo();
+        todo.description = "A fairly long description that wraps".to_string();
+        let mut view = DetailView::new_for_viewing(&todo);
+
+        let first = view.wrapped_description(20, false);
+        assert!(Rc::ptr_eq(&first, &view.wrapped_description(20, false)));
+
+        let narrower = view.wrapped_description(10, false);
+        assert!(!Rc::ptr_eq(&first, &narrower));
+        assert!(narrower.len() > first.len());
+
+        view.description.push_str(" and more");
+        assert!(!Rc::ptr_eq(&narrower, &view.wrapped_description(10, false)));
+    }
+
+    #[test]
+    fn test_text_wrap_follows_keep_indentation() {
+        assert_eq!(text_wrap(false), Wrap { trim: true });
+        assert_eq!(text_wrap(true), Wrap { trim: false });
+    }
+
     #[test]
     fn test_detail_view_creation_for_viewing() {
         let todo = create_test_todo();
@@ -290,11 +1259,43 @@ mod tests {
         detail_view.next_field();
         assert_eq!(detail_view.current_field, 1);
         
+        // Move to notes field
+        detail_view.next_field();
+        assert_eq!(detail_view.current_field, 2);
+        
+        // Move to due date field
+        detail_view.next_field();
+        assert_eq!(detail_view.current_field, 3);
+        
+        // Move to list field
+        detail_view.next_field();
+        assert_eq!(detail_view.current_field, 4);
+        
+        // Move to tags field
+        detail_view.next_field();
+        assert_eq!(detail_view.current_field, 5);
+        
         // Wrap around to field 0
         detail_view.next_field();
         assert_eq!(detail_view.current_field, 0);
         
-        // Move to previous field (should wrap to field 1)
+        // Move to previous field (should wrap to field 5)
+        detail_view.previous_field();
+        assert_eq!(detail_view.current_field, 5);
+        
+        // Move to previous field
+        detail_view.previous_field();
+        assert_eq!(detail_view.current_field, 4);
+        
+        // Move to previous field
+        detail_view.previous_field();
+        assert_eq!(detail_view.current_field, 3);
+        
+        // Move to previous field
+        detail_view.previous_field();
+        assert_eq!(detail_view.current_field, 2);
+        
+        // Move to previous field
         detail_view.previous_field();
         assert_eq!(detail_view.current_field, 1);
         
@@ -325,6 +1326,81 @@ mod tests {
         assert_eq!(detail_view.subject, "Hi");
     }
 
+    #[test]
+    fn test_notes_edited_separately_from_description() {
+        let mut detail_view = DetailView::new_for_editing(&create_test_todo());
+
+        detail_view.current_field = NOTES_FIELD;
+        assert!(detail_view.accepts_newlines());
+        for c in "Call back\nMonday".chars() {
+            detail_view.add_char(c);
+        }
+        detail_view.delete_char();
+
+        assert_eq!(detail_view.notes, "Call back\nMonda");
+        assert_eq!(detail_view.description, "Test Description");
+
+        detail_view.current_field = DUE_FIELD;
+        assert!(!detail_view.accepts_newlines());
+    }
+
+    #[test]
+    fn test_notes_section_collapses() {
+        let mut detail_view = DetailView::new_for_viewing(&create_test_todo());
+        assert!(!detail_view.notes_open());
+
+        detail_view.toggle_notes();
+        assert!(detail_view.notes_open());
+        assert!(detail_view.notes_height() > 3);
+
+        // Focusing the notes while editing always opens them
+        let mut detail_view = DetailView::new_for_editing(&create_test_todo());
+        detail_view.current_field = NOTES_FIELD;
+        assert!(detail_view.notes_open());
+    }
+
+    #[test]
+    fn test_description_sections() {
+        let text = "Intro line\n\n# Steps\nOne\nTwo\n\n## Notes\n#hashtag stays text\n";
+        assert_eq!(
+            description_sections(text),
+            vec![
+                Section { heading: None, body: "Intro line" },
+                Section { heading: Some("Steps"), body: "One\nTwo" },
+                Section { heading: Some("Notes"), body: "#hashtag stays text" },
+            ]
+        );
+        assert_eq!(
+            description_sections("# Only\n"),
+            vec![Section { heading: Some("Only"), body: "" }]
+        );
+        assert_eq!(
+            description_sections("No headings"),
+            vec![Section { heading: None, body: "No headings" }]
+        );
+        assert!(description_sections("").is_empty());
+    }
+
+    #[test]
+    fn test_sections_start_expanded_and_toggle() {
+        let mut todo = create_test_todo();
+        todo.description = "# First\nA\n# Second\nB".to_string();
+        let mut view = DetailView::new_for_viewing(&todo);
+        assert!(!view.is_section_collapsed(0));
+        assert!(!view.is_section_collapsed(1));
+
+        view.next_heading();
+        view.toggle_section();
+        assert!(!view.is_section_collapsed(0));
+        assert!(view.is_section_collapsed(1));
+
+        // Selection stops at the last heading
+        view.next_heading();
+        assert_eq!(view.selected_heading, 1);
+        view.toggle_section();
+        assert!(view.collapsed_sections.is_empty());
+    }
+
     #[test]
     fn test_delete_char() {
         let mut detail_view = DetailView::new_for_creation();
@@ -380,24 +1456,232 @@ mod tests {
         assert!(detail_view.closed_at.is_some());
         assert_eq!(detail_view.closed_at, todo.closed_at);
     }
-}
 
-fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
-    let popup_layout = Layout::default()
-        .direction(Direction::Vertical)
-        .constraints([
-            Constraint::Percentage((100 - percent_y) / 2),
-            Constraint::Percentage(percent_y),
-            Constraint::Percentage((100 - percent_y) / 2),
-        ])
-        .split(r);
+    #[test]
+    fn test_parse_due_date() {
+        assert_eq!(parse_due_date("").unwrap(), None);
+        assert_eq!(parse_due_date("   ").unwrap(), None);
 
-    Layout::default()
-        .direction(Direction::Horizontal)
-        .constraints([
-            Constraint::Percentage((100 - percent_x) / 2),
-            Constraint::Percentage(percent_x),
-            Constraint::Percentage((100 - percent_x) / 2),
-        ])
-        .split(popup_layout[1])[1]
-}
\ No newline at end of file
+        let due = parse_due_date("2024-03-15").unwrap().unwrap();
+        assert_eq!(due.format("%Y-%m-%d %H:%M:%S").to_string(), "2024-03-15 23:59:59");
+
+        assert!(parse_due_date("15/03/2024").is_err());
+        assert!(parse_due_date("tomorrow").unwrap().is_some());
+        assert!(parse_due_date("whenever").is_err());
+    }
+
+    #[test]
+    fn test_due_date_round_trip() {
+        let mut todo = create_test_todo();
+        todo.due_date = parse_due_date("2024-03-15").unwrap();
+
+        let mut detail_view = DetailView::new_for_editing(&todo);
+        assert_eq!(detail_view.due_date, "2024-03-15");
+        assert_eq!(detail_view.due_date_value(), todo.due_date);
+
+        detail_view.due_date = "not a date".to_string();
+        assert!(!detail_view.is_valid());
+    }
+
+    #[test]
+    fn test_list_value() {
+        let mut detail_view = DetailView::new_for_creation();
+        assert_eq!(detail_view.list_value(), DEFAULT_LIST);
+
+        detail_view.list = "  Work ".to_string();
+        assert_eq!(detail_view.list_value(), "Work");
+
+        detail_view.list = "   ".to_string();
+        assert_eq!(detail_view.list_value(), DEFAULT_LIST);
+    }
+
+    #[test]
+    fn test_parse_closed_at() {
+        let created = NaiveDateTime::parse_from_str("2024-03-10 09:30:00", CLOSED_FORMAT).unwrap().and_utc();
+
+        let closed = parse_closed_at("2024-03-12 17:00:00", created).unwrap();
+        assert_eq!(closed.format(CLOSED_FORMAT).to_string(), "2024-03-12 17:00:00");
+        assert!(parse_closed_at("2024-03-10 09:30:00", created).is_ok());
+
+        assert_eq!(parse_closed_at("2024-03-09 23:59:59", created), Err(ClosedAtError::BeforeCreated));
+        assert_eq!(parse_closed_at("yesterday", created), Err(ClosedAtError::Invalid));
+    }
+
+    #[test]
+    fn test_closed_field_only_for_completed_todos() {
+        let todo = create_test_todo();
+        let mut detail_view = DetailView::new_for_editing(&todo);
+        detail_view.current_field = TAGS_FIELD;
+        detail_view.next_field();
+        assert_eq!(detail_view.current_field, 0);
+
+        let mut completed = create_test_todo();
+        completed.toggle_completion();
+        let mut detail_view = DetailView::new_for_editing(&completed);
+        detail_view.current_field = TAGS_FIELD;
+        detail_view.next_field();
+        assert_eq!(detail_view.current_field, CLOSED_FIELD);
+    }
+
+    #[test]
+    fn test_closed_at_value() {
+        let mut todo = create_test_todo();
+        todo.toggle_completion();
+        let mut detail_view = DetailView::new_for_editing(&todo);
+
+        // Unchanged field keeps the exact stored timestamp
+        assert_eq!(detail_view.closed_at_value(), Ok(todo.closed_at));
+
+        detail_view.closed_date = "2000-01-01 00:00:00".to_string();
+        assert_eq!(detail_view.closed_at_value(), Err(ClosedAtError::BeforeCreated));
+        assert!(!detail_view.is_valid());
+    }
+
+    #[test]
+    fn test_paragraph_lines() {
+        let text = |lines: Vec<Line>| {
+            lines
+                .iter()
+                .map(|line| line.spans.iter().map(|span| span.content.as_ref()).collect::<String>())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            text(paragraph_lines("First line\nsame paragraph\n\nSecond paragraph", false)),
+            vec!["First line same paragraph", "", "Second paragraph"]
+        );
+        assert_eq!(text(paragraph_lines("One\n\n\n\nTwo", false)), vec!["One", "", "", "Two"]);
+        assert!(paragraph_lines("", false).is_empty());
+        assert_eq!(
+            text(paragraph_lines("\nfn main() {\n    run();\n}\n", true)),
+            vec!["fn main() {", "    run();", "}"]
+        );
+    }
+
+    #[test]
+    fn test_view_mode_keeps_indented_code() {
+        let mut todo = create_test_todo();
+        todo.description = "Steps:\n\n    cargo build\n    if ok {\n        ship();\n    }".to_string();
+        let view = DetailView::new_for_viewing(&todo);
+        let rows = |keep_indentation| {
+            view.wrapped_description(40, keep_indentation)
+                .iter()
+                .map(|line| line.spans.iter().map(|span| span.content.as_ref()).collect::<String>())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(rows(true), ["Steps:", "", "    cargo build", "    if ok {", "        ship();", "    }"]);
+        assert_eq!(rows(false), ["Steps:", "", "cargo build if ok { ship(); }"]);
+    }
+
+    #[test]
+    fn test_view_mode_keeps_leading_tabs() {
+        let mut todo = create_test_todo();
+        todo.description = "Columns:\n\n\tname\tvalue\n\t\tnested".to_string();
+        let mut view = DetailView::new_for_viewing(&todo);
+        view.tab_width = 4;
+        let rows: Vec<String> = view
+            .wrapped_description(40, true)
+            .iter()
+            .map(|line| line.spans.iter().map(|span| span.content.as_ref()).collect())
+            .collect();
+
+        assert_eq!(rows, ["Columns:", "", "    name    value", "        nested"]);
+    }
+
+    #[test]
+    fn test_metadata_height_follows_content() {
+        let new = DetailView::new_for_creation();
+        assert_eq!(new.metadata_lines().len(), 1);
+        assert_eq!(block_height(new.metadata_lines().len()), 3);
+
+        let mut todo = create_test_todo();
+        todo.attachments.push(PathBuf::from("notes.txt"));
+        todo.toggle_completion();
+        let completed = DetailView::new_for_viewing(&todo);
+        // Created, modified, status, closed and attachments
+        assert_eq!(completed.metadata_lines().len(), 5);
+        assert_eq!(block_height(completed.metadata_lines().len()), 7);
+    }
+
+    #[test]
+    fn test_subtask_metadata_line() {
+        let line_text = |line: &Line| line.spans.iter().map(|span| span.content.as_ref()).collect::<String>();
+        let mut todo = create_test_todo();
+        let view = DetailView::new_for_viewing(&todo);
+        assert!(!view.metadata_lines().iter().any(|line| line_text(line).starts_with("Subtasks")));
+
+        todo.subtasks = ["Pack", "Book", "Go", "Unpack", "Rest"].map(|subject| Subtask::new(subject.to_string())).into();
+        todo.subtasks[0].done = true;
+        todo.subtasks[3].done = true;
+        let view = DetailView::new_for_viewing(&todo);
+        let lines: Vec<String> = view.metadata_lines().iter().map(line_text).collect();
+        assert!(lines.contains(&format!("Subtasks: 2/5 done {}", progress_bar_string(2, 5, SUBTASK_BAR_WIDTH))));
+    }
+
+    #[test]
+    fn test_long_subtask_list_scrolls_with_selection() {
+        let line_text = |line: &Line| line.spans.iter().map(|span| span.content.as_ref()).collect::<String>();
+        let mut todo = create_test_todo();
+        todo.subtasks = (1..=40).map(|n| Subtask::new(format!("Step {}", n))).collect();
+        let mut view = DetailView::new_for_viewing(&todo);
+        let few = DetailView::new_for_viewing(&create_test_todo()).metadata_lines().len();
+        assert_eq!(view.metadata_lines().len(), few + 1 + MAX_SUBTASK_LINES);
+
+        view.selected_subtask = 29;
+        let lines: Vec<String> = view.metadata_lines().iter().map(line_text).collect();
+        assert!(lines.iter().any(|line| line.ends_with(" 26-30 of 40")), "{:?}", lines);
+        assert!(lines.contains(&"  ☐ Step 30".to_string()));
+        assert!(!lines.contains(&"  ☐ Step 31".to_string()));
+
+        assert_eq!(subtask_window(3, 2), 0..3);
+        assert_eq!(subtask_window(40, 0), 0..5);
+        assert_eq!(subtask_window(40, 39), 35..40);
+    }
+
+    #[test]
+    fn test_subject_height() {
+        // 20 columns wide leaves 18 for text inside the borders
+        assert_eq!(subject_height("", 20), 3);
+        assert_eq!(subject_height("Short subject", 20), 3);
+        assert_eq!(subject_height(&"x".repeat(18), 20), 3);
+        assert_eq!(subject_height(&"x".repeat(19), 20), 4);
+        assert_eq!(subject_height(&"x".repeat(40), 20), 5);
+        assert_eq!(subject_height(&"x".repeat(500), 20), MAX_SUBJECT_LINES + 2);
+    }
+
+    #[test]
+    fn test_validation_error() {
+        let mut detail_view = DetailView::new_for_creation();
+        detail_view.description = "Long notes".to_string();
+        assert_eq!(detail_view.validation_error(), Some("Subject required"));
+
+        detail_view.subject = "   ".to_string();
+        assert_eq!(detail_view.validation_error(), Some("Subject required"));
+
+        detail_view.subject = "Subject".to_string();
+        assert_eq!(detail_view.validation_error(), None);
+
+        detail_view.due_date = "soon".to_string();
+        assert_eq!(
+            detail_view.validation_error(),
+            Some("Due date must be YYYY-MM-DD, today, tomorrow, +3d or a weekday")
+        );
+    }
+
+    #[test]
+    fn test_accept_tag_suggestion() {
+        let mut detail_view = DetailView::new_for_creation();
+        detail_view.known_tags = vec![("work".to_string(), 3), ("writing".to_string(), 1)];
+        detail_view.current_field = TAGS_FIELD;
+
+        detail_view.tags = "home, w".to_string();
+        assert_eq!(detail_view.tag_suggestions(), vec!["work", "writing"]);
+        assert!(detail_view.accept_tag_suggestion());
+        assert_eq!(detail_view.tags, "home, work");
+
+        // Nothing left to complete once the tag is whole
+        assert!(!detail_view.accept_tag_suggestion());
+        assert_eq!(detail_view.tags_value(), vec!["home", "work"]);
+    }
+}
@@ -1,3 +1,4 @@
+use crate::data::{ConfirmText, PopupSize};
 use crate::ui::theme::TokyoNightTheme;
 use ratatui::{
     layout::{Constraint, Direction, Layout, Rect},
@@ -17,8 +18,10 @@ impl ConfirmDialog {
         Self { title, message }
     }
 
-    pub fn render(&self, frame: &mut Frame, area: Rect) {
-        let popup_area = centered_rect(50, 30, area);
+    /// Draws the dialog with the question and button labels from `text`.
+    pub fn render(&self, frame: &mut Frame, area: Rect, size: PopupSize, text: &ConfirmText) {
+        let size = size.clamped();
+        let popup_area = centered_rect(size.width_percent, size.height_percent, area);
         
         // Clear the background
         frame.render_widget(Clear, popup_area);
@@ -35,7 +38,7 @@ impl ConfirmDialog {
         let message_lines = vec![
             Line::from(Span::styled(&self.message, TokyoNightTheme::default())),
             Line::from(""),
-            Line::from(Span::styled("Are you sure?", TokyoNightTheme::warning().add_modifier(Modifier::BOLD))),
+            Line::from(Span::styled(text.question(), TokyoNightTheme::warning().add_modifier(Modifier::BOLD))),
         ];
 
         let message = Paragraph::new(message_lines)
@@ -53,11 +56,11 @@ impl ConfirmDialog {
             Line::from(vec![
                 Span::styled("⚠️  ", TokyoNightTheme::warning()),
                 Span::styled("y", TokyoNightTheme::error()),
-                Span::styled("=Yes  ", TokyoNightTheme::default()),
+                Span::styled(format!("={}  ", text.yes()), TokyoNightTheme::default()),
                 Span::styled("n", TokyoNightTheme::success()),
                 Span::styled("/", TokyoNightTheme::default()),
                 Span::styled("Esc", TokyoNightTheme::success()),
-                Span::styled("=No", TokyoNightTheme::default()),
+                Span::styled(format!("={}", text.no()), TokyoNightTheme::default()),
             ]),
         ];
 
@@ -71,6 +74,75 @@ impl ConfirmDialog {
     }
 }
 
+/// Single-line text prompt, e.g. for a due date applied to several todos.
+pub struct InputDialog {
+    pub title: String,
+    pub prompt: String,
+    pub value: String,
+    /// Why the last submitted value was rejected.
+    pub error: Option<String>,
+}
+
+impl InputDialog {
+    pub fn new(title: String, prompt: String) -> Self {
+        Self {
+            title,
+            prompt,
+            value: String::new(),
+            error: None,
+        }
+    }
+
+    pub fn render(&self, frame: &mut Frame, area: Rect, size: PopupSize) {
+        let size = size.clamped();
+        let popup_area = centered_rect(size.width_percent, size.height_percent, area);
+        frame.render_widget(Clear, popup_area);
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Min(3),     // Prompt and value
+                Constraint::Length(3),  // Controls
+            ])
+            .split(popup_area);
+
+        let mut lines = vec![
+            Line::from(Span::styled(&self.prompt, TokyoNightTheme::default())),
+            Line::from(vec![
+                Span::styled("> ", TokyoNightTheme::accent()),
+                Span::styled(&self.value, TokyoNightTheme::default()),
+                Span::styled("▏", TokyoNightTheme::active()),
+            ]),
+        ];
+        if let Some(error) = &self.error {
+            lines.push(Line::from(Span::styled(format!("⚠ {}", error), TokyoNightTheme::error())));
+        }
+
+        let input = Paragraph::new(lines)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_style(TokyoNightTheme::border())
+                    .title(self.title.as_str())
+                    .title_style(TokyoNightTheme::accent().add_modifier(Modifier::BOLD)),
+            );
+        frame.render_widget(input, chunks[0]);
+
+        let controls = Paragraph::new(Line::from(vec![
+            Span::styled("Enter", TokyoNightTheme::success()),
+            Span::styled("=Apply  ", TokyoNightTheme::default()),
+            Span::styled("Esc", TokyoNightTheme::warning()),
+            Span::styled("=Cancel", TokyoNightTheme::default()),
+        ]))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(TokyoNightTheme::border()),
+        );
+        frame.render_widget(controls, chunks[1]);
+    }
+}
+
 fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
     let popup_layout = Layout::default()
         .direction(Direction::Vertical)
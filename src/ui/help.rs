@@ -0,0 +1,183 @@
+use crate::app::AppState;
+use crate::ui::detail_view::centered_rect;
+use crate::ui::theme::TokyoNightTheme;
+use ratatui::{
+    layout::Rect,
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph},
+    Frame,
+};
+
+/// Keys available in each state with a short description. This is the one
+/// place both the footer and the help overlay read from, so keep it in step
+/// with the handlers in `events.rs`.
+pub fn commands_for(state: &AppState) -> Vec<(&'static str, &'static str)> {
+    match state {
+        AppState::Main => vec![
+            ("?", "Help"),
+            ("Enter", "View"),
+            ("e", "Edit"),
+            ("n", "New"),
+            ("M", "Mark as Template"),
+            ("N", "New from Template"),
+            ("d", "Toggle"),
+            ("Space", "Mark"),
+            ("Esc", "Clear Marks / Completion View"),
+            ("x", "Delete"),
+            ("C", "Clear Completed"),
+            ("D", "Due Date for Shown"),
+            ("F", "Complete All Shown"),
+            ("f", "Focus 25 min"),
+            ("p", "Priority"),
+            ("P", "Priority Filter"),
+            ("K", "Keep Priority Filter"),
+            ("i", "Flip Active/Completed"),
+            ("o", "Overdue"),
+            ("]", "Next Overdue"),
+            ("/", "Search"),
+            ("s", "Sort"),
+            ("S", "Sort Direction"),
+            ("Tab/→", "Next Tab"),
+            ("Shift+Tab/←", "Previous Tab"),
+            ("L", "Next List"),
+            ("I", "Short Ids"),
+            ("A", "Age Column"),
+            ("c", "Compact"),
+            ("z", "Fold Completed"),
+            ("R", "Recently Completed"),
+            ("T", "Theme Colors"),
+            ("y", "Copy as Markdown"),
+            ("+/-", "Goal"),
+            ("j/k ↑/↓", "Move"),
+            ("q/Ctrl+C", "Quit"),
+        ],
+        AppState::Detail => vec![
+            ("e", "Edit (view)"),
+            ("o", "Open Attachment (view)"),
+            ("N", "Expand/Collapse Notes (view)"),
+            ("r", "Reset Future Creation Time (view)"),
+            ("j/k ↑/↓", "Select Heading (view)"),
+            ("Enter", "Fold Section (view)"),
+            ("Tab/Shift+Tab", "Select Subtask (view)"),
+            ("Space", "Tick Subtask (view)"),
+            ("Shift+↑/↓", "Move Subtask (view)"),
+            ("Tab/Shift+Tab", "Switch Field (edit)"),
+            ("Ctrl+S", "Save (edit)"),
+            ("Enter", "Save from Subject (edit)"),
+            ("Ctrl+D", "Save & Close (edit)"),
+            ("Ctrl+P", "Priority (edit)"),
+            ("Ctrl+N", "Jump to Notes / Description (edit)"),
+            ("Esc", "Back / Discard Changes"),
+            ("Ctrl+C", "Quit (asks about unsaved changes)"),
+        ],
+        AppState::Search => vec![
+            ("Enter", "Apply"),
+            ("Esc", "Clear"),
+            ("Ctrl+T", "Case"),
+        ],
+        AppState::Confirm => vec![
+            ("y", "Yes"),
+            ("n/Esc", "No"),
+        ],
+        AppState::Input => vec![
+            ("Enter", "Apply"),
+            ("Esc", "Cancel"),
+        ],
+        AppState::Help | AppState::ThemePreview | AppState::RecentlyCompleted => vec![("any key", "Close")],
+    }
+}
+
+/// Every context with keys of its own, with a heading for the cheat sheet.
+const CONTEXTS: [(&str, AppState); 6] = [
+    ("Main view", AppState::Main),
+    ("Detail view", AppState::Detail),
+    ("Search", AppState::Search),
+    ("Confirmation", AppState::Confirm),
+    ("Input", AppState::Input),
+    ("Overlays", AppState::Help),
+];
+
+/// Plain-text list of every key grouped by context, for `todocli keys`.
+pub fn cheat_sheet() -> String {
+    let mut sheet = String::new();
+    for (index, (heading, state)) in CONTEXTS.iter().enumerate() {
+        if index > 0 {
+            sheet.push('\n');
+        }
+        sheet.push_str(heading);
+        sheet.push('\n');
+        for (key, description) in commands_for(state) {
+            sheet.push_str(&format!("  {:<14}{}\n", key, description));
+        }
+    }
+    sheet
+}
+
+/// Renders commands as `key=Description` spans for a footer line.
+pub fn command_spans(commands: &[(&'static str, &'static str)]) -> Vec<Span<'static>> {
+    commands
+        .iter()
+        .flat_map(|(key, description)| {
+            [
+                Span::styled(*key, TokyoNightTheme::active()),
+                Span::styled(format!("={}  ", description), TokyoNightTheme::default()),
+            ]
+        })
+        .collect()
+}
+
+/// Popup listing every main-view key, one per line.
+pub fn render_help(frame: &mut Frame, area: Rect) {
+    let popup_area = centered_rect(60, 80, area);
+    frame.render_widget(Clear, popup_area);
+
+    let lines: Vec<Line> = commands_for(&AppState::Main)
+        .into_iter()
+        .map(|(key, description)| {
+            Line::from(vec![
+                Span::styled(format!("{:>12}  ", key), TokyoNightTheme::active()),
+                Span::styled(description, TokyoNightTheme::default()),
+            ])
+        })
+        .collect();
+
+    let help = Paragraph::new(lines)
+        .style(TokyoNightTheme::default())
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(TokyoNightTheme::border())
+                .title("Keys (any key to close)")
+                .title_style(TokyoNightTheme::accent()),
+        );
+    frame.render_widget(help, popup_area);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cheat_sheet_lists_every_main_command() {
+        let sheet = cheat_sheet();
+        let main_section = sheet.split("\n\n").next().unwrap();
+        assert!(main_section.starts_with("Main view\n"));
+        for (key, description) in commands_for(&AppState::Main) {
+            assert!(
+                main_section.contains(&format!("  {:<14}{}", key, description)),
+                "{} missing from cheat sheet",
+                key
+            );
+        }
+        assert!(sheet.contains("Detail view\n"));
+        assert!(sheet.contains("Ctrl+S"));
+    }
+
+    #[test]
+    fn test_command_spans() {
+        let spans = command_spans(&[("q", "Quit")]);
+        assert_eq!(spans.len(), 2);
+        assert_eq!(spans[0].content, "q");
+        assert_eq!(spans[1].content, "=Quit  ");
+    }
+}
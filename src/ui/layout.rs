@@ -0,0 +1,65 @@
+use crate::ui::theme::TokyoNightTheme;
+use ratatui::{
+    layout::{Alignment, Rect},
+    style::Modifier,
+    text::{Line, Span},
+    widgets::Paragraph,
+    Frame,
+};
+
+pub const MIN_WIDTH: u16 = 40;
+pub const MIN_HEIGHT: u16 = 10;
+
+/// Whether `area` is too small to lay out the UI without widgets overlapping.
+pub fn is_too_small(area: Rect) -> bool {
+    area.width < MIN_WIDTH || area.height < MIN_HEIGHT
+}
+
+/// Replaces the whole UI with a short notice until the terminal is resized.
+pub fn render_too_small(frame: &mut Frame, area: Rect) {
+    let lines = vec![
+        Line::from(Span::styled(
+            "Terminal too small",
+            TokyoNightTheme::warning().add_modifier(Modifier::BOLD),
+        )),
+        Line::from(Span::styled(
+            format!("Need {}x{}, have {}x{}", MIN_WIDTH, MIN_HEIGHT, area.width, area.height),
+            TokyoNightTheme::default(),
+        )),
+    ];
+
+    // Vertically centre the two lines when there is room
+    let top = area.height.saturating_sub(lines.len() as u16) / 2;
+    let message_area = Rect {
+        y: area.y + top,
+        height: area.height - top,
+        ..area
+    };
+
+    let message = Paragraph::new(lines)
+        .alignment(Alignment::Center)
+        .style(TokyoNightTheme::default());
+    frame.render_widget(message, message_area);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn area(width: u16, height: u16) -> Rect {
+        Rect::new(0, 0, width, height)
+    }
+
+    #[test]
+    fn test_is_too_small_at_threshold() {
+        assert!(!is_too_small(area(MIN_WIDTH, MIN_HEIGHT)));
+        assert!(!is_too_small(area(MIN_WIDTH + 1, MIN_HEIGHT + 1)));
+    }
+
+    #[test]
+    fn test_is_too_small_below_threshold() {
+        assert!(is_too_small(area(MIN_WIDTH - 1, MIN_HEIGHT)));
+        assert!(is_too_small(area(MIN_WIDTH, MIN_HEIGHT - 1)));
+        assert!(is_too_small(area(0, 0)));
+    }
+}
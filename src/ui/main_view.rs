@@ -1,15 +1,52 @@
-use crate::data::Todo;
+use crate::data::{ListTab, Priority, PriorityIcons, Todo, TodoFilter};
+use crate::app::AppState;
+use crate::ui::help::{command_spans, commands_for};
 use crate::ui::theme::TokyoNightTheme;
+use std::collections::HashSet;
 use ratatui::{
-    layout::{Constraint, Direction, Layout, Rect},
-    style::Modifier,
+    layout::{Alignment, Constraint, Direction, Layout, Margin, Rect},
+    style::{Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, Paragraph, Row, Table, Cell, TableState},
+    widgets::{
+        Block, Borders, Cell, Gauge, Paragraph, Row, Scrollbar, ScrollbarOrientation, ScrollbarState, Table,
+        TableState, Tabs,
+    },
     Frame,
 };
 
+/// What the footer shows for the current frame.
+pub enum Footer<'a> {
+    Controls,
+    /// Typing a search; carries the text typed so far.
+    Searching(&'a str),
+    Status(&'a str),
+    Error(&'a str),
+}
+
 pub struct MainView {
     pub table_state: TableState,
+    /// Show the first characters of each todo's id in their own column.
+    pub show_short_ids: bool,
+    /// Show how long each todo has been open, or took to complete.
+    pub show_durations: bool,
+    /// Leave out the blank line under the column headers.
+    pub compact: bool,
+    /// Number of completed todos folded into a summary row, when folded.
+    pub collapsed_completed: Option<usize>,
+    /// Ids of todos marked for actions on several todos at once.
+    pub marked: HashSet<String>,
+    /// Countdown of a running focus session, shown in the header.
+    pub focus_label: Option<String>,
+    /// Style of the header and list titles, colored per list.
+    pub accent: Style,
+    /// Icons drawn at the start of each row to show priority.
+    pub priority_icons: PriorityIcons,
+}
+
+impl Default for MainView {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl MainView {
@@ -19,10 +56,26 @@ impl MainView {
         
         Self {
             table_state,
+            show_short_ids: false,
+            show_durations: false,
+            compact: false,
+            collapsed_completed: None,
+            marked: HashSet::new(),
+            focus_label: None,
+            accent: TokyoNightTheme::accent(),
+            priority_icons: PriorityIcons::default(),
         }
     }
 
-    pub fn render(&mut self, frame: &mut Frame, area: Rect, todos: &[&Todo]) {
+    pub fn render(
+        &mut self,
+        frame: &mut Frame,
+        area: Rect,
+        todos: &[&Todo],
+        filter: &TodoFilter,
+        daily_progress: Option<(usize, usize)>,
+        footer: Footer,
+    ) {
         let chunks = Layout::default()
             .direction(Direction::Vertical)
             .constraints([
@@ -32,91 +85,175 @@ impl MainView {
             ])
             .split(area);
 
-        // Header
-        let header = Paragraph::new("📝 TodoCLI - Terminal Todo Manager")
-            .style(TokyoNightTheme::accent().add_modifier(Modifier::BOLD))
+        // Header with list tabs
+        let tab_titles: Vec<&str> = ListTab::ALL.iter().map(|tab| tab.title()).collect();
+        let header = Tabs::new(tab_titles)
+            .select(filter.tab.index())
+            .style(TokyoNightTheme::default())
+            .highlight_style(TokyoNightTheme::accent().add_modifier(Modifier::BOLD | Modifier::UNDERLINED))
+            .divider(Span::styled("|", TokyoNightTheme::border()))
             .block(
                 Block::default()
                     .borders(Borders::ALL)
                     .border_style(TokyoNightTheme::border())
-                    .title("TodoCLI")
-                    .title_style(TokyoNightTheme::accent()),
+                    .title(format!("📝 TodoCLI - {}", filter.list.as_deref().unwrap_or("All Lists")))
+                    .title_style(self.accent),
             );
         frame.render_widget(header, chunks[0]);
 
+        if let Some(label) = &self.focus_label {
+            let focus = Paragraph::new(label.as_str())
+                .style(TokyoNightTheme::warning())
+                .alignment(Alignment::Right);
+            frame.render_widget(focus, focus_area(chunks[0]));
+        }
+
+        if let Some((done, goal)) = daily_progress {
+            let gauge_area = goal_gauge_area(chunks[0]);
+            let gauge = Gauge::default()
+                .gauge_style(TokyoNightTheme::success())
+                .ratio(goal_ratio(done, goal))
+                .label(format!("🎯 {}/{} today", done, goal));
+            frame.render_widget(gauge, gauge_area);
+        }
+
         // Todo table with columns
-        let rows: Vec<Row> = todos
+        let now = chrono::Utc::now();
+        let mut rows: Vec<Row> = todos
             .iter()
-            .enumerate()
-            .map(|(_i, todo)| {
-                let style = if todo.is_completed() {
-                    TokyoNightTheme::completed()
-                } else {
-                    TokyoNightTheme::default()
-                };
+            .map(|todo| {
+                let style = row_style(todo);
+                let (status_icon, status_style) = status_cell(todo);
 
-                let status_icon = if todo.is_completed() {
-                    "🔴"
-                } else {
-                    todo.status_icon()
+                let subject = match (self.marked.contains(&todo.id), todo.is_template) {
+                    (true, true) => format!("● ⧉ {}", todo.subject),
+                    (true, false) => format!("● {}", todo.subject),
+                    (false, true) => format!("⧉ {}", todo.subject),
+                    (false, false) => todo.subject.clone(),
                 };
-
-                let subject = &todo.subject;
                 let last_modified = todo.last_modified_at.format("%Y-%m-%d %H:%M").to_string();
 
-                Row::new(vec![
-                    Cell::from(status_icon).style(style),
-                    Cell::from(subject.as_str()).style(style),
+                let mut cells = vec![
+                    Cell::from(Line::from(vec![
+                        priority_glyph(todo.priority, &self.priority_icons),
+                        Span::styled(status_icon, status_style),
+                    ])),
+                    Cell::from(subject).style(style),
                     Cell::from(last_modified).style(style),
-                ])
+                ];
+                if self.show_short_ids {
+                    cells.insert(1, Cell::from(todo.short_id()).style(TokyoNightTheme::border()));
+                }
+                if self.show_durations {
+                    cells.push(Cell::from(todo.duration_label(now)).style(TokyoNightTheme::border()));
+                }
+                Row::new(cells)
             })
             .collect();
 
-        let table = Table::new(
-            rows,
-            [
-                Constraint::Length(3),      // Status icon column
-                Constraint::Min(20),        // Subject column (flexible)
-                Constraint::Length(16),     // Last modified column
-            ]
-        )
+        // Rows below the separator are shifted by one, so the selection is
+        // mapped onto a scratch state for rendering
+        let mut render_state = self.table_state.clone();
+        if let Some(count) = self.collapsed_completed {
+            // Folded todos aren't in `todos`, so only a summary row is added
+            rows.push(self.separator_row(format!("▸ Completed ({})", count)));
+        } else if let Some(boundary) = completed_boundary(todos) {
+            rows.insert(boundary, self.separator_row("— Completed —".to_string()));
+            if let Some(selected) = self.table_state.selected().filter(|&i| i >= boundary) {
+                render_state.select(Some(selected + 1));
+            }
+        }
+
+        let mut list_title = if filter.overdue_only {
+            format!("⏰ Overdue {} Todos", filter.tab.title())
+        } else {
+            format!("📝 {} Todos", filter.tab.title())
+        };
+        // Marks outlive filters, so the count includes hidden todos
+        if !self.marked.is_empty() {
+            list_title.push_str(&format!(" · {} marked", self.marked.len()));
+        }
+
+        let mut widths = vec![
+            Constraint::Length(priority_icons_width(&self.priority_icons) + 2), // Priority and status icon column
+            Constraint::Min(20),        // Subject column (flexible)
+            Constraint::Length(16),     // Last modified column
+        ];
+        let mut header_cells = vec![
+            Cell::from("📋"),
+            Cell::from("Subject"),
+            Cell::from("Last Modified"),
+        ];
+        if self.show_short_ids {
+            widths.insert(1, Constraint::Length(10)); // Short id column
+            header_cells.insert(1, Cell::from("Id"));
+        }
+        if self.show_durations {
+            widths.push(Constraint::Length(6)); // Age or time to complete
+            header_cells.push(Cell::from("Age"));
+        }
+
+        let total_rows = table_row_count(todos, self.collapsed_completed);
+        let table = Table::new(rows, widths)
         .header(
-            Row::new(vec![
-                Cell::from("📋"),
-                Cell::from("Subject"),
-                Cell::from("Last Modified"),
-            ])
+            Row::new(header_cells)
             .style(TokyoNightTheme::accent().add_modifier(Modifier::BOLD))
-            .bottom_margin(1)
+            .bottom_margin(header_margin(self.compact))
         )
         .block(
             Block::default()
                 .borders(Borders::ALL)
                 .border_style(TokyoNightTheme::border())
-                .title("📝 All Todos")
-                .title_style(TokyoNightTheme::accent()),
+                .title(list_title)
+                .title_style(self.accent),
         )
         .highlight_style(TokyoNightTheme::selected())
         .highlight_symbol("▶ ");
 
-        frame.render_stateful_widget(table, chunks[1], &mut self.table_state);
+        frame.render_stateful_widget(table, chunks[1], &mut render_state);
+        *self.table_state.offset_mut() = render_state.offset();
+
+        // Drawn over the right border; borders and header take three lines
+        // plus the header margin
+        let visible_rows = usize::from(chunks[1].height.saturating_sub(3 + header_margin(self.compact)));
+        if let Some(mut scrollbar_state) = list_scrollbar_state(total_rows, visible_rows, render_state.offset()) {
+            let scrollbar = Scrollbar::new(ScrollbarOrientation::VerticalRight)
+                .begin_symbol(None)
+                .end_symbol(None)
+                .style(TokyoNightTheme::border());
+            frame.render_stateful_widget(
+                scrollbar,
+                chunks[1].inner(&Margin { vertical: 1, horizontal: 0 }),
+                &mut scrollbar_state,
+            );
+        }
 
         // Footer with controls
-        let footer_text = vec![
-            Line::from(vec![
-                Span::styled("💡 Controls: ", TokyoNightTheme::accent()),
-                Span::styled("Enter", TokyoNightTheme::active()),
-                Span::styled("=View/Edit  ", TokyoNightTheme::default()),
-                Span::styled("d", TokyoNightTheme::active()),
-                Span::styled("=Toggle  ", TokyoNightTheme::default()),
-                Span::styled("n", TokyoNightTheme::active()),
-                Span::styled("=New  ", TokyoNightTheme::default()),
-                Span::styled("x", TokyoNightTheme::error()),
-                Span::styled("=Delete  ", TokyoNightTheme::default()),
-                Span::styled("q", TokyoNightTheme::warning()),
-                Span::styled("=Quit", TokyoNightTheme::default()),
-            ]),
-        ];
+        let footer_spans = if let Footer::Status(message) = footer {
+            vec![Span::styled(format!("✔ {}", message), TokyoNightTheme::success())]
+        } else if let Footer::Error(message) = footer {
+            vec![Span::styled(format!("⚠ {}", message), TokyoNightTheme::error())]
+        } else if let Footer::Searching(input) = footer {
+            search_bar_spans(filter, Some(input))
+        } else if !filter.query.is_empty() {
+            search_bar_spans(filter, None)
+        } else {
+            let mut spans = vec![Span::styled("💡 ", TokyoNightTheme::accent())];
+            spans.extend(command_spans(&commands_for(&AppState::Main)));
+
+            if filter.overdue_only {
+                spans.push(Span::styled("  [Overdue only]", TokyoNightTheme::warning()));
+            }
+            if let Some(priority) = filter.priority {
+                spans.push(Span::styled(format!("  [{} priority]", priority.label()), TokyoNightTheme::warning()));
+            }
+            if let Some(view) = filter.completion {
+                spans.push(Span::styled(format!("  [{}]", view.label()), TokyoNightTheme::warning()));
+            }
+            spans
+        };
+
+        let footer_text = vec![Line::from(footer_spans)];
 
         let footer = Paragraph::new(footer_text)
             .style(TokyoNightTheme::default())
@@ -159,6 +296,199 @@ impl MainView {
     pub fn selected_index(&self) -> Option<usize> {
         self.table_state.selected()
     }
+
+    fn separator_row(&self, label: String) -> Row<'static> {
+        let mut cells = vec![
+            Cell::from(""),
+            Cell::from(label),
+            Cell::from(""),
+        ];
+        if self.show_short_ids {
+            cells.insert(1, Cell::from(""));
+        }
+        if self.show_durations {
+            cells.push(Cell::from(""));
+        }
+        Row::new(cells)
+        .style(TokyoNightTheme::border().add_modifier(Modifier::DIM))
+    }
+
+    pub fn toggle_mark(&mut self, id: &str) {
+        if !self.marked.remove(id) {
+            self.marked.insert(id.to_string());
+        }
+    }
+
+    /// Moves the selection to the next todo below it that matches `pred`,
+    /// wrapping around to the top. Returns whether one was found; the
+    /// selection is left alone otherwise.
+    pub fn select_next_matching(&mut self, pred: impl Fn(&Todo) -> bool, todos: &[&Todo]) -> bool {
+        let start = self.table_state.selected().map_or(0, |index| index + 1);
+        let found = (0..todos.len())
+            .map(|step| (start + step) % todos.len())
+            .find(|&index| pred(todos[index]));
+        if let Some(index) = found {
+            self.table_state.select(Some(index));
+        }
+        found.is_some()
+    }
+
+    /// Selects the todo with `id`, falling back to the first row when it
+    /// isn't in `todos`. Returns whether the id was found.
+    pub fn select_by_id(&mut self, id: &str, todos: &[&Todo]) -> bool {
+        match todos.iter().position(|todo| todo.id == id) {
+            Some(index) => {
+                self.table_state.select(Some(index));
+                true
+            }
+            None => {
+                self.table_state.select(Some(0));
+                false
+            }
+        }
+    }
+}
+
+/// Index of the first completed todo when the list holds both active and
+/// completed todos. Relies on the list being sorted active-first.
+pub fn completed_boundary(todos: &[&Todo]) -> Option<usize> {
+    let boundary = todos.iter().position(|todo| todo.is_completed())?;
+    if boundary == 0 {
+        None
+    } else {
+        Some(boundary)
+    }
+}
+
+/// Rows the table shows for `todos`: one per todo, plus the completed
+/// separator or the summary row standing in for folded completed todos.
+pub fn table_row_count(todos: &[&Todo], collapsed_completed: Option<usize>) -> usize {
+    let extra = collapsed_completed.is_some() || completed_boundary(todos).is_some();
+    todos.len() + usize::from(extra)
+}
+
+/// Blank lines between the column headers and the first todo.
+pub fn header_margin(compact: bool) -> u16 {
+    if compact {
+        0
+    } else {
+        1
+    }
+}
+
+/// Scrollbar position for a list of `total_rows` showing `visible_rows`
+/// from `offset`; `None` when everything fits.
+pub fn list_scrollbar_state(total_rows: usize, visible_rows: usize, offset: usize) -> Option<ScrollbarState> {
+    if total_rows <= visible_rows {
+        return None;
+    }
+    Some(
+        ScrollbarState::new(total_rows)
+            .position(offset)
+            .viewport_content_length(visible_rows),
+    )
+}
+
+/// Row colour: green once completed, red while overdue, orange when due
+/// soon.
+pub fn row_style(todo: &Todo) -> Style {
+    if todo.is_completed() {
+        TokyoNightTheme::success()
+    } else if todo.is_overdue() {
+        TokyoNightTheme::error()
+    } else if todo.is_due_soon(chrono::Utc::now()) {
+        TokyoNightTheme::warning()
+    } else {
+        TokyoNightTheme::default()
+    }
+}
+
+/// Icon and style for the status column.
+pub fn status_cell(todo: &Todo) -> (&'static str, Style) {
+    (todo.status_icon(), row_style(todo))
+}
+
+/// Colored icon drawn at the start of each row to show priority.
+pub fn priority_glyph(priority: Priority, icons: &PriorityIcons) -> Span<'static> {
+    let color = match priority {
+        Priority::High => TokyoNightTheme::ERROR,
+        Priority::Medium => TokyoNightTheme::WARNING,
+        Priority::Low => TokyoNightTheme::COMMENT,
+    };
+    Span::styled(icons.icon(priority).to_string(), TokyoNightTheme::fg(color))
+}
+
+/// Columns taken by the widest priority icon, so emoji icons fit.
+fn priority_icons_width(icons: &PriorityIcons) -> u16 {
+    [Priority::High, Priority::Medium, Priority::Low]
+        .into_iter()
+        .map(|priority| Span::raw(icons.icon(priority)).width())
+        .max()
+        .unwrap_or(1) as u16
+}
+
+/// Footer contents while typing a search (`input` is the text typed so
+/// far), or while a search is applied.
+fn search_bar_spans(filter: &TodoFilter, input: Option<&str>) -> Vec<Span<'static>> {
+    let searching = input.is_some();
+    let case_indicator = if filter.case_sensitive { "[Aa]" } else { "[aa]" };
+    let mut spans = vec![
+        Span::styled("🔍 /", TokyoNightTheme::accent()),
+        Span::styled(input.unwrap_or(&filter.query).to_string(), TokyoNightTheme::default()),
+    ];
+    if searching {
+        spans.push(Span::styled("▏", TokyoNightTheme::active()));
+    }
+    spans.push(Span::styled(format!("  {}", case_indicator), TokyoNightTheme::warning()));
+    if searching {
+        spans.push(Span::styled("  Ctrl+T", TokyoNightTheme::active()));
+        spans.push(Span::styled("=Case  ", TokyoNightTheme::default()));
+        spans.push(Span::styled("Enter", TokyoNightTheme::active()));
+        spans.push(Span::styled("=Apply  ", TokyoNightTheme::default()));
+        spans.push(Span::styled("Esc", TokyoNightTheme::warning()));
+        spans.push(Span::styled("=Clear", TokyoNightTheme::default()));
+    } else {
+        spans.push(Span::styled("  /", TokyoNightTheme::active()));
+        spans.push(Span::styled("=Edit search", TokyoNightTheme::default()));
+    }
+    spans
+}
+
+/// Fraction of the daily goal reached, capped at 1.0 once the goal is met.
+pub fn goal_ratio(done: usize, goal: usize) -> f64 {
+    if goal == 0 {
+        return 1.0;
+    }
+    (done as f64 / goal as f64).min(1.0)
+}
+
+/// A text progress bar `width` cells wide, e.g. `███░░` for 3 of 5.
+pub fn progress_bar_string(done: usize, total: usize, width: usize) -> String {
+    let filled = (goal_ratio(done, total) * width as f64).round() as usize;
+    format!("{}{}", "█".repeat(filled), "░".repeat(width - filled))
+}
+
+/// Right-hand slice of the header's inner row where the goal gauge sits.
+fn goal_gauge_area(header: Rect) -> Rect {
+    let width = 24.min(header.width.saturating_sub(2));
+    Rect {
+        x: header.x + header.width.saturating_sub(width + 1),
+        y: header.y + 1,
+        width,
+        height: 1.min(header.height.saturating_sub(2)),
+    }
+}
+
+/// Right end of the header's top border, where the focus countdown sits
+/// clear of the goal gauge.
+fn focus_area(header: Rect) -> Rect {
+    let width = 40.min(header.width.saturating_sub(2));
+    Rect {
+        x: header.x + header.width.saturating_sub(width + 1),
+        y: header.y,
+        width,
+        height: 1.min(header.height),
+    }
 }
 
 #[cfg(test)]
@@ -171,6 +501,25 @@ mod tests {
         assert_eq!(main_view.selected_index(), Some(0));
     }
 
+    #[test]
+    fn test_compact_drops_header_margin() {
+        assert_eq!(header_margin(false), 1);
+        assert_eq!(header_margin(true), 0);
+    }
+
+    #[test]
+    fn test_list_scrollbar_state() {
+        assert_eq!(list_scrollbar_state(10, 10, 0), None);
+        assert_eq!(
+            list_scrollbar_state(50, 10, 20),
+            Some(ScrollbarState::new(50).position(20).viewport_content_length(10))
+        );
+        assert_eq!(
+            list_scrollbar_state(11, 10, 0),
+            Some(ScrollbarState::new(11).position(0).viewport_content_length(10))
+        );
+    }
+
     #[test]
     fn test_navigation_next() {
         let mut main_view = MainView::new();
@@ -250,4 +599,109 @@ mod tests {
         main_view.table_state.select(None);
         assert_eq!(main_view.selected_index(), None);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_goal_ratio() {
+        assert_eq!(goal_ratio(0, 5), 0.0);
+        assert_eq!(goal_ratio(2, 4), 0.5);
+        assert_eq!(goal_ratio(7, 5), 1.0);
+        assert_eq!(goal_ratio(0, 0), 1.0);
+    }
+
+    #[test]
+    fn test_progress_bar_string() {
+        assert_eq!(progress_bar_string(3, 5, 5), "███░░");
+        assert_eq!(progress_bar_string(0, 4, 4), "░░░░");
+        assert_eq!(progress_bar_string(9, 4, 4), "████");
+        assert_eq!(progress_bar_string(1, 3, 6), "██░░░░");
+    }
+
+    #[test]
+    fn test_select_by_id() {
+        let first = Todo::new("First".to_string(), String::new());
+        let second = Todo::new("Second".to_string(), String::new());
+        let todos = vec![&first, &second];
+        let mut main_view = MainView::new();
+
+        assert!(main_view.select_by_id(&second.id, &todos));
+        assert_eq!(main_view.selected_index(), Some(1));
+
+        assert!(!main_view.select_by_id("stale-id", &todos));
+        assert_eq!(main_view.selected_index(), Some(0));
+    }
+
+    #[test]
+    fn test_select_next_matching_wraps() {
+        let mut overdue_first = Todo::new("Overdue first".to_string(), String::new());
+        overdue_first.due_date = Some(chrono::Utc::now() - chrono::Duration::days(2));
+        let on_time = Todo::new("On time".to_string(), String::new());
+        let mut overdue_last = Todo::new("Overdue last".to_string(), String::new());
+        overdue_last.due_date = Some(chrono::Utc::now() - chrono::Duration::days(1));
+        let todos = vec![&overdue_first, &on_time, &overdue_last];
+        let mut main_view = MainView::new();
+
+        main_view.table_state.select(Some(0));
+        assert!(main_view.select_next_matching(Todo::is_overdue, &todos));
+        assert_eq!(main_view.selected_index(), Some(2));
+
+        // Past the last overdue todo it wraps to the first
+        assert!(main_view.select_next_matching(Todo::is_overdue, &todos));
+        assert_eq!(main_view.selected_index(), Some(0));
+
+        let todos = vec![&on_time];
+        assert!(!main_view.select_next_matching(Todo::is_overdue, &todos));
+        assert_eq!(main_view.selected_index(), Some(0));
+    }
+
+    #[test]
+    fn test_priority_glyph() {
+        for (priority, color) in [
+            (Priority::High, TokyoNightTheme::ERROR),
+            (Priority::Medium, TokyoNightTheme::WARNING),
+            (Priority::Low, TokyoNightTheme::COMMENT),
+        ] {
+            let glyph = priority_glyph(priority, &PriorityIcons::default());
+            assert_eq!(glyph.content, PriorityIcons::default().icon(priority));
+            assert_eq!(glyph.style, TokyoNightTheme::fg(color));
+        }
+
+        let icons = PriorityIcons { high: Some("🔥".to_string()), ..PriorityIcons::default() };
+        assert_eq!(priority_glyph(Priority::High, &icons).content, "🔥");
+        assert_eq!(priority_icons_width(&icons), 2);
+        assert_eq!(priority_icons_width(&PriorityIcons::default()), 1);
+    }
+
+    #[test]
+    fn test_status_cell() {
+        let mut todo = Todo::new("Test".to_string(), String::new());
+        assert_eq!(status_cell(&todo), ("📝", TokyoNightTheme::default()));
+
+        todo.due_date = Some(chrono::Utc::now() + chrono::Duration::hours(2));
+        assert_eq!(status_cell(&todo), ("📝", TokyoNightTheme::warning()));
+
+        todo.due_date = Some(chrono::Utc::now() - chrono::Duration::days(1));
+        assert_eq!(status_cell(&todo), ("📝", TokyoNightTheme::error()));
+
+        // Completed todos get the green check even when their due date passed
+        todo.toggle_completion();
+        assert_eq!(status_cell(&todo), ("✅", TokyoNightTheme::success()));
+    }
+
+    #[test]
+    fn test_completed_boundary() {
+        let active_a = Todo::new("Active A".to_string(), String::new());
+        let active_b = Todo::new("Active B".to_string(), String::new());
+        let mut done_a = Todo::new("Done A".to_string(), String::new());
+        done_a.toggle_completion();
+        let mut done_b = Todo::new("Done B".to_string(), String::new());
+        done_b.toggle_completion();
+
+        assert_eq!(completed_boundary(&[&active_a, &active_b, &done_a, &done_b]), Some(2));
+        assert_eq!(completed_boundary(&[&active_a, &done_a]), Some(1));
+
+        // No separator unless both groups are present
+        assert_eq!(completed_boundary(&[&active_a, &active_b]), None);
+        assert_eq!(completed_boundary(&[&done_a, &done_b]), None);
+        assert_eq!(completed_boundary(&[]), None);
+    }
+}
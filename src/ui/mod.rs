@@ -2,6 +2,10 @@ pub mod theme;
 pub mod main_view;
 pub mod detail_view;
 pub mod dialog;
+pub mod help;
+pub mod layout;
+pub mod recent;
+pub mod theme_preview;
 
 pub use main_view::*;
 pub use detail_view::*;
@@ -0,0 +1,64 @@
+use crate::data::Todo;
+use crate::ui::detail_view::centered_rect;
+use crate::ui::theme::TokyoNightTheme;
+use ratatui::{
+    layout::Rect,
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph},
+    Frame,
+};
+
+/// How many completed todos the recently completed view lists.
+pub const RECENT_LIMIT: usize = 20;
+
+/// One line per todo: when it was closed, then its subject.
+pub fn recent_lines(todos: &[&Todo]) -> Vec<Line<'static>> {
+    if todos.is_empty() {
+        return vec![Line::from(Span::styled("Nothing completed yet", TokyoNightTheme::border()))];
+    }
+    todos
+        .iter()
+        .map(|todo| {
+            let closed = todo
+                .closed_at
+                .map(|closed| closed.format("%Y-%m-%d %H:%M").to_string())
+                .unwrap_or_default();
+            Line::from(vec![
+                Span::styled(format!("{}  ", closed), TokyoNightTheme::completed()),
+                Span::styled(todo.subject.clone(), TokyoNightTheme::default()),
+            ])
+        })
+        .collect()
+}
+
+pub fn render_recently_completed(frame: &mut Frame, area: Rect, todos: &[&Todo]) {
+    let popup_area = centered_rect(60, 70, area);
+    frame.render_widget(Clear, popup_area);
+
+    let recent = Paragraph::new(recent_lines(todos))
+        .style(TokyoNightTheme::default())
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(TokyoNightTheme::border())
+                .title("Recently Completed (any key to close)")
+                .title_style(TokyoNightTheme::accent()),
+        );
+    frame.render_widget(recent, popup_area);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recent_lines() {
+        let mut todo = Todo::new("Ship release".to_string(), String::new());
+        todo.toggle_completion();
+        let lines = recent_lines(&[&todo]);
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0].spans[1].content, "Ship release");
+
+        assert_eq!(recent_lines(&[]).len(), 1);
+    }
+}
@@ -1,4 +1,7 @@
-use ratatui::style::{Color, Style};
+use ratatui::style::{Color, Modifier, Style};
+use std::collections::HashMap;
+use std::ffi::OsStr;
+use std::sync::OnceLock;
 
 pub struct TokyoNightTheme;
 
@@ -6,64 +9,340 @@ impl TokyoNightTheme {
     pub const BACKGROUND: Color = Color::Rgb(26, 27, 38);      // #1a1b26
     pub const FOREGROUND: Color = Color::Rgb(192, 202, 245);   // #c0caf5
     pub const ACTIVE: Color = Color::Rgb(122, 162, 247);       // #7aa2f7
-    pub const COMPLETED: Color = Color::Rgb(247, 118, 142);    // #f7768e (red)
+    pub const COMPLETED: Color = Color::Rgb(158, 206, 106);    // #9ece6a (green)
     pub const BORDER: Color = Color::Rgb(65, 72, 104);         // #414868
     pub const ACCENT: Color = Color::Rgb(187, 154, 247);       // #bb9af7
     pub const SUCCESS: Color = Color::Rgb(158, 206, 106);      // #9ece6a
     pub const WARNING: Color = Color::Rgb(255, 158, 100);      // #ff9e64
     pub const ERROR: Color = Color::Rgb(247, 118, 142);        // #f7768e
-    // pub const COMMENT: Color = Color::Rgb(86, 95, 137);        // #565f89
+    pub const COMMENT: Color = Color::Rgb(86, 95, 137);        // #565f89
 
+    /// Every theme color with its name, for the theme preview.
+    pub const COLORS: [(&'static str, Color); 10] = [
+        ("Background", Self::BACKGROUND),
+        ("Foreground", Self::FOREGROUND),
+        ("Active", Self::ACTIVE),
+        ("Completed", Self::COMPLETED),
+        ("Border", Self::BORDER),
+        ("Accent", Self::ACCENT),
+        ("Success", Self::SUCCESS),
+        ("Warning", Self::WARNING),
+        ("Error", Self::ERROR),
+        ("Comment", Self::COMMENT),
+    ];
+
+    // Named after the theme's default text style, not `Default::default`
+    #[allow(clippy::should_implement_trait)]
     pub fn default() -> Style {
-        Style::default()
-            .fg(Self::FOREGROUND)
-            .bg(Self::BACKGROUND)
+        Theme::current().default
     }
 
     pub fn active() -> Style {
-        Style::default()
-            .fg(Self::ACTIVE)
-            .bg(Self::BACKGROUND)
+        Theme::current().active
     }
 
     pub fn completed() -> Style {
-        Style::default()
-            .fg(Self::COMPLETED)
-            .bg(Self::BACKGROUND)
+        Theme::current().completed
     }
 
     pub fn border() -> Style {
-        Style::default()
-            .fg(Self::BORDER)
+        Theme::current().border
     }
 
     pub fn accent() -> Style {
-        Style::default()
-            .fg(Self::ACCENT)
-            .bg(Self::BACKGROUND)
+        Theme::current().accent
     }
 
     pub fn success() -> Style {
-        Style::default()
-            .fg(Self::SUCCESS)
-            .bg(Self::BACKGROUND)
+        Theme::current().success
     }
 
     pub fn warning() -> Style {
-        Style::default()
-            .fg(Self::WARNING)
-            .bg(Self::BACKGROUND)
+        Theme::current().warning
     }
 
     pub fn error() -> Style {
-        Style::default()
-            .fg(Self::ERROR)
-            .bg(Self::BACKGROUND)
+        Theme::current().error
     }
 
     pub fn selected() -> Style {
-        Style::default()
-            .fg(Self::BACKGROUND)
-            .bg(Self::ACTIVE)
+        Theme::current().selected
+    }
+
+    /// Plain foreground color, dropped when colors are disabled and
+    /// approximated on terminals without truecolor.
+    pub fn fg(color: Color) -> Style {
+        let theme = Theme::current();
+        if theme.colored {
+            Style::default().fg(theme.color(color))
+        } else {
+            Style::default()
+        }
+    }
+}
+
+static CURRENT: OnceLock<Theme> = OnceLock::new();
+
+/// The styles the UI draws with.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Theme {
+    pub default: Style,
+    pub active: Style,
+    pub completed: Style,
+    pub border: Style,
+    pub accent: Style,
+    pub success: Style,
+    pub warning: Style,
+    pub error: Style,
+    pub selected: Style,
+    colored: bool,
+    truecolor: bool,
+}
+
+impl Theme {
+    pub fn tokyo_night() -> Self {
+        let on_background = |color| Style::default().fg(color).bg(TokyoNightTheme::BACKGROUND);
+        Self {
+            default: on_background(TokyoNightTheme::FOREGROUND),
+            active: on_background(TokyoNightTheme::ACTIVE),
+            completed: on_background(TokyoNightTheme::COMPLETED),
+            border: Style::default().fg(TokyoNightTheme::BORDER),
+            accent: on_background(TokyoNightTheme::ACCENT),
+            success: on_background(TokyoNightTheme::SUCCESS),
+            warning: on_background(TokyoNightTheme::WARNING),
+            error: on_background(TokyoNightTheme::ERROR),
+            selected: Style::default()
+                .fg(TokyoNightTheme::BACKGROUND)
+                .bg(TokyoNightTheme::ACTIVE),
+            colored: true,
+            truecolor: true,
+        }
+    }
+
+    /// Uncolored styles for terminals where `NO_COLOR` is set. Emphasis
+    /// that would otherwise come from color uses modifiers instead.
+    pub fn monochrome() -> Self {
+        let plain = Style::default();
+        Self {
+            default: plain,
+            active: plain,
+            completed: plain.add_modifier(Modifier::DIM),
+            border: plain,
+            accent: plain.add_modifier(Modifier::BOLD),
+            success: plain,
+            warning: plain.add_modifier(Modifier::BOLD),
+            error: plain.add_modifier(Modifier::BOLD),
+            selected: plain.add_modifier(Modifier::REVERSED),
+            colored: false,
+            truecolor: true,
+        }
+    }
+
+    /// Monochrome when `NO_COLOR` is set to a non-empty value
+    /// (https://no-color.org), Tokyo Night otherwise.
+    pub fn for_no_color(no_color: Option<&OsStr>) -> Self {
+        match no_color {
+            Some(value) if !value.is_empty() => Self::monochrome(),
+            _ => Self::tokyo_night(),
+        }
+    }
+
+    /// The same styles with every RGB color swapped for its closest of the
+    /// 16 basic terminal colors (see `approximate_color`).
+    pub fn approximated(self) -> Self {
+        let approximate = |style: Style| Style {
+            fg: style.fg.map(approximate_color),
+            bg: style.bg.map(approximate_color),
+            ..style
+        };
+        Self {
+            default: approximate(self.default),
+            active: approximate(self.active),
+            completed: approximate(self.completed),
+            border: approximate(self.border),
+            accent: approximate(self.accent),
+            success: approximate(self.success),
+            warning: approximate(self.warning),
+            error: approximate(self.error),
+            selected: approximate(self.selected),
+            truecolor: false,
+            ..self
+        }
+    }
+
+    /// Picks the theme from the `NO_COLOR`, `COLORTERM` and `TERM`
+    /// environment values: monochrome when `NO_COLOR` is set, Tokyo Night
+    /// on truecolor terminals, and its 16-color approximation elsewhere.
+    pub fn detect(no_color: Option<&OsStr>, colorterm: Option<&OsStr>, term: Option<&OsStr>) -> Self {
+        let theme = Self::for_no_color(no_color);
+        if theme.colored && !supports_truecolor(colorterm, term) {
+            theme.approximated()
+        } else {
+            theme
+        }
+    }
+
+    /// `color` as this theme draws it: unchanged on truecolor terminals,
+    /// approximated otherwise.
+    pub fn color(&self, color: Color) -> Color {
+        if self.truecolor {
+            color
+        } else {
+            approximate_color(color)
+        }
+    }
+
+    /// Accent for the header and list titles while `list` is shown: its
+    /// color from `list_colors` (a name such as `"cyan"` or `"#7dcfff"`),
+    /// or the theme accent for other lists, unparsable colors and when
+    /// colors are off.
+    pub fn list_accent(&self, list: Option<&str>, list_colors: &HashMap<String, String>) -> Style {
+        let color = list
+            .and_then(|list| list_colors.get(list))
+            .and_then(|color| color.parse::<Color>().ok());
+        match color {
+            Some(color) if self.colored => self.accent.fg(self.color(color)),
+            _ => self.accent,
+        }
+    }
+
+    /// Theme used for drawing, picked from the environment on first use.
+    pub fn current() -> &'static Theme {
+        CURRENT.get_or_init(|| {
+            Self::detect(
+                std::env::var_os("NO_COLOR").as_deref(),
+                std::env::var_os("COLORTERM").as_deref(),
+                std::env::var_os("TERM").as_deref(),
+            )
+        })
+    }
+}
+
+/// Whether the terminal advertises 24-bit color, through `COLORTERM`
+/// (`truecolor` or `24bit`) or a `TERM` such as `xterm-direct`.
+pub fn supports_truecolor(colorterm: Option<&OsStr>, term: Option<&OsStr>) -> bool {
+    let colorterm = colorterm.and_then(OsStr::to_str).unwrap_or_default();
+    let term = term.and_then(OsStr::to_str).unwrap_or_default();
+    matches!(colorterm, "truecolor" | "24bit")
+        || ["truecolor", "24bit", "direct"].iter().any(|name| term.contains(name))
+}
+
+/// Closest of the 16 basic terminal colors to an RGB color; other colors
+/// are returned unchanged. Muted colors map to black, grays or white by
+/// lightness, the rest by hue, using the bright variant for light colors.
+pub fn approximate_color(color: Color) -> Color {
+    let Color::Rgb(r, g, b) = color else {
+        return color;
+    };
+    let (r, g, b) = (f32::from(r), f32::from(g), f32::from(b));
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let chroma = max - min;
+    let lightness = (max + min) / 2.0;
+
+    if chroma < 60.0 {
+        return match lightness {
+            l if l < 60.0 => Color::Black,
+            l if l < 140.0 => Color::DarkGray,
+            l if l < 200.0 => Color::Gray,
+            _ => Color::White,
+        };
+    }
+
+    let hue = if max == r {
+        (60.0 * (g - b) / chroma).rem_euclid(360.0)
+    } else if max == g {
+        60.0 * ((b - r) / chroma + 2.0)
+    } else {
+        60.0 * ((r - g) / chroma + 4.0)
+    };
+    let light = lightness > 150.0;
+    let pick = |dark: Color, bright: Color| if light { bright } else { dark };
+    match hue {
+        h if !(20.0..330.0).contains(&h) => pick(Color::Red, Color::LightRed),
+        h if h < 75.0 => pick(Color::Yellow, Color::LightYellow),
+        h if h < 150.0 => pick(Color::Green, Color::LightGreen),
+        h if h < 190.0 => pick(Color::Cyan, Color::LightCyan),
+        h if h < 255.0 => pick(Color::Blue, Color::LightBlue),
+        _ => pick(Color::Magenta, Color::LightMagenta),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_monochrome_has_no_colors() {
+        let theme = Theme::monochrome();
+        for style in [
+            theme.default,
+            theme.active,
+            theme.completed,
+            theme.border,
+            theme.accent,
+            theme.success,
+            theme.warning,
+            theme.error,
+            theme.selected,
+        ] {
+            assert_eq!(style.fg, None);
+            assert_eq!(style.bg, None);
+        }
+        assert!(theme.selected.add_modifier.contains(Modifier::REVERSED));
+    }
+
+    #[test]
+    fn test_list_accent() {
+        let colors = HashMap::from([
+            ("Work".to_string(), "#ff0000".to_string()),
+            ("Home".to_string(), "not a color".to_string()),
+        ]);
+        let theme = Theme::tokyo_night();
+        assert_eq!(theme.list_accent(Some("Work"), &colors).fg, Some(Color::Rgb(255, 0, 0)));
+        assert_eq!(theme.list_accent(Some("Unknown"), &colors), theme.accent);
+        assert_eq!(theme.list_accent(Some("Home"), &colors), theme.accent);
+        assert_eq!(theme.list_accent(None, &colors), theme.accent);
+
+        let monochrome = Theme::monochrome();
+        assert_eq!(monochrome.list_accent(Some("Work"), &colors), monochrome.accent);
+    }
+
+    #[test]
+    fn test_approximate_color() {
+        assert_eq!(approximate_color(Color::Rgb(0, 0, 0)), Color::Black);
+        assert_eq!(approximate_color(Color::Rgb(255, 255, 255)), Color::White);
+        assert_eq!(approximate_color(Color::Rgb(200, 0, 0)), Color::Red);
+        assert_eq!(approximate_color(Color::Rgb(0, 180, 0)), Color::Green);
+        assert_eq!(approximate_color(TokyoNightTheme::BACKGROUND), Color::Black);
+        assert_eq!(approximate_color(TokyoNightTheme::COMMENT), Color::DarkGray);
+        assert_eq!(approximate_color(TokyoNightTheme::ACTIVE), Color::LightBlue);
+        assert_eq!(approximate_color(TokyoNightTheme::COMPLETED), Color::LightGreen);
+        assert_eq!(approximate_color(TokyoNightTheme::ACCENT), Color::LightMagenta);
+        assert_eq!(approximate_color(TokyoNightTheme::WARNING), Color::LightYellow);
+        assert_eq!(approximate_color(TokyoNightTheme::ERROR), Color::LightRed);
+        assert_eq!(approximate_color(Color::Cyan), Color::Cyan);
+    }
+
+    #[test]
+    fn test_detect_downgrades_without_truecolor() {
+        let truecolor = Some(OsStr::new("truecolor"));
+        let xterm = Some(OsStr::new("xterm-256color"));
+        assert_eq!(Theme::detect(None, truecolor, xterm), Theme::tokyo_night());
+        assert_eq!(Theme::detect(None, None, Some(OsStr::new("xterm-direct"))), Theme::tokyo_night());
+
+        let approximated = Theme::detect(None, None, xterm);
+        assert_eq!(approximated, Theme::tokyo_night().approximated());
+        assert_eq!(approximated.active.fg, Some(Color::LightBlue));
+        assert_eq!(approximated.color(TokyoNightTheme::ERROR), Color::LightRed);
+
+        assert_eq!(Theme::detect(Some(OsStr::new("1")), None, xterm), Theme::monochrome());
+    }
+
+    #[test]
+    fn test_no_color_selects_monochrome() {
+        assert_eq!(Theme::for_no_color(Some(OsStr::new("1"))), Theme::monochrome());
+        assert_eq!(Theme::for_no_color(Some(OsStr::new(""))), Theme::tokyo_night());
+        assert_eq!(Theme::for_no_color(None), Theme::tokyo_night());
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,62 @@
+use crate::ui::detail_view::centered_rect;
+use crate::ui::theme::TokyoNightTheme;
+use ratatui::{
+    layout::Rect,
+    style::{Color, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph},
+    Frame,
+};
+
+/// One line per theme color: a swatch, the name, and the value.
+pub fn preview_lines() -> Vec<Line<'static>> {
+    TokyoNightTheme::COLORS
+        .iter()
+        .map(|(name, color)| {
+            Line::from(vec![
+                Span::styled("      ", Style::default().bg(*color)),
+                Span::styled(format!("  {:<12}", name), TokyoNightTheme::default()),
+                Span::styled(color_value(*color), TokyoNightTheme::border()),
+            ])
+        })
+        .collect()
+}
+
+fn color_value(color: Color) -> String {
+    match color {
+        Color::Rgb(r, g, b) => format!("#{:02x}{:02x}{:02x}", r, g, b),
+        other => format!("{:?}", other),
+    }
+}
+
+pub fn render_theme_preview(frame: &mut Frame, area: Rect) {
+    let popup_area = centered_rect(50, 60, area);
+    frame.render_widget(Clear, popup_area);
+
+    let preview = Paragraph::new(preview_lines())
+        .style(TokyoNightTheme::default())
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(TokyoNightTheme::border())
+                .title("Theme Colors (any key to close)")
+                .title_style(TokyoNightTheme::accent()),
+        );
+    frame.render_widget(preview, popup_area);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_one_line_per_color() {
+        let lines = preview_lines();
+        assert_eq!(lines.len(), TokyoNightTheme::COLORS.len());
+
+        let first = &lines[0];
+        assert_eq!(first.spans[0].style.bg, Some(TokyoNightTheme::BACKGROUND));
+        assert!(first.spans[1].content.contains("Background"));
+        assert_eq!(first.spans[2].content, "#1a1b26");
+    }
+}